@@ -0,0 +1,450 @@
+//! The BattlEye RCON protocol used by Arma and DayZ servers.
+//!
+//! Unlike Source RCON it runs over UDP: every packet carries a CRC32 of its
+//! contents, the client has to send something at least every 45 seconds to
+//! stay logged in, and the server pushes chat and log messages that have to
+//! be acknowledged. [`BeConnection`] handles all of that on background tasks
+//! once authenticated.
+//!
+//! ```no_run
+//! # async fn run() -> specul::Result<()> {
+//! use specul::battleye::BeConnection;
+//!
+//! let connection = BeConnection::connect("127.0.0.1:2306", "password").await?;
+//! let mut messages = connection.messages();
+//!
+//! println!("{}", connection.execute_command("players").await?);
+//!
+//! while let Ok(message) = messages.recv().await {
+//!     println!("{}", message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use derive_builder::Builder;
+use tokio::{
+    net::{ToSocketAddrs, UdpSocket},
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+};
+
+use crate::{Error, Result};
+
+const LOGIN: u8 = 0x00;
+const COMMAND: u8 = 0x01;
+const MESSAGE: u8 = 0x02;
+
+/// The largest datagram a server sends.
+const MAX_DATAGRAM: usize = 65_507;
+
+/// A logged-in connection to a BattlEye RCON server.
+///
+/// Built around a connected [`UdpSocket`] with [`BeConnectionBuilder`], or
+/// with [`BeConnection::connect`]. Until [`authenticate`](Self::authenticate)
+/// succeeds no background tasks run; afterwards a keep-alive is sent every
+/// `keepalive_interval`, server messages are acknowledged and published to
+/// [`messages`](Self::messages), and commands may be executed from several
+/// tasks at once. The tasks stop when the connection is dropped.
+#[derive(Debug, Builder)]
+#[builder(pattern = "owned", build_fn(validate = "Self::validate"))]
+pub struct BeConnection {
+    /// A socket connected to the server's RCON port.
+    #[builder(setter(into))]
+    socket: Arc<UdpSocket>,
+    /// How often an empty command is sent to keep the login alive. Servers
+    /// drop clients they have not heard from in 45 seconds.
+    #[builder(default = "Duration::from_secs(30)")]
+    keepalive_interval: Duration,
+    /// How long authentication or a command may wait for the server's
+    /// answer before failing with [`Error::Timeout`].
+    #[builder(default = "Duration::from_secs(5)")]
+    timeout: Duration,
+    /// How many server messages are buffered for slow subscribers.
+    #[builder(default = "64")]
+    message_capacity: usize,
+    #[builder(setter(skip))]
+    inner: Option<Arc<Inner>>,
+    #[builder(setter(skip))]
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl BeConnectionBuilder {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if self.keepalive_interval >= Some(Duration::from_secs(45)) {
+            return Err("keepalive_interval must be shorter than 45 seconds".to_string());
+        }
+
+        if self.message_capacity == Some(0) {
+            return Err("message_capacity must be positive".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+/// State shared with the background tasks.
+#[derive(Debug)]
+struct Inner {
+    commands: Mutex<Commands>,
+    messages: broadcast::Sender<String>,
+}
+
+#[derive(Debug, Default)]
+struct Commands {
+    next_sequence: u8,
+    pending: HashMap<u8, Pending>,
+    /// Counts the commands sent, telling apart those that reused a sequence.
+    issued: u64,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Pending {
+    command: u64,
+    parts: Vec<Option<Vec<u8>>>,
+    reply: oneshot::Sender<Result<String>>,
+}
+
+impl Commands {
+    /// Returns the next sequence number no command is waiting on, or `None`
+    /// if all 256 are.
+    fn next_sequence(&mut self) -> Option<u8> {
+        for _ in 0..=u8::MAX {
+            let sequence = self.next_sequence;
+            self.next_sequence = self.next_sequence.wrapping_add(1);
+
+            if !self.pending.contains_key(&sequence) {
+                return Some(sequence);
+            }
+        }
+
+        None
+    }
+
+    /// Forgets the command `command` if it is still waiting on `sequence`,
+    /// and not a later one that reused it.
+    fn abandon(&mut self, sequence: u8, command: u64) {
+        if self.pending.get(&sequence).map(|pending| pending.command) == Some(command) {
+            self.pending.remove(&sequence);
+        }
+    }
+}
+
+impl BeConnection {
+    /// Connects to the server at `addr` from an ephemeral port and logs in.
+    pub async fn connect<A: ToSocketAddrs>(addr: A, password: &str) -> Result<Self> {
+        let mut last_error = None;
+
+        for addr in tokio::net::lookup_host(addr).await? {
+            let local = if addr.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            let socket = UdpSocket::bind(local).await?;
+
+            match socket.connect(addr).await {
+                Ok(()) => {
+                    let mut connection = BeConnectionBuilder::default()
+                        .socket(socket)
+                        .build()
+                        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+                    connection.authenticate(password).await?;
+                    return Ok(connection);
+                }
+                Err(error) => last_error = Some(error),
+            }
+        }
+
+        Err(Error::Io(last_error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no addresses to connect to")
+        })))
+    }
+
+    /// Logs in and starts the background tasks.
+    ///
+    /// Fails with [`Error::Authentication`] if the password is rejected and
+    /// with [`Error::Timeout`] if the server does not answer within
+    /// `timeout`. Packets other than the login response are ignored until
+    /// then.
+    pub async fn authenticate(&mut self, password: &str) -> Result<()> {
+        self.socket
+            .send(&encode(LOGIN, &[], password.as_bytes()))
+            .await?;
+
+        let accepted = tokio::time::timeout(self.timeout, self.receive_login())
+            .await
+            .map_err(|_| Error::Timeout)??;
+
+        if !accepted {
+            return Err(Error::Authentication);
+        }
+
+        self.start();
+        Ok(())
+    }
+
+    /// Executes a command and returns the response, reassembled if the
+    /// server split it over several packets.
+    ///
+    /// Fails with [`Error::Authentication`] before logging in, with
+    /// [`Error::Timeout`] if the response does not arrive completely within
+    /// `timeout`, with [`Error::ConnectionClosed`] once the socket has
+    /// failed, and with [`Error::Protocol`] if 256 commands, as many as
+    /// there are sequence numbers, are already waiting for responses.
+    pub async fn execute_command(&self, command: &str) -> Result<String> {
+        let inner = self.inner.as_ref().ok_or(Error::Authentication)?;
+        let (reply, response) = oneshot::channel();
+
+        let (sequence, command_id) = {
+            let mut commands = inner.commands.lock().unwrap();
+
+            if commands.closed {
+                return Err(Error::ConnectionClosed);
+            }
+
+            let sequence = commands
+                .next_sequence()
+                .ok_or(Error::Protocol("every command sequence number is in use"))?;
+            commands.issued += 1;
+            let command_id = commands.issued;
+            commands.pending.insert(
+                sequence,
+                Pending {
+                    command: command_id,
+                    parts: Vec::new(),
+                    reply,
+                },
+            );
+            (sequence, command_id)
+        };
+
+        let result = async {
+            self.socket
+                .send(&encode(COMMAND, &[sequence], command.as_bytes()))
+                .await?;
+
+            response.await.unwrap_or(Err(Error::ConnectionClosed))
+        };
+
+        let result = tokio::time::timeout(self.timeout, result)
+            .await
+            .unwrap_or(Err(Error::Timeout));
+
+        if result.is_err() {
+            inner.commands.lock().unwrap().abandon(sequence, command_id);
+        }
+
+        result
+    }
+
+    /// Subscribes to the messages the server pushes, such as chat and
+    /// kick notices. Each message is delivered once, even if the server
+    /// resends it.
+    ///
+    /// Subscribing before logging in yields a receiver that is closed
+    /// straight away.
+    pub fn messages(&self) -> broadcast::Receiver<String> {
+        match &self.inner {
+            Some(inner) => inner.messages.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    async fn receive_login(&self) -> Result<bool> {
+        let mut datagram = vec![0; MAX_DATAGRAM];
+
+        loop {
+            let read = self.socket.recv(&mut datagram).await?;
+
+            if let Ok((LOGIN, body)) = decode(&datagram[..read]) {
+                return Ok(body.first() == Some(&0x01));
+            }
+        }
+    }
+
+    fn start(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+
+        let inner = Arc::new(Inner {
+            commands: Mutex::new(Commands::default()),
+            messages: broadcast::channel(self.message_capacity).0,
+        });
+
+        self.tasks
+            .push(tokio::spawn(receive(self.socket.clone(), inner.clone())));
+        self.tasks.push(tokio::spawn(keep_alive(
+            self.socket.clone(),
+            inner.clone(),
+            self.keepalive_interval,
+        )));
+
+        self.inner = Some(inner);
+    }
+}
+
+impl Drop for BeConnection {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Routes every datagram from the server until the socket fails.
+async fn receive(socket: Arc<UdpSocket>, inner: Arc<Inner>) {
+    let mut datagram = vec![0; MAX_DATAGRAM];
+    let mut last_message = None;
+
+    while let Ok(read) = socket.recv(&mut datagram).await {
+        let (kind, body) = match decode(&datagram[..read]) {
+            Ok(packet) => packet,
+            // A corrupted datagram is resent by the server if it mattered.
+            Err(_) => continue,
+        };
+
+        match (kind, body) {
+            (COMMAND, [sequence, rest @ ..]) => inner.complete(*sequence, rest),
+            (MESSAGE, [sequence, message @ ..]) => {
+                let _ = socket.send(&encode(MESSAGE, &[*sequence], &[])).await;
+
+                // Unacknowledged messages are resent with the same sequence.
+                if last_message != Some(*sequence) {
+                    last_message = Some(*sequence);
+                    let _ = inner
+                        .messages
+                        .send(String::from_utf8_lossy(message).into_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut commands = inner.commands.lock().unwrap();
+    commands.closed = true;
+
+    for (_, pending) in commands.pending.drain() {
+        let _ = pending.reply.send(Err(Error::ConnectionClosed));
+    }
+}
+
+/// Sends an empty command every `interval`, which the server answers and
+/// counts as activity.
+async fn keep_alive(socket: Arc<UdpSocket>, inner: Arc<Inner>, interval: Duration) {
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+
+    loop {
+        ticks.tick().await;
+
+        // With every sequence number waiting, the commands keep the
+        // connection alive.
+        let Some(sequence) = inner.commands.lock().unwrap().next_sequence() else {
+            continue;
+        };
+
+        if socket
+            .send(&encode(COMMAND, &[sequence], &[]))
+            .await
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+impl Inner {
+    /// Records a command response, replying once every part has arrived.
+    fn complete(&self, sequence: u8, body: &[u8]) {
+        let mut commands = self.commands.lock().unwrap();
+
+        let Some(pending) = commands.pending.get_mut(&sequence) else {
+            // The answer to a keep-alive, or to a command that timed out.
+            return;
+        };
+
+        match body {
+            // A part of a split response: 0x00, the part count and its index.
+            [0x00, count, index, part @ ..] if index < count => {
+                if pending.parts.len() != *count as usize {
+                    pending.parts = vec![None; *count as usize];
+                }
+
+                pending.parts[*index as usize] = Some(part.to_vec());
+
+                if pending.parts.iter().any(Option::is_none) {
+                    return;
+                }
+            }
+            _ => pending.parts = vec![Some(body.to_vec())],
+        }
+
+        if let Some(pending) = commands.pending.remove(&sequence) {
+            let bytes: Vec<u8> = pending.parts.into_iter().flatten().flatten().collect();
+            let _ = pending
+                .reply
+                .send(Ok(String::from_utf8_lossy(&bytes).into_owned()));
+        }
+    }
+}
+
+/// Frames a packet: `BE`, the CRC32 of everything after it, `0xFF`, the
+/// packet type, then the header bytes and body.
+fn encode(kind: u8, header: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut checked = Vec::with_capacity(2 + header.len() + body.len());
+    checked.push(0xFF);
+    checked.push(kind);
+    checked.extend_from_slice(header);
+    checked.extend_from_slice(body);
+
+    let mut packet = Vec::with_capacity(6 + checked.len());
+    packet.extend_from_slice(b"BE");
+    packet.extend_from_slice(&crc32(&checked).to_le_bytes());
+    packet.extend_from_slice(&checked);
+    packet
+}
+
+/// Checks a datagram's framing and checksum, returning its packet type and
+/// the bytes after it.
+fn decode(datagram: &[u8]) -> Result<(u8, &[u8])> {
+    match datagram {
+        [b'B', b'E', c0, c1, c2, c3, checked @ ..] if checked.len() >= 2 => {
+            if checked[0] != 0xFF {
+                return Err(Error::MalformedPacket("missing 0xFF after checksum"));
+            }
+
+            if crc32(checked) != u32::from_le_bytes([*c0, *c1, *c2, *c3]) {
+                return Err(Error::MalformedPacket("checksum mismatch"));
+            }
+
+            Ok((checked[1], &checked[2..]))
+        }
+        _ => Err(Error::MalformedPacket("not a BattlEye packet")),
+    }
+}
+
+/// The CRC-32 (IEEE) checksum BattlEye uses.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+
+    for &byte in bytes {
+        crc ^= byte as u32;
+
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+
+    !crc
+}
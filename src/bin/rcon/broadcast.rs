@@ -0,0 +1,143 @@
+//! Executing one command on several profiles' servers, as `rcon broadcast`.
+
+use std::{collections::BTreeMap, env, path::Path, sync::Arc, time::Duration};
+
+use specul::{
+    fleet::{Fleet, ServerConfig},
+    Error,
+};
+use tokio::task::JoinSet;
+
+use crate::{output::Format, profile};
+
+/// How each server did.
+enum Outcome {
+    Done(String),
+    Failed(Error),
+    /// Not waited for, after another server failed with `--fail-fast`.
+    Cancelled,
+}
+
+/// Executes `command` on every profile's server at once and reports each
+/// result, in the order the profiles were given.
+///
+/// Fails if any server did. With `fail_fast`, the servers still running when
+/// the first one fails are not waited for, and reported as cancelled.
+pub async fn broadcast(
+    config_path: &Path,
+    names: &[String],
+    password: Option<&str>,
+    command: &str,
+    format: Format,
+    fail_fast: bool,
+    timeout: Duration,
+) -> Result<(), String> {
+    let mut fleet = Fleet::new().timeout(timeout);
+    let mut addrs = BTreeMap::new();
+
+    for name in names {
+        let profile = profile::load(config_path, name)?;
+        let mut connection = profile
+            .connection
+            .ok_or_else(|| format!("profile {} has no host", name))?;
+        connection.password = match (password, &profile.password) {
+            (Some(password), _) => password.to_string(),
+            (None, Some(source)) => source.resolve()?,
+            (None, None) => env::var("RCON_PASSWORD")
+                .map_err(|_| format!("{}: no password in the profile or $RCON_PASSWORD", name))?,
+        };
+
+        // The profile's quirks, prefix and timeouts apply to its server too.
+        addrs.insert(name.as_str(), connection.addr());
+        fleet.insert(name.as_str(), ServerConfig::from_config(connection));
+    }
+
+    let fleet = Arc::new(fleet);
+    let mut tasks = JoinSet::new();
+
+    for name in names {
+        let fleet = fleet.clone();
+        let name = name.clone();
+        let command = command.to_string();
+
+        tasks.spawn(async move {
+            let result = fleet.execute_on(&[&name], &command).await.remove(&name);
+            (name, result.expect("the fleet reports every server"))
+        });
+    }
+
+    let mut outcomes: BTreeMap<String, Outcome> = BTreeMap::new();
+
+    while let Some(joined) = tasks.join_next().await {
+        let (name, result) = match joined {
+            Ok(joined) => joined,
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        };
+
+        let failed = result.is_err();
+        outcomes.insert(
+            name,
+            match result {
+                Ok(payloads) => Outcome::Done(payloads.concat()),
+                Err(error) => Outcome::Failed(error),
+            },
+        );
+
+        if failed && fail_fast {
+            tasks.abort_all();
+            break;
+        }
+    }
+
+    let mut failures = 0;
+
+    for name in names {
+        let outcome = outcomes.remove(name).unwrap_or(Outcome::Cancelled);
+
+        if !matches!(outcome, Outcome::Done(_)) {
+            failures += 1;
+        }
+
+        report(name, &addrs[name.as_str()], &outcome, format);
+    }
+
+    match failures {
+        0 => Ok(()),
+        failures => Err(format!("{} of {} servers failed", failures, names.len())),
+    }
+}
+
+/// Prints a server's response with each line prefixed by its profile, or
+/// its error on stderr, or with `--output json`, either as an object.
+fn report(name: &str, addr: &str, outcome: &Outcome, format: Format) {
+    if format == Format::Json {
+        let (response, error) = match outcome {
+            Outcome::Done(response) => (Some(response.clone()), None),
+            Outcome::Failed(error) => (None, Some(error.to_string())),
+            Outcome::Cancelled => (None, Some("cancelled".to_string())),
+        };
+
+        println!(
+            "{}",
+            serde_json::json!({
+                "profile": name,
+                "server": addr,
+                "ok": error.is_none(),
+                "response": response,
+                "error": error,
+            })
+        );
+        return;
+    }
+
+    match outcome {
+        Outcome::Done(response) if response.is_empty() => println!("{}: ok", name),
+        Outcome::Done(response) => {
+            for line in response.lines() {
+                println!("{}: {}", name, line);
+            }
+        }
+        Outcome::Failed(error) => eprintln!("rcon: {}: {}", name, error),
+        Outcome::Cancelled => eprintln!("rcon: {}: cancelled", name),
+    }
+}
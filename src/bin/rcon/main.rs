@@ -0,0 +1,616 @@
+//! `rcon`, a command-line client for one-shot commands and interactive
+//! sessions.
+//!
+//! ```text
+//! rcon --host 127.0.0.1:27015 --password hunter2 status
+//! RCON_PASSWORD=hunter2 rcon --host 127.0.0.1:27015 "say hello" status
+//! rcon --host 127.0.0.1:25575 --game minecraft repl
+//! rcon --profile eu1 status
+//! rcon --host 127.0.0.1:27015 watch --interval 10s --diff status
+//! rcon broadcast --profiles eu1,eu2,us1 "say Maintenance in 10 minutes"
+//! rcon --host 127.0.0.1:27015 exec --delay 100ms setup.cfg
+//! ```
+
+mod broadcast;
+mod output;
+mod profile;
+mod repl;
+mod watch;
+
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    time::Duration,
+};
+
+use output::{Format, Printer};
+use profile::Profile;
+use specul::{
+    Connection, ConnectionBuilder, ConnectionConfig, Error, OnError, Quirks, ScriptOptions,
+};
+use tokio::{io::AsyncReadExt, net::TcpStream};
+use watch::Watch;
+
+const USAGE: &str = "\
+Usage: rcon [OPTIONS] COMMAND...
+       rcon [OPTIONS] repl
+       rcon [OPTIONS] watch COMMAND
+       rcon [OPTIONS] broadcast --profiles NAME,... COMMAND
+       rcon [OPTIONS] exec FILE
+
+Executes each COMMAND on an RCON server and prints its response, or with
+repl, reads commands from stdin one line at a time until :quit, Ctrl-D or
+the end of input, or with watch, executes COMMAND every --interval until
+Ctrl-C, reconnecting if the connection is lost, or with broadcast,
+executes COMMAND on the servers of several profiles at once, printing each
+line of their responses after the profile's name, or with exec, executes
+the commands of FILE, or of stdin if it is -, one per line, skipping blank
+lines and comments starting with # or //. Run a server command named repl,
+watch, broadcast or exec with `rcon -- repl`.
+
+Options:
+  -H, --host HOST:PORT    the server, or $RCON_HOST
+  -p, --password PASSWORD the password, or $RCON_PASSWORD, or asked for
+  -u, --url URL           an rcon:// URL instead of --host and --password
+  -P, --profile NAME      the server of that name in the config file
+  -c, --config PATH       the config file [default:
+                          $XDG_CONFIG_HOME/rcon/servers.toml, or
+                          ~/.config/rcon/servers.toml]
+  -t, --timeout SECONDS   how long to wait for the server [default: 10]
+  -g, --game GAME         source, minecraft or factorio, for completion
+  -o, --output FORMAT     plain, json for a JSON object per response with
+                          its latency and packets, or table to show the
+                          players of status and list as columns
+                          [default: plain]
+  -i, --interval DURATION how often watch executes its command, such as
+                          500ms, 10s or 1m [default: 2s]
+  -d, --diff              have watch print only the lines that changed,
+                          prefixed with - or +
+  -n, --count N           have watch stop after N runs
+      --profiles NAME,...  the profiles broadcast executes its command on
+      --fail-fast         have broadcast stop at the first server to fail,
+                          rather than waiting for them all
+      --delay DURATION    how long exec waits between commands [default: 0]
+      --keep-going        have exec go on after a command fails
+  -h, --help              show this message
+
+In the repl, Tab completes the known commands of the --game, the arrow
+keys and Ctrl-R recall past commands, which are kept in $RCON_HISTORY or
+~/.rcon_history, :history lists them and :complete PREFIX lists the known
+commands starting with PREFIX. Ctrl-C discards the line being typed, or
+stops waiting for a command and ends the session.
+
+A profile is a [NAME] table of host, port, quirks (source, minecraft or
+factorio), timeout, command_deadline and read_timeout in seconds or as
+durations such as \"5s\", multiple_responses, command_prefix, and the
+password as password_env, naming an environment variable,
+password_command, a shell command printing it, or password_keyring, the
+user of an entry of the rcon service in the system keyring. Options given
+on the command line take precedence.
+
+Exits with 1 if the server cannot be reached, rejects the password or
+fails a command, and with 2 on invalid arguments.";
+
+/// Commands offered by completion and `:complete`, for each game.
+const SOURCE_COMMANDS: &[&str] = &[
+    "changelevel",
+    "cvarlist",
+    "echo",
+    "exec",
+    "find",
+    "help",
+    "host_framerate",
+    "kick",
+    "kickid",
+    "listid",
+    "maps",
+    "mp_restartgame",
+    "mp_timelimit",
+    "say",
+    "status",
+    "sv_cheats",
+    "sv_password",
+    "users",
+    "version",
+];
+const MINECRAFT_COMMANDS: &[&str] = &[
+    "ban",
+    "ban-ip",
+    "banlist",
+    "deop",
+    "difficulty",
+    "gamemode",
+    "gamerule",
+    "give",
+    "kick",
+    "list",
+    "op",
+    "pardon",
+    "save-all",
+    "save-off",
+    "save-on",
+    "say",
+    "seed",
+    "stop",
+    "teleport",
+    "tell",
+    "tellraw",
+    "time",
+    "title",
+    "tp",
+    "weather",
+    "whitelist",
+];
+const FACTORIO_COMMANDS: &[&str] = &[
+    "/admins",
+    "/ban",
+    "/banlist",
+    "/c",
+    "/evolution",
+    "/kick",
+    "/players",
+    "/promote",
+    "/purge",
+    "/save",
+    "/seed",
+    "/server-save",
+    "/silent-command",
+    "/time",
+    "/unban",
+    "/version",
+    "/whisper",
+];
+
+#[derive(Debug, Default)]
+struct Args {
+    host: Option<String>,
+    password: Option<String>,
+    url: Option<String>,
+    profile: Option<String>,
+    config: Option<PathBuf>,
+    timeout: Option<Duration>,
+    game: Option<&'static [&'static str]>,
+    output: Format,
+    mode: Mode,
+    watch: Watch,
+    profiles: Vec<String>,
+    fail_fast: bool,
+    script: ScriptOptions,
+    commands: Vec<String>,
+}
+
+/// What to do with the connection once it is open.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    /// Execute the commands given once each.
+    #[default]
+    Commands,
+    Repl,
+    Watch,
+    Broadcast,
+    Exec,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Args>, String> {
+    let mut parsed = Args::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| format!("{} needs a value", arg));
+
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-H" | "--host" => parsed.host = Some(value()?),
+            "-p" | "--password" => parsed.password = Some(value()?),
+            "-u" | "--url" => parsed.url = Some(value()?),
+            "-P" | "--profile" => parsed.profile = Some(value()?),
+            "-c" | "--config" => parsed.config = Some(value()?.into()),
+            "-t" | "--timeout" => {
+                let seconds = value()?;
+                let seconds: f64 = seconds
+                    .parse()
+                    .map_err(|_| format!("invalid timeout {}", seconds))?;
+                let timeout = Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| format!("invalid timeout {}", seconds))?;
+                parsed.timeout = Some(timeout);
+            }
+            "-g" | "--game" => {
+                let game = value()?;
+                parsed.game = Some(match game.as_str() {
+                    "source" => SOURCE_COMMANDS,
+                    "minecraft" => MINECRAFT_COMMANDS,
+                    "factorio" => FACTORIO_COMMANDS,
+                    _ => return Err(format!("unknown game {}", game)),
+                });
+            }
+            "-o" | "--output" => {
+                let output = value()?;
+                parsed.output =
+                    Format::parse(&output).ok_or_else(|| format!("unknown output {}", output))?;
+            }
+            "-i" | "--interval" => {
+                let interval = value()?;
+                parsed.watch.interval = parse_duration(&interval)
+                    .ok_or_else(|| format!("invalid interval {}", interval))?;
+            }
+            "-d" | "--diff" => parsed.watch.diff = true,
+            "-n" | "--count" => {
+                let count = value()?;
+                parsed.watch.count = Some(
+                    count
+                        .parse()
+                        .map_err(|_| format!("invalid count {}", count))?,
+                );
+            }
+            "--profiles" => {
+                parsed.profiles = value()?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|name| !name.is_empty())
+                    .map(String::from)
+                    .collect();
+            }
+            "--fail-fast" => parsed.fail_fast = true,
+            "--delay" => {
+                let delay = value()?;
+                parsed.script.delay =
+                    parse_duration(&delay).ok_or_else(|| format!("invalid delay {}", delay))?;
+            }
+            "--keep-going" => parsed.script.on_error = OnError::Continue,
+            "--" => parsed.commands.extend(args.by_ref()),
+            "repl" if parsed.commands.is_empty() && parsed.mode == Mode::Commands => {
+                parsed.mode = Mode::Repl;
+            }
+            "watch" if parsed.commands.is_empty() && parsed.mode == Mode::Commands => {
+                parsed.mode = Mode::Watch;
+            }
+            "broadcast" if parsed.commands.is_empty() && parsed.mode == Mode::Commands => {
+                parsed.mode = Mode::Broadcast;
+            }
+            "exec" if parsed.commands.is_empty() && parsed.mode == Mode::Commands => {
+                parsed.mode = Mode::Exec;
+            }
+            option if option.starts_with('-') && option.len() > 1 => {
+                return Err(format!("unknown option {}", option));
+            }
+            _ => parsed.commands.push(arg),
+        }
+    }
+
+    match parsed.mode {
+        Mode::Commands if parsed.commands.is_empty() => {
+            return Err("no command given".to_string());
+        }
+        Mode::Repl if !parsed.commands.is_empty() => {
+            return Err("repl takes no commands".to_string());
+        }
+        Mode::Watch if parsed.commands.len() != 1 => {
+            return Err("watch takes one command".to_string());
+        }
+        Mode::Broadcast if parsed.commands.len() != 1 => {
+            return Err("broadcast takes one command".to_string());
+        }
+        Mode::Exec if parsed.commands.len() != 1 => {
+            return Err("exec takes one file".to_string());
+        }
+        Mode::Broadcast if parsed.profiles.is_empty() => {
+            return Err("broadcast needs --profiles".to_string());
+        }
+        Mode::Broadcast
+            if parsed.host.is_some() || parsed.url.is_some() || parsed.profile.is_some() =>
+        {
+            return Err(
+                "broadcast takes its servers from --profiles, not --host, --url or --profile"
+                    .to_string(),
+            );
+        }
+        _ => {}
+    }
+
+    if parsed.mode != Mode::Watch && parsed.watch != Watch::default() {
+        return Err("--interval, --diff and --count only apply to watch".to_string());
+    }
+
+    if parsed.mode != Mode::Broadcast && (!parsed.profiles.is_empty() || parsed.fail_fast) {
+        return Err("--profiles and --fail-fast only apply to broadcast".to_string());
+    }
+
+    if parsed.mode != Mode::Exec && parsed.script != ScriptOptions::default() {
+        return Err("--delay and --keep-going only apply to exec".to_string());
+    }
+
+    if parsed.watch.diff && parsed.output == Format::Json {
+        return Err("--diff cannot be combined with --output json".to_string());
+    }
+
+    if parsed.watch.interval.is_zero() {
+        return Err("the interval must not be zero".to_string());
+    }
+
+    if parsed.url.is_some()
+        && (parsed.host.is_some() || parsed.password.is_some() || parsed.profile.is_some())
+    {
+        return Err("--url cannot be combined with --host, --password or --profile".to_string());
+    }
+
+    Ok(Some(parsed))
+}
+
+/// Parses `500ms`, `10s`, `1m` or `1h`, or a number of seconds.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit() && c != '.') {
+        Some(index) => text.split_at(index),
+        None => (text, "s"),
+    };
+    let number: f64 = number.parse().ok()?;
+
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Asks for the password on the terminal, without echoing it. If stdin is
+/// not a terminal, the password is read from it instead, a byte at a time,
+/// so that nothing after it is taken from the commands the repl reads next.
+async fn prompt_password() -> io::Result<String> {
+    if io::stdin().is_terminal() {
+        return tokio::task::spawn_blocking(|| rpassword::prompt_password("Password: "))
+            .await
+            .map_err(io::Error::other)?;
+    }
+
+    eprint!("Password: ");
+    io::stderr().flush()?;
+
+    let mut stdin = tokio::io::stdin();
+    let mut password = Vec::new();
+
+    loop {
+        match stdin.read_u8().await {
+            Ok(b'\n') => break,
+            Ok(byte) => password.push(byte),
+            Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(error),
+        }
+    }
+
+    let password = String::from_utf8(password)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "password is not UTF-8"))?;
+
+    Ok(password.trim_end_matches('\r').to_string())
+}
+
+/// Returns where to connect and how, from the `--url`, or else from the
+/// options, the profile and the environment, in that order, asking for the
+/// password if it was given nowhere.
+async fn config(args: &Args, profile: &Profile) -> Result<ConnectionConfig, String> {
+    if let Some(url) = &args.url {
+        return ConnectionConfig::from_url(url).map_err(|error| error.to_string());
+    }
+
+    let mut config = match (&args.host, &profile.connection) {
+        (None, Some(connection)) => connection.clone(),
+        (host, connection) => {
+            let host = match host {
+                Some(host) => host.clone(),
+                None => env::var("RCON_HOST").map_err(|_| "no --host given")?,
+            };
+            let addr = ConnectionConfig::from_url(&format!("rcon://{}", host))
+                .map_err(|error| error.to_string())?;
+
+            // --host only moves the profile's server elsewhere.
+            match connection {
+                Some(connection) => ConnectionConfig {
+                    host: addr.host,
+                    port: addr.port,
+                    ..connection.clone()
+                },
+                None => addr,
+            }
+        }
+    };
+
+    config.password = match (&args.password, &profile.password) {
+        (Some(password), _) => password.clone(),
+        (None, Some(source)) => source.resolve()?,
+        (None, None) => match env::var("RCON_PASSWORD") {
+            Ok(password) => password,
+            Err(_) => prompt_password().await.map_err(|error| error.to_string())?,
+        },
+    };
+
+    Ok(config)
+}
+
+async fn connect(
+    config: &ConnectionConfig,
+    timeout: Duration,
+) -> specul::Result<Connection<TcpStream>> {
+    let connect = async {
+        let tcp = TcpStream::connect(config.addr()).await?;
+        let mut connection = config
+            .apply(ConnectionBuilder::default().io(tcp))
+            .build()
+            .expect("connection builder is complete");
+
+        connection.authenticate(&config.password).await?;
+        Ok(connection)
+    };
+
+    tokio::time::timeout(timeout, connect)
+        .await
+        .unwrap_or(Err(Error::Timeout))
+}
+
+/// Describes why connecting failed.
+fn connect_error(error: Error) -> String {
+    match error {
+        Error::Authentication => "authentication failed: wrong password".to_string(),
+        error => error.to_string(),
+    }
+}
+
+async fn run(args: Args) -> Result<(), String> {
+    let config_path = || match &args.config {
+        Some(path) => Ok(path.clone()),
+        None => profile::default_path().ok_or("no home directory for the config file"),
+    };
+
+    if args.mode == Mode::Broadcast {
+        return broadcast::broadcast(
+            &config_path()?,
+            &args.profiles,
+            args.password.as_deref(),
+            &args.commands[0],
+            args.output,
+            args.fail_fast,
+            args.timeout.unwrap_or(Duration::from_secs(10)),
+        )
+        .await;
+    }
+
+    let profile = match &args.profile {
+        Some(name) => profile::load(&config_path()?, name)?,
+        None => Profile::default(),
+    };
+
+    let config = config(&args, &profile).await?;
+    let timeout = args
+        .timeout
+        .or(profile.timeout())
+        .unwrap_or(Duration::from_secs(10));
+    let dictionary = args.game.unwrap_or(match config.quirks {
+        Some(Quirks::Source) => SOURCE_COMMANDS,
+        Some(Quirks::Minecraft) => MINECRAFT_COMMANDS,
+        Some(Quirks::Factorio) => FACTORIO_COMMANDS,
+        None => &[],
+    });
+
+    let printer = Printer {
+        format: args.output,
+        server: config.addr(),
+        profile: args.profile.clone(),
+    };
+
+    // Read before connecting, so a missing file fails without a connection.
+    let script = match args.mode {
+        Mode::Exec => Some(read_script(&args.commands[0]).await?),
+        _ => None,
+    };
+
+    let mut connection = connect(&config, timeout).await.map_err(connect_error)?;
+
+    if let Some(script) = script {
+        return exec(
+            &mut connection,
+            &printer,
+            &args.commands[0],
+            &script,
+            args.script,
+        )
+        .await;
+    }
+
+    if args.mode == Mode::Watch {
+        let command = &args.commands[0];
+        return watch::watch(connection, &config, &printer, command, args.watch, timeout).await;
+    }
+
+    for command in &args.commands {
+        let response = tokio::time::timeout(timeout, connection.execute(command))
+            .await
+            .unwrap_or(Err(Error::Timeout))
+            .map_err(|error| format!("{}: {}", command, error))?;
+
+        printer.print(command, &response);
+    }
+
+    if args.mode == Mode::Repl {
+        repl::repl(&mut connection, &printer, dictionary, timeout).await?;
+    }
+
+    let _ = connection.close().await;
+    Ok(())
+}
+
+async fn read_script(path: &str) -> Result<Vec<u8>, String> {
+    let mut script = Vec::new();
+
+    let read = match path {
+        "-" => tokio::io::stdin().read_to_end(&mut script).await.map(drop),
+        path => fs::read(path).map(|bytes| script = bytes),
+    };
+
+    read.map_err(|error| format!("{}: {}", path, error))?;
+    Ok(script)
+}
+
+/// Executes a script, printing each response and reporting each failure
+/// with its line.
+async fn exec(
+    connection: &mut Connection<TcpStream>,
+    printer: &Printer,
+    path: &str,
+    script: &[u8],
+    options: ScriptOptions,
+) -> Result<(), String> {
+    let lines = connection
+        .run_script_with(script, options)
+        .await
+        .map_err(|error| format!("{}: {}", path, error))?;
+
+    let mut failures = 0;
+
+    for line in &lines {
+        match &line.result {
+            Ok(response) => printer.print(&line.command, response),
+            Err(error) => {
+                failures += 1;
+                eprintln!("rcon: {}:{}: {}: {}", path, line.line, line.command, error);
+            }
+        }
+    }
+
+    let _ = connection.close().await;
+
+    match failures {
+        0 => Ok(()),
+        failures => Err(format!("{} of {} commands failed", failures, lines.len())),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(message) => {
+            eprintln!("rcon: {}\n\n{}", message, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("failed to start the runtime");
+
+    let result = runtime.block_on(run(args));
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("rcon: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
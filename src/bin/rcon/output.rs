@@ -0,0 +1,171 @@
+//! Printing responses as plain text, JSON lines or tables.
+
+use specul::{minecraft::ListPlayers, source::Status, Command, Response};
+
+/// How responses are printed, chosen with `--output`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    /// The response as the server sent it.
+    #[default]
+    Plain,
+    /// One JSON object per command, with the response and its metadata.
+    Json,
+    /// Player lists as aligned columns, and other responses as plain text.
+    Table,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "plain" => Some(Format::Plain),
+            "json" => Some(Format::Json),
+            "table" => Some(Format::Table),
+            _ => None,
+        }
+    }
+}
+
+/// What each printed response is attributed to.
+#[derive(Debug)]
+pub struct Printer {
+    pub format: Format,
+    pub server: String,
+    pub profile: Option<String>,
+}
+
+impl Printer {
+    pub fn print(&self, command: &str, response: &Response) {
+        match self.format {
+            Format::Json => println!("{}", self.json(command, response)),
+            Format::Plain | Format::Table => print!("{}", self.text(command, response)),
+        }
+    }
+
+    /// Returns the response as plain text or a table, ending in a newline
+    /// unless it is empty.
+    pub fn text(&self, command: &str, response: &Response) -> String {
+        let table = match self.format {
+            Format::Table => table(command, &response.body),
+            Format::Plain | Format::Json => None,
+        };
+
+        table.unwrap_or_else(|| plain(&response.body))
+    }
+
+    fn json(&self, command: &str, response: &Response) -> serde_json::Value {
+        serde_json::json!({
+            "command": command,
+            "response": response.body,
+            "payloads": response.payloads,
+            "packets": response.packets(),
+            "request_id": response.request_id,
+            "latency_ms": response.latency.as_secs_f64() * 1000.0,
+            "server": self.server,
+            "profile": self.profile,
+        })
+    }
+}
+
+fn plain(response: &str) -> String {
+    if response.ends_with('\n') || response.is_empty() {
+        response.to_string()
+    } else {
+        format!("{}\n", response)
+    }
+}
+
+/// Renders the responses of commands whose output is known, `status` and
+/// Minecraft's `list`, as tables.
+fn table(command: &str, response: &str) -> Option<String> {
+    let name = command.split_whitespace().next()?;
+
+    match name.trim_start_matches('/') {
+        "status" => {
+            let status = Status::parse(response).ok()?;
+            let rows = status.player_list.iter().map(|player| {
+                vec![
+                    player.userid.to_string(),
+                    player.name.clone(),
+                    player.steam_id.map(|id| id.to_string()).unwrap_or_default(),
+                    player.connected.clone(),
+                    player.ping.to_string(),
+                    player.loss.to_string(),
+                    player
+                        .address
+                        .map(|adr| adr.to_string())
+                        .unwrap_or_default(),
+                ]
+            });
+
+            let players = match status.max_players {
+                Some(max) => format!("{}/{}", status.players, max),
+                None => status.players.to_string(),
+            };
+
+            Some(format!(
+                "hostname: {}\nmap: {}\nplayers: {} ({} bots)\n\n{}",
+                status.hostname,
+                status.map,
+                players,
+                status.bots,
+                columns(
+                    &[
+                        "USERID",
+                        "NAME",
+                        "STEAMID",
+                        "CONNECTED",
+                        "PING",
+                        "LOSS",
+                        "ADDRESS"
+                    ],
+                    rows,
+                ),
+            ))
+        }
+        "list" => {
+            let list = ListPlayers::parse_response(response).ok()?;
+            let rows = list.players.iter().map(|player| {
+                vec![
+                    player.name.clone(),
+                    player.uuid.map(|uuid| uuid.to_string()).unwrap_or_default(),
+                ]
+            });
+
+            Some(format!(
+                "players: {}/{}\n\n{}",
+                list.online,
+                list.max,
+                columns(&["NAME", "UUID"], rows)
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Lays out `rows` under `headers`, each column as wide as its widest cell.
+fn columns(headers: &[&str], rows: impl Iterator<Item = Vec<String>>) -> String {
+    let rows: Vec<Vec<String>> = rows.collect();
+    let mut widths: Vec<usize> = headers.iter().map(|header| header.len()).collect();
+
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let headers = headers.iter().map(|header| header.to_string()).collect();
+    let mut table = String::new();
+
+    for row in std::iter::once(headers).chain(rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+
+    table
+}
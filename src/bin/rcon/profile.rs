@@ -0,0 +1,168 @@
+//! Named server profiles, read from a `servers.toml` such as:
+//!
+//! ```toml
+//! [eu1]
+//! host = "eu1.example.com"
+//! port = 27015
+//! password_env = "EU1_RCON_PASSWORD"
+//! timeout = 5
+//!
+//! [creative]
+//! host = "10.0.0.7:25575"
+//! password_keyring = "creative"
+//! quirks = "minecraft"
+//! ```
+//!
+//! Each table is a [`ConnectionConfig`], with the password given as one of
+//! `password`, `password_env`, `password_command` or `password_keyring`.
+
+use std::{
+    env,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use specul::ConnectionConfig;
+
+/// The keyring service `password_keyring` entries are stored under.
+const KEYRING_SERVICE: &str = "rcon";
+
+/// A server's settings, each of which the command line may override.
+#[derive(Debug, Default)]
+pub struct Profile {
+    /// The server and how to talk to it, without the password.
+    pub connection: Option<ConnectionConfig>,
+    pub password: Option<PasswordSource>,
+}
+
+impl Profile {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.connection.as_ref()?.timeout
+    }
+}
+
+/// Where a profile's password comes from, so it need not be written in the
+/// file.
+#[derive(Debug)]
+pub enum PasswordSource {
+    /// `password`, the password itself.
+    Literal(String),
+    /// `password_env`, an environment variable holding it.
+    Env(String),
+    /// `password_command`, a shell command printing it, such as a password
+    /// manager's.
+    Command(String),
+    /// `password_keyring`, the user of an entry of the `rcon` service in
+    /// the system keyring: the macOS Keychain, the Windows Credential
+    /// Manager or the Secret Service.
+    Keyring(String),
+}
+
+impl PasswordSource {
+    pub fn resolve(&self) -> Result<String, String> {
+        match self {
+            PasswordSource::Literal(password) => Ok(password.clone()),
+            PasswordSource::Env(name) => {
+                env::var(name).map_err(|_| format!("password variable {} is not set", name))
+            }
+            PasswordSource::Command(command) => {
+                let output = Command::new("sh")
+                    .args(["-c", command])
+                    .output()
+                    .map_err(|error| format!("password command failed: {}", error))?;
+
+                if !output.status.success() {
+                    return Err(format!("password command exited with {}", output.status));
+                }
+
+                let output = String::from_utf8(output.stdout)
+                    .map_err(|_| "password command printed invalid UTF-8".to_string())?;
+
+                Ok(output.lines().next().unwrap_or_default().to_string())
+            }
+            PasswordSource::Keyring(user) => keyring::Entry::new(KEYRING_SERVICE, user)
+                .and_then(|entry| entry.get_password())
+                .map_err(|error| format!("cannot read keyring entry {}: {}", user, error)),
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/rcon/servers.toml`, or `~/.config/rcon/servers.toml`.
+pub fn default_path() -> Option<PathBuf> {
+    let config = match env::var_os("XDG_CONFIG_HOME") {
+        Some(config) => PathBuf::from(config),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+
+    Some(config.join("rcon").join("servers.toml"))
+}
+
+/// Reads the profile called `name` from the file at `path`.
+pub fn load(path: &Path, name: &str) -> Result<Profile, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|error| format!("cannot read {}: {}", path.display(), error))?;
+    let mut tables: toml::Table = text
+        .parse()
+        .map_err(|error| format!("{}: {}", path.display(), error))?;
+
+    let table = tables
+        .remove(name)
+        .ok_or_else(|| format!("no profile {} in {}", name, path.display()))?;
+
+    profile(table).map_err(|error| format!("profile {}: {}", name, error))
+}
+
+fn profile(table: toml::Value) -> Result<Profile, String> {
+    let toml::Value::Table(mut table) = table else {
+        return Err("not a table".to_string());
+    };
+
+    let sources = [
+        (
+            "password_env",
+            PasswordSource::Env as fn(String) -> PasswordSource,
+        ),
+        ("password_command", PasswordSource::Command),
+        ("password_keyring", PasswordSource::Keyring),
+    ];
+    let mut password = None;
+
+    for (key, source) in sources {
+        match table.remove(key) {
+            Some(toml::Value::String(value)) => {
+                if password.replace(source(value)).is_some() {
+                    return Err("more than one password is given".to_string());
+                }
+            }
+            Some(value) => return Err(format!("invalid {}: {}", key, value)),
+            None => continue,
+        }
+    }
+
+    let has_port = table.contains_key("port");
+    let mut connection: ConnectionConfig = toml::Value::Table(table)
+        .try_into()
+        .map_err(|error: toml::de::Error| error.message().to_string())?;
+
+    // Without a `port`, the host may carry one, as `--host` does.
+    if !has_port {
+        if let Ok(addr) = ConnectionConfig::from_url(&format!("rcon://{}", connection.host)) {
+            connection.host = addr.host;
+            connection.port = addr.port;
+        }
+    }
+
+    if !connection.password.is_empty() {
+        let literal = PasswordSource::Literal(std::mem::take(&mut connection.password));
+
+        if password.replace(literal).is_some() {
+            return Err("more than one password is given".to_string());
+        }
+    }
+
+    Ok(Profile {
+        connection: Some(connection),
+        password,
+    })
+}
@@ -0,0 +1,156 @@
+//! The interactive session of `rcon repl`, edited with `rustyline`.
+
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::PathBuf,
+    time::Duration,
+};
+
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    history::FileHistory, validate::Validator, Context, Editor, Helper,
+};
+use specul::{Connection, Error};
+use tokio::net::TcpStream;
+
+use crate::output::Printer;
+
+/// The repl's own commands, completed along with the game's.
+const REPL_COMMANDS: &[&str] = &[":complete", ":history", ":quit"];
+
+/// Completes the command at the start of a line from the `--game`'s
+/// commands and the repl's own.
+struct Commands(&'static [&'static str]);
+
+impl Completer for Commands {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let prefix = &line[..pos];
+
+        // Arguments are not completed.
+        if prefix.contains(char::is_whitespace) {
+            return Ok((pos, Vec::new()));
+        }
+
+        let candidates = REPL_COMMANDS
+            .iter()
+            .chain(self.0)
+            .filter(|command| command.starts_with(prefix))
+            .map(|command| command.to_string())
+            .collect();
+
+        Ok((0, candidates))
+    }
+}
+
+impl Hinter for Commands {
+    type Hint = String;
+}
+
+impl Highlighter for Commands {}
+
+impl Validator for Commands {}
+
+impl Helper for Commands {}
+
+fn history_path() -> Option<PathBuf> {
+    match env::var_os("RCON_HISTORY") {
+        Some(path) => Some(path.into()),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".rcon_history")),
+    }
+}
+
+/// Reads commands until `:quit`, Ctrl-D or the end of stdin, or Ctrl-C
+/// while a command runs. Ctrl-C at the prompt only discards the line being
+/// typed.
+pub async fn repl(
+    connection: &mut Connection<TcpStream>,
+    printer: &Printer,
+    dictionary: &'static [&'static str],
+    timeout: Duration,
+) -> Result<(), String> {
+    let interactive = io::stdin().is_terminal();
+    let mut editor = Editor::<Commands, FileHistory>::new().map_err(|error| error.to_string())?;
+    editor.set_helper(Some(Commands(dictionary)));
+
+    let history_path = history_path();
+    // History is best effort: without a readable and writable file, it is
+    // not kept.
+    if let Some(path) = &history_path {
+        let _ = editor.load_history(path);
+    }
+
+    loop {
+        // Reading a line blocks, so it is done off the runtime.
+        let (returned, line) = tokio::task::spawn_blocking(move || {
+            let line = editor.readline("> ");
+            (editor, line)
+        })
+        .await
+        .map_err(|error| error.to_string())?;
+        editor = returned;
+
+        let line = match line {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error.to_string()),
+        };
+        let line = line.trim();
+
+        match line.split_once(' ').unwrap_or((line, "")) {
+            ("", _) => continue,
+            (":quit" | ":q" | ":exit", _) => break,
+            (":history", _) => {
+                for command in editor.history().iter() {
+                    println!("{}", command);
+                }
+                continue;
+            }
+            (":complete", prefix) => {
+                for command in dictionary
+                    .iter()
+                    .filter(|command| command.starts_with(prefix))
+                {
+                    println!("{}", command);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let _ = editor.add_history_entry(line);
+        if let Some(path) = &history_path {
+            let _ = editor.append_history(path);
+        }
+
+        let result = tokio::select! {
+            result = tokio::time::timeout(timeout, connection.execute(line)) => {
+                result.unwrap_or(Err(Error::Timeout))
+            }
+            _ = tokio::signal::ctrl_c() => {
+                if interactive {
+                    println!();
+                }
+                break;
+            }
+        };
+
+        match result {
+            Ok(response) => printer.print(line, &response),
+            Err(error @ (Error::Timeout | Error::Io(_) | Error::Disconnected)) => {
+                return Err(error.to_string());
+            }
+            Err(error) => eprintln!("rcon: {}", error),
+        }
+    }
+
+    Ok(())
+}
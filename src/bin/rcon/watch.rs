@@ -0,0 +1,156 @@
+//! Re-executing a command on an interval, as `rcon watch`.
+
+use std::{
+    io::{self, IsTerminal, Write},
+    time::Duration,
+};
+
+use specul::{Connection, ConnectionConfig, Error, ErrorKind};
+use tokio::{net::TcpStream, time::MissedTickBehavior};
+
+use crate::output::Printer;
+
+/// How `rcon watch` runs its command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Watch {
+    pub interval: Duration,
+    /// Print only the lines that changed since the last run.
+    pub diff: bool,
+    /// Stop after this many runs, rather than at Ctrl-C.
+    pub count: Option<u64>,
+}
+
+impl Default for Watch {
+    fn default() -> Self {
+        Watch {
+            interval: Duration::from_secs(2),
+            diff: false,
+            count: None,
+        }
+    }
+}
+
+/// Executes `command` every interval until Ctrl-C or the count runs out.
+///
+/// A failed command is reported and retried on the next run, and if it left
+/// the connection unusable, the connection is reopened first. Only a wrong
+/// password ends the watch, since retrying cannot fix it.
+pub async fn watch(
+    connection: Connection<TcpStream>,
+    config: &ConnectionConfig,
+    printer: &Printer,
+    command: &str,
+    watch: Watch,
+    timeout: Duration,
+) -> Result<(), String> {
+    let clear = io::stdout().is_terminal() && !watch.diff;
+    let mut connection = Some(connection);
+    let mut previous: Option<String> = None;
+    let mut interval = tokio::time::interval(watch.interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    for _ in 0..watch.count.unwrap_or(u64::MAX) {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+
+        let current = match connection.take() {
+            Some(current) => current,
+            None => match crate::connect(config, timeout).await {
+                Ok(current) => current,
+                Err(error @ Error::Authentication) => return Err(crate::connect_error(error)),
+                Err(error) => {
+                    eprintln!("rcon: reconnecting: {}", crate::connect_error(error));
+                    continue;
+                }
+            },
+        };
+        let current = connection.insert(current);
+
+        let result = tokio::time::timeout(timeout, current.execute(command))
+            .await
+            .unwrap_or(Err(Error::Timeout));
+
+        let response = match result {
+            Ok(response) => response,
+            Err(error) => {
+                eprintln!("rcon: {}: {}", command, error);
+
+                // Errors that may have left the connection out of step with
+                // the server.
+                if matches!(
+                    error.kind(),
+                    ErrorKind::Io
+                        | ErrorKind::Timeout
+                        | ErrorKind::Protocol
+                        | ErrorKind::Disconnected
+                ) {
+                    connection = None;
+                }
+                continue;
+            }
+        };
+
+        if !watch.diff {
+            if clear {
+                print!("\x1b[2J\x1b[HEvery {:?}: {}\n\n", watch.interval, command);
+            }
+            printer.print(command, &response);
+        } else {
+            let text = printer.text(command, &response);
+
+            match &previous {
+                Some(previous) => print!("{}", diff(previous, &text)),
+                None => print!("{}", text),
+            }
+            previous = Some(text);
+        }
+
+        let _ = io::stdout().flush();
+    }
+
+    if let Some(mut connection) = connection {
+        let _ = connection.close().await;
+    }
+
+    Ok(())
+}
+
+/// Returns the lines removed from `old`, prefixed with `-`, and those added
+/// in `new`, prefixed with `+`, in order, or nothing if they are the same.
+fn diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j] is the length of the longest common subsequence of
+    // old[i..] and new[j..].
+    let mut common = vec![vec![0; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut diff = String::new();
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            i += 1;
+            j += 1;
+        } else if j == new.len() || (i < old.len() && common[i + 1][j] >= common[i][j + 1]) {
+            diff.push_str(&format!("- {}\n", old[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+ {}\n", new[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
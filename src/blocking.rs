@@ -0,0 +1,120 @@
+//! A synchronous connection, for scripts and tools that have no async
+//! runtime of their own.
+//!
+//! ```no_run
+//! # fn run() -> specul::Result<()> {
+//! use specul::blocking::Connection;
+//!
+//! let mut connection = Connection::connect("127.0.0.1:27015", "password")?;
+//! let response = connection.execute_command("status")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io,
+    net::{SocketAddr, ToSocketAddrs},
+};
+
+use tokio::{net::TcpStream, runtime::Runtime};
+
+use crate::{Command, Response, Result, State};
+
+/// A connection over TCP whose methods block until they finish.
+///
+/// Each connection drives an asynchronous [`Connection`](crate::Connection)
+/// on its own single-threaded runtime, so commands take the same code paths,
+/// with the same framing, timeouts and quirks, as they do asynchronously.
+/// Blocking methods must not be called from within an async runtime.
+#[derive(Debug)]
+pub struct Connection {
+    // Drops before the runtime it is registered with.
+    connection: crate::Connection<TcpStream>,
+    runtime: Runtime,
+}
+
+impl Connection {
+    /// Connects to `addr` and authenticates with `password`, like
+    /// [`Connection::connect`](crate::Connection::connect).
+    pub fn connect(addr: impl ToSocketAddrs, password: &str) -> Result<Self> {
+        let mut connection = Self::open(addr)?;
+        connection.authenticate(password)?;
+
+        Ok(connection)
+    }
+
+    /// Connects and authenticates as described by an `rcon://` URL, like
+    /// [`Connection::connect_url`](crate::Connection::connect_url).
+    pub fn connect_url(url: &str) -> Result<Self> {
+        let runtime = runtime()?;
+        let connection = runtime.block_on(crate::Connection::connect_url(url))?;
+
+        Ok(Connection {
+            connection,
+            runtime,
+        })
+    }
+
+    /// Connects to `addr` without authenticating, for servers that need
+    /// something else done first.
+    pub fn open(addr: impl ToSocketAddrs) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+        let runtime = runtime()?;
+        let connection = runtime.block_on(crate::Connection::dial(&addrs[..]))?;
+
+        Ok(Connection {
+            connection,
+            runtime,
+        })
+    }
+
+    /// See [`Connection::authenticate`](crate::Connection::authenticate).
+    pub fn authenticate(&mut self, password: &str) -> Result<()> {
+        self.runtime
+            .block_on(self.connection.authenticate(password))
+    }
+
+    /// See [`Connection::execute_command`](crate::Connection::execute_command).
+    pub fn execute_command(&mut self, command: &str) -> Result<Vec<String>> {
+        self.runtime
+            .block_on(self.connection.execute_command(command))
+    }
+
+    /// See [`Connection::execute`](crate::Connection::execute).
+    pub fn execute(&mut self, command: &str) -> Result<Response> {
+        self.runtime.block_on(self.connection.execute(command))
+    }
+
+    /// See [`Connection::run`](crate::Connection::run).
+    pub fn run<C: Command>(&mut self, command: &C) -> Result<C::Output> {
+        self.runtime.block_on(self.connection.run(command))
+    }
+
+    /// See [`Connection::reconnect`](crate::Connection::reconnect).
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.runtime.block_on(self.connection.reconnect())
+    }
+
+    /// See [`Connection::close`](crate::Connection::close).
+    pub fn close(&mut self) -> Result<()> {
+        self.runtime.block_on(self.connection.close())
+    }
+
+    pub fn state(&self) -> State {
+        self.connection.state()
+    }
+
+    pub fn is_authenticated(&self) -> bool {
+        self.connection.is_authenticated()
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.connection.peer_addr()
+    }
+}
+
+fn runtime() -> io::Result<Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+}
@@ -0,0 +1,100 @@
+use std::{borrow::Cow, io};
+
+/// How payloads are turned into text and back.
+///
+/// RCON payloads are meant to be ASCII, but some servers, such as GoldSrc
+/// and modded ones, send Windows-1252 or embed binary data, which fails a
+/// strict UTF-8 decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Charset {
+    /// UTF-8, failing payloads that are not with an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error.
+    #[default]
+    Utf8,
+    /// UTF-8, with invalid sequences replaced by `U+FFFD`.
+    Utf8Lossy,
+    /// ISO-8859-1, which maps every byte to the character of the same value,
+    /// so nothing is lost either way. Characters above `U+00FF` cannot be
+    /// sent.
+    Latin1,
+    /// Any encoding `encoding_rs` supports, such as
+    /// [`WINDOWS_1252`](encoding_rs::WINDOWS_1252). Invalid sequences are
+    /// replaced, as are characters the encoding cannot represent when
+    /// sending.
+    #[cfg(feature = "encoding")]
+    Encoding(&'static encoding_rs::Encoding),
+}
+
+impl Charset {
+    /// Returns the name of the encoding, such as `"utf-8"` or
+    /// `"windows-1252"`.
+    ///
+    /// ```
+    /// use specul::Charset;
+    ///
+    /// assert_eq!(Charset::Latin1.name(), "iso-8859-1");
+    /// ```
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "utf-8",
+            Charset::Utf8Lossy => "utf-8 (lossy)",
+            Charset::Latin1 => "iso-8859-1",
+            #[cfg(feature = "encoding")]
+            Charset::Encoding(encoding) => encoding.name(),
+        }
+    }
+
+    /// Decodes a payload received from the server.
+    pub fn decode(self, bytes: &[u8]) -> io::Result<String> {
+        match self {
+            Charset::Utf8 => String::from_utf8(bytes.to_vec())
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Invalid UTF-8 payload")),
+            Charset::Utf8Lossy => Ok(String::from_utf8_lossy(bytes).into_owned()),
+            Charset::Latin1 => Ok(bytes.iter().copied().map(char::from).collect()),
+            #[cfg(feature = "encoding")]
+            Charset::Encoding(encoding) => {
+                Ok(encoding.decode_without_bom_handling(bytes).0.into_owned())
+            }
+        }
+    }
+
+    /// Encodes a payload to send to the server.
+    pub fn encode(self, text: &str) -> io::Result<Cow<'_, [u8]>> {
+        match self {
+            Charset::Utf8 | Charset::Utf8Lossy => Ok(Cow::Borrowed(text.as_bytes())),
+            Charset::Latin1 if text.is_ascii() => Ok(Cow::Borrowed(text.as_bytes())),
+            Charset::Latin1 => text
+                .chars()
+                .map(|c| u8::try_from(c).ok())
+                .collect::<Option<Vec<u8>>>()
+                .map(Cow::Owned)
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "payload has characters outside Latin-1",
+                    )
+                }),
+            #[cfg(feature = "encoding")]
+            Charset::Encoding(encoding) => Ok(encoding.encode(text).0),
+        }
+    }
+
+    /// Encodes an owned payload, reusing its allocation when it needs no
+    /// transcoding. The text is zeroed if it was copied, with the `secrecy`
+    /// feature, since it may be a password.
+    pub(crate) fn encode_string(self, text: String) -> io::Result<Vec<u8>> {
+        let encoded = self.encode(&text)?;
+
+        if let Cow::Owned(bytes) = encoded {
+            #[cfg(feature = "secrecy")]
+            {
+                let mut text = text;
+                zeroize::Zeroize::zeroize(&mut text);
+            }
+
+            return Ok(bytes);
+        }
+
+        Ok(text.into_bytes())
+    }
+}
@@ -0,0 +1,496 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use bytes::Bytes;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{mpsc, oneshot},
+    time::Instant,
+};
+
+use crate::{
+    interceptor::Interceptors, monitor::Shared, text, transform, Charset, Connection,
+    ConnectionMonitor, ConnectionReceiver, ConnectionSender, Error, Event, Packet, PacketType,
+    Response, ResponseTransform, Result, State,
+};
+
+/// How many commands may wait to be written before callers have to wait too.
+const QUEUE_SIZE: usize = 64;
+
+/// A cloneable handle to a connection owned by background tasks, for running
+/// commands from many tasks at once.
+///
+/// Unlike [`SharedConnection`](crate::SharedConnection), commands are
+/// pipelined: each one is written as soon as it is queued, and responses are
+/// matched to their commands by packet id, so a slow command does not hold
+/// up the others. The connection is closed once every handle is dropped.
+///
+/// Created with [`Connection::client`], usually after authenticating. The
+/// connection's `multiple_responses`, `command_prefix`, `charset`,
+/// `response_transform`, `strip_formatting`, interceptors, `min_command_interval`,
+/// `rate_limit` and `command_deadline` (or `timeout`) settings carry over.
+///
+/// If the connection has a `keepalive_interval`, a harmless packet is sent
+/// whenever that long passes. Once the connection is found to be gone, its
+/// monitor's state becomes [`State::Closed`] and [`Event::Disconnected`] is
+/// emitted.
+#[derive(Debug, Clone)]
+pub struct RconClient {
+    requests: mpsc::Sender<Job>,
+    monitor: ConnectionMonitor,
+    deadline: Option<Duration>,
+}
+
+#[derive(Debug)]
+enum Job {
+    Command(Request),
+    Keepalive,
+}
+
+#[derive(Debug)]
+struct Request {
+    command: String,
+    reply: oneshot::Sender<Result<Response>>,
+}
+
+#[derive(Debug)]
+struct Pending {
+    /// The command as sent, for the interceptors' `after_receive`.
+    command: String,
+    request_id: i32,
+    sequence: u64,
+    started: Instant,
+    packets: Vec<Packet>,
+    reply: oneshot::Sender<Result<Response>>,
+}
+
+/// Commands written but not yet answered, shared by the writer and reader.
+#[derive(Debug, Default)]
+struct Inflight {
+    commands: HashMap<i32, Pending>,
+    /// Sentinel id to the id of the command it follows.
+    sentinels: HashMap<i32, i32>,
+    /// Ids whose remaining packets, such as the marker after a mirrored
+    /// sentinel, are dropped, with the sequence they were written in.
+    discard: HashMap<i32, u64>,
+    /// How many commands and keep-alives have been written.
+    written: u64,
+    closed: bool,
+}
+
+impl Inflight {
+    fn next_sequence(&mut self) -> u64 {
+        self.written += 1;
+        self.written
+    }
+
+    /// Forgets the discarded ids written before `sequence`: responses arrive
+    /// in order, so theirs are complete.
+    fn settle(&mut self, sequence: u64) {
+        self.discard.retain(|_, written| *written >= sequence);
+    }
+
+    /// Moves the commands whose callers stopped waiting, such as after a
+    /// timeout, to the ids whose packets are dropped.
+    fn abandon(&mut self) {
+        let abandoned: Vec<i32> = self
+            .commands
+            .iter()
+            .filter(|(_, pending)| pending.reply.is_closed())
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in abandoned {
+            let Some(pending) = self.commands.remove(&id) else {
+                continue;
+            };
+            self.discard.insert(id, pending.sequence);
+
+            let discard = &mut self.discard;
+            self.sentinels.retain(|sentinel, command| {
+                if *command == id {
+                    discard.insert(*sentinel, pending.sequence);
+                }
+                *command != id
+            });
+        }
+    }
+}
+
+impl RconClient {
+    pub(crate) fn new<T>(connection: Connection<T>) -> Self
+    where
+        T: Unpin + AsyncRead + AsyncWrite + Send + 'static,
+    {
+        let (requests, queue) = mpsc::channel(QUEUE_SIZE);
+        let inflight = Arc::new(Mutex::new(Inflight::default()));
+        let shared = connection.shared.clone();
+        let multi = connection.exec_options().multi;
+        let join = connection.quirks.joins_fragments();
+        let transform = connection.response_transform.clone();
+        let interceptors = connection.interceptors.clone();
+        let charset = connection.charset;
+        let strip_formatting = connection.strip_formatting;
+        let deadline = connection.command_deadline.or(connection.timeout);
+        let keepalive_interval = connection.keepalive_interval;
+        let keepalive_command = connection.keepalive_command.clone();
+        let (sender, receiver) = connection.split();
+
+        tokio::spawn(
+            Writer {
+                sender,
+                inflight: inflight.clone(),
+                shared: shared.clone(),
+                multi,
+                keepalive_command,
+            }
+            .run(queue),
+        );
+
+        if let Some(interval) = keepalive_interval {
+            tokio::spawn(keep_alive(requests.downgrade(), interval));
+        }
+
+        tokio::spawn(
+            Reader {
+                receiver,
+                inflight,
+                shared: shared.clone(),
+                transform,
+                interceptors,
+                charset,
+                strip_formatting,
+                multi,
+                join,
+            }
+            .run(),
+        );
+
+        RconClient {
+            requests,
+            monitor: ConnectionMonitor::new(shared),
+            deadline,
+        }
+    }
+
+    /// Executes a command on the server, alongside any other commands in
+    /// flight.
+    ///
+    /// Fails with [`Error::ConnectionClosed`] once the connection is gone,
+    /// and with [`Error::Timeout`] if a `command_deadline` was configured and
+    /// the response takes longer.
+    pub async fn execute_command(&self, command: &str) -> Result<Vec<String>> {
+        self.execute(command)
+            .await
+            .map(|response| response.payloads)
+    }
+
+    /// Executes a command like [`execute_command`](Self::execute_command),
+    /// returning the response with the packet ids and round-trip time it
+    /// arrived with.
+    pub async fn execute(&self, command: &str) -> Result<Response> {
+        let (reply, response) = oneshot::channel();
+        let request = Request {
+            command: command.to_string(),
+            reply,
+        };
+
+        self.requests
+            .send(Job::Command(request))
+            .await
+            .map_err(|_| Error::ConnectionClosed)?;
+
+        let response = async { response.await.unwrap_or(Err(Error::ConnectionClosed)) };
+
+        match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, response)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => response.await,
+        }
+    }
+
+    /// Returns a monitor for the underlying connection.
+    pub fn monitor(&self) -> ConnectionMonitor {
+        self.monitor.clone()
+    }
+
+    /// Whether the connection is gone, so every command fails.
+    #[cfg_attr(not(feature = "tower"), allow(dead_code))]
+    pub(crate) fn is_closed(&self) -> bool {
+        self.monitor.state() == State::Closed
+    }
+}
+
+/// Queues a keep-alive every `interval` until every handle is dropped.
+async fn keep_alive(requests: mpsc::WeakSender<Job>, interval: Duration) {
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+
+    loop {
+        ticks.tick().await;
+
+        let Some(requests) = requests.upgrade() else {
+            return;
+        };
+
+        if requests.send(Job::Keepalive).await.is_err() {
+            return;
+        }
+    }
+}
+
+struct Writer<T> {
+    sender: ConnectionSender<T>,
+    inflight: Arc<Mutex<Inflight>>,
+    shared: Arc<Shared>,
+    multi: bool,
+    keepalive_command: Option<String>,
+}
+
+/// The packets of the jobs taken from the queue at once, written together.
+#[derive(Default)]
+struct Batch {
+    packets: Vec<Packet>,
+    /// The commands whose packets are in the batch.
+    commands: Vec<i32>,
+    keepalive: bool,
+}
+
+impl<T: AsyncWrite> Writer<T> {
+    async fn run(mut self, mut queue: mpsc::Receiver<Job>) {
+        let mut batch = Batch::default();
+
+        while let Some(job) = queue.recv().await {
+            self.add(job, &mut batch).await;
+
+            // Whatever else is already queued goes out in the same write,
+            // unless pacing has to space the commands out.
+            while !self.sender.is_paced() && batch.packets.len() < QUEUE_SIZE {
+                match queue.try_recv() {
+                    Ok(job) => self.add(job, &mut batch).await,
+                    Err(_) => break,
+                }
+            }
+
+            self.write(&mut batch).await;
+        }
+
+        let _ = self.sender.shutdown().await;
+    }
+
+    async fn add(&mut self, job: Job, batch: &mut Batch) {
+        match job {
+            Job::Command(request) => self.add_command(request, batch).await,
+            Job::Keepalive => self.add_keepalive(batch),
+        }
+    }
+
+    async fn add_command(&mut self, request: Request, batch: &mut Batch) {
+        let command = match self.sender.prepare(&request.command) {
+            Ok(command) => command,
+            Err(error) => {
+                let _ = request.reply.send(Err(error));
+                return;
+            }
+        };
+
+        self.sender.pace().await;
+
+        let id = self.sender.new_packet_id();
+        let packet = match self.sender.command_packet(id, &command) {
+            Ok(packet) => packet,
+            Err(error) => {
+                let _ = request.reply.send(Err(error));
+                return;
+            }
+        };
+        let sentinel = self.multi.then(|| self.sender.new_packet_id());
+
+        // Registered before writing, so the reader knows the response
+        // however soon it arrives.
+        {
+            let mut inflight = self.inflight.lock().unwrap();
+
+            if inflight.closed {
+                let _ = request.reply.send(Err(Error::ConnectionClosed));
+                return;
+            }
+
+            inflight.abandon();
+            let sequence = inflight.next_sequence();
+            inflight.commands.insert(
+                id,
+                Pending {
+                    command,
+                    request_id: id,
+                    sequence,
+                    started: Instant::now(),
+                    packets: Vec::new(),
+                    reply: request.reply,
+                },
+            );
+
+            if let Some(sentinel) = sentinel {
+                inflight.sentinels.insert(sentinel, id);
+            }
+        }
+
+        batch.packets.push(packet);
+        batch.commands.push(id);
+
+        if let Some(sentinel) = sentinel {
+            batch
+                .packets
+                .push(Packet::new(sentinel, PacketType::Response, String::new()));
+        }
+    }
+
+    /// Queues the `keepalive_command`, or an empty `SERVERDATA_RESPONSE_VALUE`
+    /// that servers mirror back, and drops whatever answers it.
+    fn add_keepalive(&mut self, batch: &mut Batch) {
+        let id = self.sender.new_packet_id();
+        let packet = match &self.keepalive_command {
+            Some(command) => match self.sender.encode(command.clone()) {
+                Ok(payload) => Packet::new(id, PacketType::Message, payload),
+                Err(_) => return,
+            },
+            None => Packet::new(id, PacketType::Response, String::new()),
+        };
+
+        {
+            let mut inflight = self.inflight.lock().unwrap();
+
+            if inflight.closed {
+                return;
+            }
+
+            let sequence = inflight.next_sequence();
+            inflight.discard.insert(id, sequence);
+        }
+
+        batch.packets.push(packet);
+        batch.keepalive = true;
+    }
+
+    /// Writes the batch, failing its commands if the write fails, and
+    /// empties it.
+    async fn write(&mut self, batch: &mut Batch) {
+        if !batch.packets.is_empty() && self.sender.send_packets(&batch.packets).await.is_err() {
+            {
+                let mut inflight = self.inflight.lock().unwrap();
+
+                for id in &batch.commands {
+                    if let Some(pending) = inflight.commands.remove(id) {
+                        let _ = pending.reply.send(Err(Error::ConnectionClosed));
+                    }
+                }
+            }
+
+            if batch.keepalive {
+                disconnect(&self.inflight, &self.shared);
+            }
+        }
+
+        batch.packets.clear();
+        batch.commands.clear();
+        batch.keepalive = false;
+    }
+}
+
+/// Marks the connection as gone, emitting [`Event::Disconnected`] the first
+/// time, and fails every command still waiting.
+fn disconnect(inflight: &Mutex<Inflight>, shared: &Shared) {
+    let mut inflight = inflight.lock().unwrap();
+
+    if !inflight.closed {
+        inflight.closed = true;
+        shared.set_state(State::Closed);
+        shared.emit(Event::Disconnected);
+    }
+
+    for (_, pending) in inflight.commands.drain() {
+        let _ = pending.reply.send(Err(Error::ConnectionClosed));
+    }
+}
+
+struct Reader<T> {
+    receiver: ConnectionReceiver<T>,
+    inflight: Arc<Mutex<Inflight>>,
+    shared: Arc<Shared>,
+    transform: Option<ResponseTransform>,
+    interceptors: Interceptors,
+    charset: Charset,
+    strip_formatting: bool,
+    multi: bool,
+    join: bool,
+}
+
+impl<T: AsyncRead> Reader<T> {
+    async fn run(mut self) {
+        while let Ok(packet) = self.receiver.receive_packet().await {
+            self.route(packet);
+        }
+
+        disconnect(&self.inflight, &self.shared);
+    }
+
+    fn route(&mut self, packet: Packet) {
+        let mut inflight = self.inflight.lock().unwrap();
+
+        if let Some(id) = inflight.sentinels.remove(&packet.id) {
+            if let Some(pending) = inflight.commands.remove(&id) {
+                inflight.settle(pending.sequence);
+                inflight.discard.insert(packet.id, pending.sequence);
+                self.complete(pending);
+            }
+        } else if self.multi && inflight.commands.contains_key(&packet.id) {
+            if let Some(pending) = inflight.commands.get_mut(&packet.id) {
+                let sequence = pending.sequence;
+                pending.packets.push(packet);
+                inflight.settle(sequence);
+            }
+        } else if let Some(mut pending) = inflight.commands.remove(&packet.id) {
+            inflight.settle(pending.sequence);
+            pending.packets.push(packet);
+            self.complete(pending);
+        } else if let Some(&sequence) = inflight.discard.get(&packet.id) {
+            inflight.settle(sequence);
+        } else {
+            self.shared.unsolicited(packet);
+        }
+    }
+
+    fn complete(&self, pending: Pending) {
+        let latency = pending.started.elapsed();
+        let response_ids = pending.packets.iter().map(|packet| packet.id).collect();
+        let mut payloads: Vec<Bytes> = pending
+            .packets
+            .into_iter()
+            .map(|packet| packet.payload)
+            .collect();
+
+        if self.join && payloads.len() > 1 {
+            payloads = vec![payloads.concat().into()];
+        }
+
+        let response = transform::decode_payloads(self.transform.as_ref(), self.charset, payloads)
+            .map(|payloads| text::strip_payloads(self.strip_formatting, payloads))
+            .and_then(|mut payloads| {
+                self.interceptors
+                    .after_receive(&pending.command, &mut payloads)?;
+                Ok(payloads)
+            })
+            .map(|payloads| Response {
+                body: payloads.concat(),
+                payloads,
+                request_id: pending.request_id,
+                response_ids,
+                latency,
+            });
+
+        let _ = pending.reply.send(response);
+    }
+}
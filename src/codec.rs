@@ -0,0 +1,216 @@
+//! The wire format, as a codec that can also be used with
+//! [`Framed`](tokio_util::codec::Framed) when the `codec` feature is enabled.
+
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+#[cfg(feature = "codec")]
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{packet::Header, Error, Framing, Packet, PrefixWidth, Result};
+
+/// The largest length field accepted in an incoming packet by default.
+pub const DEFAULT_MAX_INCOMING_PACKET_SIZE: usize = 1024 * 1024;
+
+/// Encodes and decodes packets with the given [`Framing`].
+///
+/// Decoded packets have their type read as a server response, so type 2 is
+/// [`PacketType::AuthenticationResponse`](crate::PacketType::AuthenticationResponse).
+/// [`Connection`](crate::Connection) uses the same codec internally.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RconCodec {
+    framing: Framing,
+    max_incoming_packet_size: usize,
+    /// Terminator bytes of the last packet that had not arrived when it was
+    /// decoded, skipped if they turn up.
+    owed_nulls: u8,
+}
+
+impl Default for RconCodec {
+    fn default() -> Self {
+        RconCodec::new(Framing::default())
+    }
+}
+
+impl RconCodec {
+    /// Creates a codec with the given framing.
+    pub fn new(framing: Framing) -> Self {
+        RconCodec {
+            framing,
+            max_incoming_packet_size: DEFAULT_MAX_INCOMING_PACKET_SIZE,
+            owed_nulls: 0,
+        }
+    }
+
+    /// Sets the largest length field accepted when decoding. Longer packets
+    /// fail with [`Error::MalformedPacket`] before anything is allocated for
+    /// them.
+    pub fn with_max_incoming_packet_size(mut self, max: usize) -> Self {
+        self.max_incoming_packet_size = max;
+        self
+    }
+
+    /// Returns the framing the codec uses.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Returns the largest length field accepted when decoding.
+    pub fn max_incoming_packet_size(&self) -> usize {
+        self.max_incoming_packet_size
+    }
+
+    /// Carries the terminator bytes still owed over from a codec that
+    /// decoded from the same stream.
+    pub(crate) fn owing(mut self, nulls: u8) -> Self {
+        self.owed_nulls = nulls;
+        self
+    }
+
+    pub(crate) fn owed_nulls(&self) -> u8 {
+        self.owed_nulls
+    }
+
+    /// Appends the packet's wire bytes to `dst`, returning how many were
+    /// written.
+    pub fn encode_packet(&self, packet: &Packet, dst: &mut BytesMut) -> io::Result<usize> {
+        let length = (packet.payload.len() + self.framing.overhead()) as i32;
+        let total = self.framing.prefix_width.len() + length as usize;
+
+        dst.reserve(total);
+
+        match self.framing.prefix_width {
+            PrefixWidth::Two => {
+                let length = u16::try_from(length).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "packet too long for a 2-byte length prefix",
+                    )
+                })?;
+                dst.put_u16_le(length);
+            }
+            PrefixWidth::Four => dst.put_i32_le(length),
+        }
+
+        dst.put_i32_le(packet.id);
+        dst.put_i32_le(packet.packet_type.format());
+        dst.put_slice(&packet.payload);
+
+        // Ending empty strings
+        dst.put_bytes(0x00, self.framing.trailing_nulls as usize);
+
+        Ok(total)
+    }
+
+    /// Removes a complete packet from the front of `buffer`, returning `None`
+    /// if it does not hold one yet.
+    ///
+    /// The payload is a slice of the buffer's bytes, not a copy, and is not
+    /// checked to be text. A length equal to the overhead is an empty
+    /// payload; like any other packet its terminator is only consumed if it
+    /// is actually NUL, unless `strict_terminator` is set. A length shorter
+    /// than the overhead or longer than `max_incoming_packet_size` fails with
+    /// [`Error::MalformedPacket`], leaving the buffer as it was.
+    ///
+    /// Without `strict_terminator`, a packet is decoded as soon as its
+    /// payload is in the buffer, so one whose terminator never comes is not
+    /// left waiting. The codec remembers the terminator bytes that have not
+    /// arrived yet and skips them if they are the next bytes decoded.
+    pub fn decode_packet(&mut self, buffer: &mut BytesMut) -> Result<Option<Packet>> {
+        while self.owed_nulls > 0 && buffer.first() == Some(&0x00) {
+            buffer.advance(1);
+            self.owed_nulls -= 1;
+        }
+
+        if !buffer.is_empty() {
+            self.owed_nulls = 0;
+        }
+
+        let header = match Header::peek(buffer, self.framing) {
+            Some(header) => header,
+            None => {
+                buffer.reserve(self.framing.prefix_width.len() + self.framing.overhead());
+                return Ok(None);
+            }
+        };
+
+        if header.length < self.framing.overhead() as i32 {
+            return Err(Error::MalformedPacket("packet length too short"));
+        }
+
+        if header.length as usize > self.max_incoming_packet_size {
+            return Err(Error::MalformedPacket("packet length too long"));
+        }
+
+        let prefix = self.framing.prefix_width.len();
+        let nulls = self.framing.trailing_nulls as usize;
+        let total = prefix + header.length as usize;
+        let end = total - nulls;
+        let needed = if self.framing.strict_terminator {
+            total
+        } else {
+            end
+        };
+
+        if buffer.len() < needed {
+            buffer.reserve(total - buffer.len());
+            return Ok(None);
+        }
+
+        // The terminator bytes that have arrived, all of them when strict.
+        let arrived = &buffer[end..buffer.len().min(total)];
+        let terminated = arrived.iter().all(|&byte| byte == 0x00);
+
+        if !terminated && self.framing.strict_terminator {
+            return Err(Error::MalformedPacket("missing packet terminator"));
+        }
+
+        // Skip ending empty strings, if the server sent them
+        let consumed = match terminated {
+            true => {
+                self.owed_nulls = (nulls - arrived.len()) as u8;
+                end + arrived.len()
+            }
+            false => end,
+        };
+        let frame = buffer.split_to(consumed).freeze();
+        let payload = frame.slice(prefix + 8..end);
+
+        Ok(Some(Packet {
+            id: header.id,
+            length: header.length,
+            packet_type: header.packet_type,
+            payload,
+        }))
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Encoder<Packet> for RconCodec {
+    type Error = Error;
+
+    fn encode(&mut self, packet: Packet, dst: &mut BytesMut) -> Result<()> {
+        self.encode_packet(&packet, dst)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Encoder<&Packet> for RconCodec {
+    type Error = Error;
+
+    fn encode(&mut self, packet: &Packet, dst: &mut BytesMut) -> Result<()> {
+        self.encode_packet(packet, dst)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Decoder for RconCodec {
+    type Item = Packet;
+    type Error = Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Packet>> {
+        self.decode_packet(src)
+    }
+}
@@ -0,0 +1,42 @@
+use crate::Result;
+
+/// A command with a typed response, run with
+/// [`Connection::run`](crate::Connection::run).
+///
+/// Implemented by games and libraries building typed command sets on top of
+/// this crate. Parse failures should be reported as
+/// [`Error::UnexpectedResponse`](crate::Error::UnexpectedResponse).
+///
+/// ```
+/// use specul::parse::{parse_minecraft_list, MinecraftPlayer};
+/// use specul::Command;
+///
+/// struct ListPlayers;
+///
+/// impl Command for ListPlayers {
+///     type Output = Vec<MinecraftPlayer>;
+///
+///     fn to_command_string(&self) -> String {
+///         "list".to_string()
+///     }
+///
+///     fn parse_response(response: &str) -> specul::Result<Self::Output> {
+///         Ok(parse_minecraft_list(response).players)
+///     }
+/// }
+///
+/// let players =
+///     ListPlayers::parse_response("There are 1 of a max of 20 players online: Steve")?;
+/// assert_eq!(players[0].name, "Steve");
+/// # Ok::<(), specul::Error>(())
+/// ```
+pub trait Command {
+    /// What the response parses into.
+    type Output;
+
+    /// Returns the command as sent to the server.
+    fn to_command_string(&self) -> String;
+
+    /// Parses the response, joined into one string.
+    fn parse_response(response: &str) -> Result<Self::Output>;
+}
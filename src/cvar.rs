@@ -0,0 +1,210 @@
+use std::fmt;
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::Instant,
+};
+
+use crate::{parse::parse_pairs, Connection, Error, Result};
+
+/// The value of a console variable, typed by how it reads.
+///
+/// Source prints booleans as `0` and `1`, so they read as
+/// [`Int`](CvarValue::Int); use [`as_bool`](CvarValue::as_bool) for those.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl CvarValue {
+    /// Types `text` as the first of a boolean (`true` or `false`), an
+    /// integer or a float that it parses as, or else a string.
+    pub fn parse(text: &str) -> Self {
+        match text {
+            "true" => CvarValue::Bool(true),
+            "false" => CvarValue::Bool(false),
+            text => match (text.parse(), text.parse()) {
+                (Ok(int), _) => CvarValue::Int(int),
+                (_, Ok(float)) => CvarValue::Float(float),
+                _ => CvarValue::String(text.to_string()),
+            },
+        }
+    }
+
+    /// Returns the value as a boolean, reading `0` and `1` as Source does.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CvarValue::Bool(value) => Some(*value),
+            CvarValue::Int(0) => Some(false),
+            CvarValue::Int(1) => Some(true),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            CvarValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value as a float, converting integers.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            CvarValue::Int(value) => Some(*value as f64),
+            CvarValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CvarValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// Formats the value as it is set: booleans as `0` or `1`.
+impl fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CvarValue::Bool(value) => write!(f, "{}", u8::from(*value)),
+            CvarValue::Int(value) => write!(f, "{}", value),
+            CvarValue::Float(value) => write!(f, "{}", value),
+            CvarValue::String(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<bool> for CvarValue {
+    fn from(value: bool) -> Self {
+        CvarValue::Bool(value)
+    }
+}
+
+impl From<i64> for CvarValue {
+    fn from(value: i64) -> Self {
+        CvarValue::Int(value)
+    }
+}
+
+impl From<i32> for CvarValue {
+    fn from(value: i32) -> Self {
+        CvarValue::Int(value.into())
+    }
+}
+
+impl From<f64> for CvarValue {
+    fn from(value: f64) -> Self {
+        CvarValue::Float(value)
+    }
+}
+
+impl From<&str> for CvarValue {
+    fn from(value: &str) -> Self {
+        CvarValue::String(value.to_string())
+    }
+}
+
+impl From<String> for CvarValue {
+    fn from(value: String) -> Self {
+        CvarValue::String(value)
+    }
+}
+
+impl<T> Connection<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    /// Reads a console variable, parsing responses such as
+    /// `"sv_cheats" = "0" ( def. "0" )` and CS2's `sv_cheats = false`.
+    ///
+    /// The variable is read without the connection's `command_prefix`.
+    /// Values read within the last `cvar_cache_ttl` are returned without
+    /// asking the server. Fails with [`Error::InvalidCommand`] if the name
+    /// could be read as more than one command, and with
+    /// [`Error::UnexpectedResponse`] if the server does not know the
+    /// variable.
+    pub async fn get_cvar(&mut self, name: &str) -> Result<CvarValue> {
+        check_cvar_name(name)?;
+
+        if let (Some(ttl), Some((value, fetched))) = (self.cvar_cache_ttl, self.cvars.get(name)) {
+            if fetched.elapsed() < ttl {
+                return Ok(value.clone());
+            }
+        }
+
+        let response = self.execute_unprefixed(name).await?.concat();
+        let value = parse_pairs(&response, &['='])
+            .remove(name)
+            .map(|value| CvarValue::parse(&value))
+            .ok_or(Error::UnexpectedResponse(response))?;
+
+        self.cache_cvar(name, value.clone());
+        Ok(value)
+    }
+
+    /// Sets a console variable with `<name> "<value>"`, without the
+    /// connection's `command_prefix`.
+    ///
+    /// Servers may report the value differently than it was set, such as a
+    /// boolean as `1`, so any cached value is forgotten and the next
+    /// [`get_cvar`](Self::get_cvar) asks the server. Fails with
+    /// [`Error::InvalidCommand`] if the name or value could be read as more
+    /// than one command, and with [`Error::UnexpectedResponse`] if the server
+    /// does not know the variable.
+    pub async fn set_cvar(&mut self, name: &str, value: impl Into<CvarValue>) -> Result<()> {
+        check_cvar_name(name)?;
+        let text = value.into().to_string();
+
+        if text.contains(['"', ';', '\n']) {
+            return Err(Error::InvalidCommand(format!(
+                "cvar value {:?} contains a quote, ; or newline",
+                text
+            )));
+        }
+
+        let response = self
+            .execute_unprefixed(&format!("{} \"{}\"", name, text))
+            .await?
+            .concat();
+        self.cvars.remove(name);
+
+        if response.starts_with("Unknown command") {
+            return Err(Error::UnexpectedResponse(response));
+        }
+
+        Ok(())
+    }
+
+    /// Forgets the cached value of a console variable.
+    pub fn invalidate_cvar(&mut self, name: &str) {
+        self.cvars.remove(name);
+    }
+
+    /// Forgets every cached console variable.
+    pub fn invalidate_cvars(&mut self) {
+        self.cvars.clear();
+    }
+
+    fn cache_cvar(&mut self, name: &str, value: CvarValue) {
+        if self.cvar_cache_ttl.is_some() {
+            self.cvars.insert(name.to_string(), (value, Instant::now()));
+        }
+    }
+}
+
+fn check_cvar_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.contains(|c: char| c.is_whitespace() || c == '"' || c == ';') {
+        return Err(Error::InvalidCommand(format!(
+            "invalid cvar name {:?}",
+            name
+        )));
+    }
+
+    Ok(())
+}
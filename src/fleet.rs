@@ -0,0 +1,259 @@
+//! Running commands across many servers at once.
+//!
+//! ```no_run
+//! # async fn run() {
+//! use specul::fleet::{Fleet, ServerConfig};
+//!
+//! let mut fleet = Fleet::new();
+//! fleet.insert("eu1", ServerConfig::new("eu1.example.com:27015", "password"));
+//! fleet.insert("eu2", ServerConfig::new("eu2.example.com:27015", "password"));
+//! fleet.insert("us1", ServerConfig::new("us1.example.com:27015", "password"));
+//!
+//! for (name, result) in fleet.execute_all("say restart in 5m").await {
+//!     if let Err(error) = result {
+//!         eprintln!("{}: {}", name, error);
+//!     }
+//! }
+//!
+//! fleet.execute_on(&["eu1", "eu2"], "changelevel de_dust2").await;
+//! # }
+//! ```
+
+use std::{collections::BTreeMap, fmt, sync::Arc, time::Duration};
+
+use tokio::{net::TcpStream, sync::Mutex, task::JoinSet};
+
+use crate::{reconnect::Password, Connection, ConnectionBuilder, ConnectionConfig, Error, Result};
+
+type Configure =
+    Arc<dyn Fn(ConnectionBuilder<TcpStream>) -> ConnectionBuilder<TcpStream> + Send + Sync>;
+
+/// Where a server is, how to authenticate with it and how to talk to it.
+#[derive(Clone)]
+pub struct ServerConfig {
+    addr: String,
+    password: Password,
+    configure: Option<Configure>,
+}
+
+impl ServerConfig {
+    /// A server at `addr`, given as `host:port`, with the default connection
+    /// settings.
+    pub fn new(addr: impl Into<String>, password: &str) -> Self {
+        ServerConfig {
+            addr: addr.into(),
+            password: Password::new(password),
+            configure: None,
+        }
+    }
+
+    /// The server a [`ConnectionConfig`] describes, connected to with its
+    /// options, such as its `quirks`, `command_prefix` and timeouts.
+    pub fn from_config(config: ConnectionConfig) -> Self {
+        ServerConfig::new(config.addr(), &config.password)
+            .configure(move |builder| config.apply(builder))
+    }
+
+    /// Adds settings the server's connections are built with, such as its
+    /// `framing`, after any set before. Settings that do not build make the
+    /// server's commands fail with an
+    /// [`InvalidInput`](std::io::ErrorKind::InvalidInput) io error.
+    ///
+    /// ```
+    /// use specul::{fleet::ServerConfig, Framing};
+    ///
+    /// let server = ServerConfig::new("10.0.0.5:25575", "password").configure(|builder| {
+    ///     builder.framing(Framing {
+    ///         trailing_nulls: 1,
+    ///         ..Framing::default()
+    ///     })
+    /// });
+    /// ```
+    pub fn configure(
+        mut self,
+        configure: impl Fn(ConnectionBuilder<TcpStream>) -> ConnectionBuilder<TcpStream>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.configure = Some(match self.configure.take() {
+            Some(before) => Arc::new(move |builder| configure(before(builder))),
+            None => Arc::new(configure),
+        });
+        self
+    }
+
+    /// Returns the server's address.
+    pub fn addr(&self) -> &str {
+        &self.addr
+    }
+}
+
+impl fmt::Debug for ServerConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServerConfig")
+            .field("addr", &self.addr)
+            .field("password", &self.password)
+            .field("configured", &self.configure.is_some())
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct Server {
+    config: ServerConfig,
+    connection: Mutex<Option<Connection<TcpStream>>>,
+}
+
+impl Server {
+    /// Executes `command`, connecting first if there is no connection yet or
+    /// the last one failed.
+    async fn execute(&self, command: &str, timeout: Duration) -> Result<Vec<String>> {
+        let mut slot = self.connection.lock().await;
+
+        let result = tokio::time::timeout(timeout, async {
+            let connection = match slot.take() {
+                Some(connection) => connection,
+                None => self.connect().await?,
+            };
+
+            slot.insert(connection).execute_command(command).await
+        })
+        .await
+        .unwrap_or(Err(Error::Timeout));
+
+        if let Err(error) = &result {
+            if !error.leaves_connection_usable() {
+                *slot = None;
+            }
+        }
+
+        result
+    }
+
+    async fn connect(&self) -> Result<Connection<TcpStream>> {
+        let configure = self.config.configure.clone();
+        let mut connection =
+            Connection::dial_with(self.config.addr(), move |builder| match &configure {
+                Some(configure) => configure(builder),
+                None => builder,
+            })
+            .await?;
+
+        connection
+            .authenticate(self.config.password.expose())
+            .await?;
+        Ok(connection)
+    }
+}
+
+/// A set of named servers, each connected on first use and reconnected on
+/// the next command after its connection fails.
+///
+/// Commands run on every targeted server at once, and each server's result
+/// is reported separately, so one unreachable server does not hide the
+/// others' responses.
+#[derive(Debug)]
+pub struct Fleet {
+    servers: BTreeMap<String, Arc<Server>>,
+    timeout: Duration,
+}
+
+impl Fleet {
+    /// Creates an empty fleet.
+    pub fn new() -> Self {
+        Fleet {
+            servers: BTreeMap::new(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+
+    /// Sets how long each server may take to connect and respond before its
+    /// result is [`Error::Timeout`]. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Adds a server, replacing and disconnecting any of the same name.
+    pub fn insert(&mut self, name: impl Into<String>, config: ServerConfig) {
+        let server = Server {
+            config,
+            connection: Mutex::new(None),
+        };
+
+        self.servers.insert(name.into(), Arc::new(server));
+    }
+
+    /// Removes a server, disconnecting it once any command running on it
+    /// finishes.
+    pub fn remove(&mut self, name: &str) -> Option<ServerConfig> {
+        self.servers
+            .remove(name)
+            .map(|server| server.config.clone())
+    }
+
+    /// Returns the servers' names, in order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.servers.keys().map(String::as_str)
+    }
+
+    /// Executes `command` on every server.
+    pub async fn execute_all(&self, command: &str) -> BTreeMap<String, Result<Vec<String>>> {
+        self.execute(self.servers.keys().map(String::as_str), command)
+            .await
+    }
+
+    /// Executes `command` on the named servers. Names not in the fleet get
+    /// [`Error::UnknownServer`].
+    pub async fn execute_on(
+        &self,
+        names: &[&str],
+        command: &str,
+    ) -> BTreeMap<String, Result<Vec<String>>> {
+        self.execute(names.iter().copied(), command).await
+    }
+
+    async fn execute<'a>(
+        &self,
+        names: impl Iterator<Item = &'a str>,
+        command: &str,
+    ) -> BTreeMap<String, Result<Vec<String>>> {
+        let mut results = BTreeMap::new();
+        let mut tasks = JoinSet::new();
+
+        for name in names {
+            let Some(server) = self.servers.get(name) else {
+                results.insert(
+                    name.to_string(),
+                    Err(Error::UnknownServer(name.to_string())),
+                );
+                continue;
+            };
+
+            let server = server.clone();
+            let name = name.to_string();
+            let command = command.to_string();
+            let timeout = self.timeout;
+
+            tasks.spawn(async move { (name, server.execute(&command, timeout).await) });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((name, result)) => {
+                    results.insert(name, result);
+                }
+                Err(error) => std::panic::resume_unwind(error.into_panic()),
+            }
+        }
+
+        results
+    }
+}
+
+impl Default for Fleet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
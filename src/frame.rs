@@ -0,0 +1,214 @@
+use std::{fmt, sync::Arc};
+
+use crate::{ConnectionBuilder, Framing, Packet, PacketHeader};
+
+/// Which way a [`Frame`] went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FrameDirection {
+    Sent,
+    Received,
+}
+
+/// A packet as it went over the wire, passed to the builder's
+/// [`on_frame`](crate::ConnectionBuilder::on_frame) hook.
+///
+/// Displayed as its header and a hexdump of its first
+/// [`DEFAULT_DUMP_LIMIT`](Frame::DEFAULT_DUMP_LIMIT) bytes:
+///
+/// ```text
+/// > sent id 0, type Authentication, length 17, 21 bytes
+/// 0000  11 00 00 00 00 00 00 00  03 00 00 00 2a 2a 2a 2a  |............****|
+/// 0010  2a 2a 2a 00 00                                    |***..|
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Frame {
+    pub direction: FrameDirection,
+    pub header: PacketHeader,
+    /// Every byte of the packet, with the payload of authentication packets
+    /// replaced by `*`, so passwords are not logged.
+    pub bytes: Vec<u8>,
+}
+
+impl Frame {
+    /// How many bytes of a frame its [`Display`](fmt::Display) shows.
+    pub const DEFAULT_DUMP_LIMIT: usize = 256;
+
+    /// Describes a packet encoded as `bytes`, masking its payload if it is a
+    /// password.
+    fn new(direction: FrameDirection, packet: &Packet, bytes: &[u8], framing: Framing) -> Self {
+        let mut bytes = bytes.to_vec();
+
+        if packet.holds_password() {
+            let start = (framing.prefix_width.len() + 8).min(bytes.len());
+            let end = (start + packet.payload.len()).min(bytes.len());
+            bytes[start..end].fill(b'*');
+        }
+
+        Frame {
+            direction,
+            header: packet.header(),
+            bytes,
+        }
+    }
+
+    /// Returns the header and a hexdump of the first `limit` bytes, with the
+    /// number of bytes left out.
+    pub fn dump(&self, limit: usize) -> String {
+        let (arrow, verb) = match self.direction {
+            FrameDirection::Sent => ('>', "sent"),
+            FrameDirection::Received => ('<', "received"),
+        };
+
+        let mut dump = format!(
+            "{} {} id {}, type {:?}, length {}, {} bytes\n",
+            arrow,
+            verb,
+            self.header.id,
+            self.header.packet_type,
+            self.header.length,
+            self.bytes.len()
+        );
+
+        let shown = &self.bytes[..self.bytes.len().min(limit)];
+
+        for (row, bytes) in shown.chunks(16).enumerate() {
+            let mut hex = String::new();
+
+            for (i, byte) in bytes.iter().enumerate() {
+                if i == 8 {
+                    hex.push(' ');
+                }
+                hex.push_str(&format!("{:02x} ", byte));
+            }
+
+            let text: String = bytes
+                .iter()
+                .map(|&byte| match byte {
+                    0x20..=0x7e => byte as char,
+                    _ => '.',
+                })
+                .collect();
+
+            dump.push_str(&format!("{:04x}  {:<49} |{}|\n", row * 16, hex, text));
+        }
+
+        if shown.len() < self.bytes.len() {
+            dump.push_str(&format!(
+                "... {} more bytes\n",
+                self.bytes.len() - shown.len()
+            ));
+        }
+
+        dump
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.dump(Frame::DEFAULT_DUMP_LIMIT).trim_end())
+    }
+}
+
+type HookFn = dyn Fn(&Frame) + Send + Sync;
+
+/// The builder's `on_frame` hook.
+#[derive(Clone)]
+pub(crate) struct FrameHook(Arc<HookFn>);
+
+impl FrameHook {
+    fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&Frame) + Send + Sync + 'static,
+    {
+        FrameHook(Arc::new(hook))
+    }
+}
+
+impl<T> ConnectionBuilder<T> {
+    /// Calls `hook` with every packet sent and received, as a [`Frame`], for
+    /// debugging what goes over the wire. Passwords are masked.
+    ///
+    /// With the `tracing` feature, frames are also emitted as trace events
+    /// with the target `specul::frame`, whether or not a hook is set.
+    ///
+    /// ```
+    /// use specul::ConnectionBuilder;
+    ///
+    /// let builder = ConnectionBuilder::<tokio::net::TcpStream>::default()
+    ///     .on_frame(|frame| eprintln!("{}", frame.dump(64)));
+    /// ```
+    pub fn on_frame<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Frame) + Send + Sync + 'static,
+    {
+        self.on_frame = Some(Some(FrameHook::new(hook)));
+        self
+    }
+}
+
+impl fmt::Debug for FrameHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("FrameHook")
+    }
+}
+
+/// Whether frames are wanted, by a hook or by a `tracing` subscriber at the
+/// trace level, so the bytes of received packets need keeping.
+pub(crate) fn wanted(hook: Option<&FrameHook>) -> bool {
+    #[cfg(feature = "tracing")]
+    if tracing::enabled!(target: "specul::frame", tracing::Level::TRACE) {
+        return true;
+    }
+
+    hook.is_some()
+}
+
+/// Passes a packet, encoded as `bytes`, to the hook and to `tracing`.
+pub(crate) fn emit(
+    hook: Option<&FrameHook>,
+    direction: FrameDirection,
+    packet: &Packet,
+    bytes: &[u8],
+    framing: Framing,
+) {
+    if !wanted(hook) {
+        return;
+    }
+
+    let frame = Frame::new(direction, packet, bytes, framing);
+
+    #[cfg(feature = "tracing")]
+    tracing::trace!(target: "specul::frame", "{}", frame);
+
+    if let Some(hook) = hook {
+        (hook.0)(&frame);
+    }
+}
+
+/// Passes packets written one after another as `bytes`, each taking the
+/// matching count of bytes in `written`, to the hook and to `tracing`.
+pub(crate) fn emit_sent<'a>(
+    hook: Option<&FrameHook>,
+    packets: impl IntoIterator<Item = &'a Packet>,
+    bytes: &[u8],
+    written: &[usize],
+    framing: Framing,
+) {
+    if !wanted(hook) {
+        return;
+    }
+
+    let mut start = 0;
+
+    for (packet, &written) in packets.into_iter().zip(written) {
+        let end = start + written;
+        emit(
+            hook,
+            FrameDirection::Sent,
+            packet,
+            &bytes[start..end],
+            framing,
+        );
+        start = end;
+    }
+}
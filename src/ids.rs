@@ -0,0 +1,71 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+};
+
+/// How packet ids are chosen for commands, selected with the builder's
+/// `id_strategy`.
+///
+/// Whatever the strategy, the `keepalive_id` is never used, and neither is
+/// `-1` unless counted up to from a negative `current_packet_id`, since
+/// servers answer failed authentication with that id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IdStrategy {
+    /// Count up from `current_packet_id`, wrapping to `default_packet_id`
+    /// after `i32::MAX`.
+    #[default]
+    Sequential,
+    /// A random non-negative id for every packet, so that responses to
+    /// commands this connection did not send are unlikely to match.
+    Random,
+    /// The same id for every packet, for servers that answer with a fixed id
+    /// whatever was sent. Responses cannot be told apart by id, so this
+    /// cannot be combined with `multiple_responses` or quirks that follow
+    /// commands with a sentinel, and only one command can be in flight:
+    /// [`Connection::client`](crate::Connection::client),
+    /// [`fire`](crate::Connection::fire) and batches of more than one
+    /// command fail with [`Error::Unsupported`](crate::Error::Unsupported).
+    Constant(i32),
+}
+
+impl IdStrategy {
+    /// Returns the next id, advancing the counter of sequential ids.
+    pub(crate) fn next(self, current: &mut i32, default: i32, keepalive: Option<i32>) -> i32 {
+        loop {
+            let id = match self {
+                IdStrategy::Sequential => {
+                    let id = *current;
+                    *current = current.checked_add(1).unwrap_or(default);
+                    id
+                }
+                IdStrategy::Random => random_id(),
+                IdStrategy::Constant(id) => return id,
+            };
+
+            if Some(id) != keepalive {
+                return id;
+            }
+        }
+    }
+
+    pub(crate) fn check(self, keepalive: Option<i32>, sentinel: bool) -> Result<(), String> {
+        match self {
+            IdStrategy::Constant(-1) => Err("a constant packet id cannot be -1".to_string()),
+            IdStrategy::Constant(id) if Some(id) == keepalive => {
+                Err("a constant packet id cannot be the keepalive_id".to_string())
+            }
+            IdStrategy::Constant(_) if sentinel => {
+                Err("a constant packet id cannot be used with sentinels".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Every `RandomState` is keyed differently, so hashing nothing with a fresh
+/// one gives an unpredictable value.
+fn random_id() -> i32 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 33) as i32
+}
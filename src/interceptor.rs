@@ -0,0 +1,91 @@
+use std::{fmt, sync::Arc};
+
+use crate::{ConnectionBuilder, Result};
+
+/// Inspects or rewrites every command before it is sent and every response
+/// after it is received, for concerns such as audit logging or filtering
+/// that apply to all commands.
+///
+/// Interceptors are added to a connection with
+/// [`ConnectionBuilder::interceptor`] and form a stack: `before_send` runs in
+/// the order they were added, and `after_receive` in reverse, so the last
+/// interceptor added is closest to the server: it sees each command just
+/// before it is sent, and each response first.
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use specul::{Error, Interceptor, Result};
+///
+/// /// Refuses to run `quit`, and counts the commands that do run.
+/// #[derive(Default)]
+/// struct Audit {
+///     commands: AtomicUsize,
+/// }
+///
+/// impl Interceptor for Audit {
+///     fn before_send(&self, command: &mut String) -> Result<()> {
+///         if command.trim() == "quit" {
+///             return Err(Error::Rejected(command.clone()));
+///         }
+///
+///         self.commands.fetch_add(1, Ordering::Relaxed);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait Interceptor: Send + Sync {
+    /// Called with each command, after any `command_prefix` is added. Failing
+    /// stops the command from being sent, and the error is returned to the
+    /// caller.
+    fn before_send(&self, command: &mut String) -> Result<()> {
+        let _ = command;
+        Ok(())
+    }
+
+    /// Called with the payloads of each command's response, after any
+    /// `response_transform` and `strip_formatting`. Failing discards the
+    /// response, and the error is returned to the caller.
+    fn after_receive(&self, command: &str, response: &mut Vec<String>) -> Result<()> {
+        let _ = (command, response);
+        Ok(())
+    }
+}
+
+/// The interceptors of a connection, in the order they were added.
+#[derive(Clone, Default)]
+pub(crate) struct Interceptors(Vec<Arc<dyn Interceptor>>);
+
+impl Interceptors {
+    pub(crate) fn before_send(&self, command: &mut String) -> Result<()> {
+        self.0
+            .iter()
+            .try_for_each(|interceptor| interceptor.before_send(command))
+    }
+
+    pub(crate) fn after_receive(&self, command: &str, response: &mut Vec<String>) -> Result<()> {
+        self.0
+            .iter()
+            .rev()
+            .try_for_each(|interceptor| interceptor.after_receive(command, response))
+    }
+}
+
+impl fmt::Debug for Interceptors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interceptors")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+impl<T> ConnectionBuilder<T> {
+    /// Adds an [`Interceptor`] on top of those already added.
+    pub fn interceptor(mut self, interceptor: impl Interceptor + 'static) -> Self {
+        self.interceptors
+            .get_or_insert_with(Interceptors::default)
+            .0
+            .push(Arc::new(interceptor));
+        self
+    }
+}
@@ -1,15 +1,15 @@
 //! A simple client for the [RCON](https://developer.valvesoftware.com/wiki/Source_RCON_Protocol) protocol.
 //! # Example
 //! ```no_run
-//! use rcon::{Connection, ConnectionBuilder};
+//! use specul::ConnectionBuilder;
 //! use tokio::net::TcpStream;
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let tcp = TcpStream::connect(0.0.0.0:8080).await?;
-//!     let mut connection = Connection::default().io(tcp).build()?;
+//!     let tcp = TcpStream::connect("127.0.0.1:27015").await?;
+//!     let mut connection = ConnectionBuilder::default().io(tcp).build()?;
 //!
-//!     connection.authenticate().await?;
+//!     connection.authenticate("password").await?;
 //!
 //!     let response = connection.execute_command("status").await?;
 //!
@@ -18,15 +18,126 @@
 //!   Ok(())
 //! }
 //! ```
+//!
+//! # Runtime
+//!
+//! Connections must be used from within a Tokio runtime with the time
+//! driver enabled, such as one built by `#[tokio::main]` or with
+//! `enable_time`. The io may be any [`AsyncRead`](tokio::io::AsyncRead) and
+//! [`AsyncWrite`](tokio::io::AsyncWrite) stream, but timeouts, draining,
+//! command pacing, the wait in [`close`](Connection::close), reconnection
+//! backoff and the authentication handshake all run on Tokio's timers, which
+//! panic outside such a runtime. [`Connection::client`] and the `fleet` also
+//! spawn tasks onto it.
 
-use std::io;
+// `err-derive` expands its impls inside an anonymous const.
+#![allow(non_local_definitions)]
 
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::Arc,
+    task::{ready, Context, Poll},
+    time::Duration,
+};
+
+use bytes::{Bytes, BytesMut};
+use codec::RconCodec;
 use derive_builder::Builder;
 use err_derive::Error;
-use packet::{Packet, PacketType};
-use tokio::io::{AsyncRead, AsyncWrite};
+use packet::Header;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf},
+    time::Instant,
+};
+
+pub use charset::Charset;
+#[cfg(feature = "client")]
+pub use client::RconClient;
+pub use command::Command;
+pub use cvar::CvarValue;
+pub use frame::{Frame, FrameDirection};
+pub use ids::IdStrategy;
+pub use interceptor::Interceptor;
+use interceptor::Interceptors;
+pub use metrics::Metrics;
+pub use monitor::{ConnectionMonitor, ConsoleItem, Event, Stats};
+pub use packet::{
+    Framing, Packet, PacketHeader, PacketType, PacketTypeIds, PrefixWidth, WireConfig,
+};
+pub use quirks::Quirks;
+use rate_limit::Pacing;
+use reconnect::Password;
+pub use reconnect::{Backoff, Connector, RetryPolicy};
+pub use response::Response;
+pub use script::{OnError, ScriptLine, ScriptOptions};
+pub use shared::SharedConnection;
+pub use split::{ConnectionReceiver, ConnectionSender};
+#[cfg(feature = "stream")]
+pub use stream::PacketStream;
+pub use template::CommandTemplate;
+pub use transform::ResponseTransform;
+pub use url::ConnectionConfig;
 
+#[cfg(feature = "battleye")]
+pub mod battleye;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod charset;
+#[cfg(feature = "client")]
+mod client;
+pub mod codec;
+mod command;
+mod cvar;
+#[cfg(feature = "fleet")]
+pub mod fleet;
+mod frame;
+mod ids;
+mod interceptor;
+mod metrics;
+pub mod parse;
+
+#[cfg(feature = "minecraft")]
+pub mod minecraft;
+mod monitor;
 mod packet;
+#[cfg(feature = "pool")]
+pub mod pool;
+#[cfg(feature = "proxy")]
+pub mod proxy;
+mod quirks;
+mod rate_limit;
+mod reconnect;
+#[cfg(feature = "recording")]
+pub mod recording;
+mod response;
+mod script;
+#[cfg(feature = "serde")]
+mod serialize;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(feature = "tower")]
+pub mod service;
+mod shared;
+#[cfg(feature = "source")]
+pub mod source;
+mod split;
+#[cfg(feature = "stream")]
+mod stream;
+#[cfg(feature = "tcp")]
+mod tcp;
+mod template;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod text;
+#[cfg(feature = "tls")]
+pub mod tls;
+mod transform;
+mod url;
+#[cfg(feature = "webrcon")]
+pub mod webrcon;
 
 /// An error that can occur when communicating with the server.
 #[derive(Debug, Error)]
@@ -39,75 +150,1797 @@ pub enum Error {
 
     #[error(display = "payload size exceeded")]
     PayloadSize,
+
+    #[error(display = "no connector configured")]
+    NoConnector,
+
+    #[error(display = "unexpected packet type {}", _0)]
+    UnexpectedPacketType(i32),
+
+    #[error(display = "not an RCON server, received {:02x?}", _0)]
+    NotRconServer(Vec<u8>),
+
+    #[error(display = "connection closed")]
+    ConnectionClosed,
+
+    #[error(display = "disconnected by the server")]
+    Disconnected,
+
+    #[error(display = "operation timed out")]
+    Timeout,
+
+    #[error(display = "malformed packet: {}", _0)]
+    MalformedPacket(&'static str),
+
+    #[error(display = "protocol error: {}", _0)]
+    Protocol(&'static str),
+
+    #[error(display = "server returned an error: {}", _0)]
+    ServerError(String),
+
+    #[error(display = "unexpected response: {}", _0)]
+    UnexpectedResponse(String),
+
+    #[error(display = "invalid connection url: {}", _0)]
+    InvalidUrl(String),
+
+    #[error(display = "no server named {}", _0)]
+    UnknownServer(String),
+
+    #[error(display = "rejected by an interceptor: {}", _0)]
+    Rejected(String),
+
+    /// A command or argument holding characters that could make the server
+    /// run something else, such as a newline.
+    #[error(display = "invalid command: {}", _0)]
+    InvalidCommand(String),
+
+    /// A [`CommandTemplate`] that does not parse, or values it cannot take.
+    #[error(display = "command template: {}", _0)]
+    Template(String),
+
+    /// Something the connection cannot do as configured, such as having
+    /// several commands in flight with a constant packet id.
+    #[error(display = "unsupported: {}", _0)]
+    Unsupported(&'static str),
+
+    /// A command on a connection built with `require_authentication` that
+    /// has not authenticated.
+    #[error(display = "not authenticated")]
+    NotAuthenticated,
+
+    /// An attempt to authenticate a connection that already has. Most
+    /// servers answer it with an extra packet the connection would mistake
+    /// for the response to the next command, so it is not sent.
+    #[error(display = "already authenticated")]
+    AlreadyAuthenticated,
+
+    #[error(
+        display = "expected a response to packet {}, received packet {}",
+        expected,
+        received
+    )]
+    IdMismatch { expected: i32, received: i32 },
+
+    /// Another error, with the command being executed when it occurred and
+    /// the header of the packet that caused it, if one did. Only returned
+    /// with `error_context` set.
+    #[error(display = "{}, executing {:?}", source, command)]
+    Context {
+        #[error(source, no_from)]
+        source: Box<Error>,
+        command: String,
+        header: Option<PacketHeader>,
+    },
+}
+
+/// The broad category of an [`Error`], for handling errors without matching
+/// every variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// An io error not covered by another kind.
+    Io,
+    Timeout,
+    /// The server rejected the password.
+    Auth,
+    /// The server sent something that does not follow the protocol, or
+    /// answered differently than expected.
+    Protocol,
+    /// A payload could not be encoded or decoded in the `charset`.
+    Encoding,
+    /// A command was too long to send.
+    TooLarge,
+    /// The server went away, or the connection was closed.
+    Disconnected,
+    /// Errors in configuration or usage, errors the server returned, and
+    /// commands rejected by an interceptor.
+    Other,
+}
+
+impl Error {
+    /// Returns the broad category of the error.
+    ///
+    /// ```
+    /// use specul::{Error, ErrorKind};
+    ///
+    /// assert_eq!(Error::PayloadSize.kind(), ErrorKind::TooLarge);
+    /// assert_eq!(Error::Protocol("no authentication response").kind(), ErrorKind::Protocol);
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self.root() {
+            error if error.is_disconnect() => ErrorKind::Disconnected,
+            Error::Io(error) => match error.kind() {
+                io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => ErrorKind::Encoding,
+                io::ErrorKind::TimedOut => ErrorKind::Timeout,
+                _ => ErrorKind::Io,
+            },
+            Error::Timeout => ErrorKind::Timeout,
+            Error::Authentication | Error::NotAuthenticated => ErrorKind::Auth,
+            Error::PayloadSize => ErrorKind::TooLarge,
+            Error::ConnectionClosed | Error::Disconnected => ErrorKind::Disconnected,
+            Error::UnexpectedPacketType(_)
+            | Error::NotRconServer(_)
+            | Error::MalformedPacket(_)
+            | Error::Protocol(_)
+            | Error::UnexpectedResponse(_)
+            | Error::IdMismatch { .. } => ErrorKind::Protocol,
+            _ => ErrorKind::Other,
+        }
+    }
+
+    /// Returns the error without any [`Context`](Error::Context).
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Context { source, .. } => source.root(),
+            error => error,
+        }
+    }
+
+    /// Returns the command being executed when the error occurred, if
+    /// `error_context` is set.
+    pub fn command(&self) -> Option<&str> {
+        match self {
+            Error::Context { command, .. } => Some(command),
+            _ => None,
+        }
+    }
+
+    /// Returns the header of the packet that caused the error, if
+    /// `error_context` is set and a packet did.
+    pub fn header(&self) -> Option<PacketHeader> {
+        match self {
+            Error::Context { header, .. } => *header,
+            _ => None,
+        }
+    }
+
+    /// Whether the error means the server went away, such as after a restart.
+    fn is_disconnect(&self) -> bool {
+        match self.root() {
+            Error::Io(error) => matches!(
+                error.kind(),
+                io::ErrorKind::BrokenPipe
+                    | io::ErrorKind::ConnectionReset
+                    | io::ErrorKind::ConnectionAborted
+                    | io::ErrorKind::NotConnected
+                    | io::ErrorKind::UnexpectedEof
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the error may pass if the command is tried again: io errors
+    /// other than invalid input or data, timeouts, and the server going away.
+    ///
+    /// Authentication failures, errors the server returned, and errors in
+    /// the command itself are not retriable.
+    pub fn is_retriable(&self) -> bool {
+        match self.root() {
+            Error::Io(error) => !matches!(
+                error.kind(),
+                io::ErrorKind::InvalidInput | io::ErrorKind::InvalidData
+            ),
+            Error::Timeout | Error::Disconnected => true,
+            _ => false,
+        }
+    }
+
+    /// Whether the error means the stream is out of step with the server:
+    /// a malformed packet, a response carrying another command's id, or
+    /// bytes that are not RCON at all.
+    fn is_desync(&self) -> bool {
+        matches!(
+            self.root(),
+            Error::MalformedPacket(_) | Error::IdMismatch { .. } | Error::NotRconServer(_)
+        )
+    }
+
+    /// Whether a connection that failed a command with this error is still
+    /// in step with the server, so it can be used again.
+    fn leaves_connection_usable(&self) -> bool {
+        matches!(
+            self.root(),
+            Error::PayloadSize
+                | Error::InvalidCommand(_)
+                | Error::ServerError(_)
+                | Error::Rejected(_)
+                | Error::NotAuthenticated
+                | Error::AlreadyAuthenticated
+        )
+    }
 }
 
 /// A specialized [`Result`](std::result::Result) type for RCON operations.
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// The lifecycle state of a [`Connection`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    /// Connected, but not yet authenticated.
+    Connected,
+    /// Authenticated with the server.
+    Authenticated,
+    /// The server rejected the password.
+    Failed,
+    /// Closed with [`Connection::close`]. Any further io fails with
+    /// [`Error::ConnectionClosed`].
+    Closed,
+}
+
+/// What a server supports, as found by [`Connection::handshake`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServerCapabilities {
+    /// Whether the server mirrors an empty `SERVERDATA_RESPONSE_VALUE`, which
+    /// is what multi-packet responses are terminated with.
+    pub multi_response: bool,
+    /// The largest payload the server sent in one packet of its response to
+    /// the builder's `handshake_probe`, or 0 without a probe. Only a probe
+    /// whose response the server had to split measures the server's limit;
+    /// otherwise it is just the largest packet seen.
+    pub max_fragment: usize,
+}
+
+/// The packets answering one command, and how long they took.
+#[derive(Debug)]
+struct Exchange {
+    /// The command as sent, after any interceptors rewrote it.
+    command: String,
+    request_id: i32,
+    packets: Vec<Packet>,
+    latency: Duration,
+}
+
+/// Per-call overrides for [`Connection::execute_command_with`].
+///
+/// Start from [`Connection::exec_options`] to inherit the connection's
+/// configuration and change only what differs.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExecOptions {
+    /// Whether to wait for a response at all. Commands that make the server
+    /// close the connection or never answer should set this to `false`.
+    ///
+    /// Should the server answer anyway, the response is discarded by the
+    /// next command that reads one, as with [`Connection::fire`].
+    pub expect_response: bool,
+    /// Whether to collect a response split over several packets, like
+    /// `multiple_responses`.
+    pub multi: bool,
+    /// How long the command may take before failing with [`Error::Timeout`],
+    /// like `command_deadline`.
+    pub timeout: Option<Duration>,
+    /// An opaque token, such as a trace id, recorded on the `command` span
+    /// that covers sending the command and receiving its response. Only used
+    /// with the `tracing` feature.
+    pub correlation: Option<String>,
+}
+
+impl Default for ExecOptions {
+    fn default() -> Self {
+        ExecOptions {
+            expect_response: true,
+            multi: false,
+            timeout: None,
+            correlation: None,
+        }
+    }
+}
+
 /// A connection to a RCON server.
 /// Can be constructed with any type that implements
 /// [`AsyncRead`](tokio::io::AsyncRead) and [`AsyncWrite`](tokio::io::AsyncWrite).
 #[derive(Debug, Builder)]
+#[builder(pattern = "owned", build_fn(validate = "Self::validate"))]
 pub struct Connection<T> {
     io: T,
+    #[builder(default, setter(strip_option))]
+    connector: Option<Connector<T>>,
+    /// Reconnect through the `connector`, and re-authenticate, when a
+    /// command finds the stream out of sync: a malformed packet, a response
+    /// with another command's id under `strict_ids` or in a batch, or a
+    /// first packet that is not RCON. The command still fails.
+    #[builder(default = "false")]
+    reconnect_on_desync: bool,
+    /// Redial through the `connector`, re-authenticate and retry the command
+    /// once when a command fails because the server went away.
+    #[builder(default, setter(strip_option))]
+    auto_reconnect: Option<Backoff>,
+    /// A command that turns on the output a
+    /// [`console_messages`](Connection::console_messages) stream tails, such
+    /// as `log on`. It is run again, after a [`ConsoleItem::Reconnected`],
+    /// whenever `auto_reconnect` or `reconnect_on_desync` re-establishes the
+    /// connection while the stream is open.
+    #[builder(default, setter(into, strip_option))]
+    console_command: Option<String>,
+    /// Retries commands that fail with a [retriable](Error::is_retriable)
+    /// error, redialing through the `connector` first if the server went
+    /// away.
+    #[builder(default, setter(strip_option))]
+    retry_policy: Option<RetryPolicy>,
+    #[builder(setter(skip))]
+    password: Option<Password>,
+    /// Replaced by [`metrics`](ConnectionBuilder::metrics).
+    #[builder(default, setter(custom))]
+    shared: Arc<monitor::Shared>,
     #[builder(default = "0")]
     default_packet_id: i32,
     #[builder(default = "0")]
     current_packet_id: i32,
+    /// How packet ids are chosen. Sequential by default.
+    #[builder(default)]
+    id_strategy: IdStrategy,
+    /// The longest command sent, lowered further by the `quirks` of servers
+    /// that accept less.
     #[builder(default = "4096")]
     max_payload_size: usize,
+    /// Send a command too long for the server one line at a time, as
+    /// separate commands, instead of failing with [`Error::PayloadSize`].
+    ///
+    /// Only suitable for commands whose lines can run independently, such as
+    /// several `say` lines. Applies to
+    /// [`execute_command`](Connection::execute_command) and the methods built
+    /// on it, which return the responses to every line in order. A single
+    /// line that is still too long fails as before, after the lines before it
+    /// were executed.
+    #[builder(default = "false")]
+    split_long_commands: bool,
+    /// Fail commands holding control characters other than tabs, such as a
+    /// newline smuggled in with a player name, with
+    /// [`Error::InvalidCommand`] instead of sending them.
+    ///
+    /// Checks each command as sent, after the `command_prefix` and any
+    /// [`Interceptor`], and after `split_long_commands` splits it into lines.
+    /// Raw commands are not checked.
+    #[builder(default = "false")]
+    reject_control_characters: bool,
+    /// Fail commands with [`Error::NotAuthenticated`] until the connection
+    /// has authenticated, instead of sending them to a server that would
+    /// answer with an error or not at all.
+    ///
+    /// Off by default, for servers that need no password.
+    #[builder(default = "false")]
+    require_authentication: bool,
+    /// Server-specific deviations from the Source protocol to work around.
+    #[builder(default)]
+    quirks: Quirks,
+    /// The largest length field accepted in a packet from the server. Longer
+    /// packets fail with [`Error::MalformedPacket`] instead of being
+    /// buffered, so a broken or hostile server cannot exhaust memory.
+    #[builder(default = "codec::DEFAULT_MAX_INCOMING_PACKET_SIZE")]
+    max_incoming_packet_size: usize,
+    /// Collect responses split over several packets, by following every
+    /// command with a sentinel packet and reading until it is mirrored back.
+    /// See [`recieve_multi_response`](Connection::recieve_multi_response).
     #[builder(default = "false")]
     multiple_responses: bool,
+    /// The maximum number of packets read while waiting for the
+    /// authentication response before giving up.
+    #[builder(default = "16")]
+    max_auth_packets: usize,
+    /// Fail authentication on packets of an unknown type, which usually means
+    /// the io is not connected to an RCON port.
+    #[builder(default = "false")]
+    strict_auth: bool,
+    /// Check that the first packet received looks like RCON, failing with
+    /// [`Error::NotRconServer`] if it does not.
+    #[builder(default = "true")]
+    validate_first_packet: bool,
+    #[builder(setter(skip))]
+    received_packet: bool,
+    #[builder(setter(skip))]
+    read_buffer: BytesMut,
+    /// Carried between the codecs that decode the `read_buffer`.
+    #[builder(setter(skip))]
+    owed_nulls: u8,
+    #[builder(setter(skip))]
+    write_buffer: packet::WireBuffer,
+    /// How packets are framed on the wire.
+    #[builder(default)]
+    framing: Framing,
+    /// How payloads are encoded, for servers that do not use UTF-8.
+    #[builder(default)]
+    charset: Charset,
+    /// Set while [`execute_command_raw`](Connection::execute_command_raw)
+    /// runs, so its command is encoded as Latin-1, byte for byte.
+    #[builder(setter(skip))]
+    raw: bool,
+    /// A prefix added to every command run with
+    /// [`execute_command`](Connection::execute_command), such as `sm_`.
+    #[builder(default, setter(into, strip_option))]
+    command_prefix: Option<String>,
+    /// Rewrites the bytes of every command's response before they are decoded.
+    #[builder(default, setter(strip_option))]
+    response_transform: Option<ResponseTransform>,
+    /// Removes color and formatting codes from every command's response,
+    /// after any `response_transform`, with [`text::strip_formatting`].
+    #[builder(default)]
+    strip_formatting: bool,
+    /// Added with [`interceptor`](ConnectionBuilder::interceptor).
+    #[builder(default, setter(custom))]
+    interceptors: Interceptors,
+    /// Set with [`on_frame`](ConnectionBuilder::on_frame).
+    #[builder(default, setter(custom))]
+    on_frame: Option<frame::FrameHook>,
+    /// A command sent by [`close`](Connection::close) before shutting down,
+    /// for servers that expect a clean logout.
+    #[builder(default, setter(into, strip_option))]
+    disconnect_command: Option<String>,
+    /// A command sent by [`ping`](Connection::ping) instead of an empty
+    /// `SERVERDATA_RESPONSE_VALUE`, for servers that do not mirror one.
+    #[builder(default, setter(into, strip_option))]
+    ping_command: Option<String>,
+    /// How long [`close`](Connection::close) waits, after shutting down, for
+    /// the server to close its side of the connection.
+    #[builder(default, setter(strip_option))]
+    close_wait: Option<Duration>,
+    /// How long [`drain`](Connection::drain) waits for another packet.
+    #[builder(default = "Duration::from_millis(100)")]
+    drain_timeout: Duration,
+    /// A command with a long response, such as `cvarlist` on Source servers,
+    /// that [`handshake`](Connection::handshake) runs to measure the largest
+    /// packet the server sends.
+    #[builder(default, setter(into, strip_option))]
+    handshake_probe: Option<String>,
+    /// The longest the server may go without sending any bytes while a
+    /// packet is being read. Every chunk received restarts the timer.
+    #[builder(default, setter(strip_option))]
+    read_timeout: Option<Duration>,
+    /// The longest a whole command, from sending it to receiving all of its
+    /// response, may take.
+    #[builder(default, setter(strip_option))]
+    command_deadline: Option<Duration>,
+    /// The longest authentication, a write, or a command without a
+    /// `command_deadline` may take before failing with [`Error::Timeout`].
+    #[builder(default, setter(strip_option))]
+    timeout: Option<Duration>,
+    /// Set with [`min_command_interval`](ConnectionBuilder::min_command_interval)
+    /// and [`rate_limit`](ConnectionBuilder::rate_limit).
+    #[builder(default, setter(custom))]
+    pacing: Pacing,
+    /// Fail with [`Error::IdMismatch`] when a packet that does not answer the
+    /// command arrives, instead of passing it to event subscribers.
+    #[builder(default = "false")]
+    strict_ids: bool,
+    /// Wrap the errors of [`execute_command`](Connection::execute_command)
+    /// and the other single-command methods in [`Error::Context`], with the
+    /// command and the header of the packet that caused the error.
+    /// [`Error::kind`] and [`Error::root`] see through the wrapper.
+    #[builder(default = "false")]
+    error_context: bool,
+    /// The header of the packet that made the running command fail.
+    #[builder(setter(skip))]
+    failed_header: Option<PacketHeader>,
+    /// The id of empty keep-alive packets the server sends while idle. Such
+    /// packets are ignored, and the id is never used for a command.
+    ///
+    /// Empty packets whose id does not match the command being executed are
+    /// ignored as keep-alives either way.
+    #[builder(default, setter(strip_option))]
+    keepalive_id: Option<i32>,
+    /// How long an [`RconClient`] may go without sending anything before it
+    /// sends a keep-alive, for servers and NAT gateways that drop idle
+    /// connections.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    #[builder(default, setter(strip_option))]
+    keepalive_interval: Option<Duration>,
+    /// A command sent as the keep-alive, such as `echo`, in place of an
+    /// empty `SERVERDATA_RESPONSE_VALUE`. Its response is discarded.
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    #[builder(default, setter(into, strip_option))]
+    keepalive_command: Option<String>,
+    /// How long a value read or set with [`get_cvar`](Connection::get_cvar)
+    /// is reused before asking the server again. Nothing is cached unless
+    /// set.
+    #[builder(default, setter(strip_option))]
+    cvar_cache_ttl: Option<Duration>,
+    #[builder(setter(skip))]
+    cvars: HashMap<String, (CvarValue, Instant)>,
+    /// Ids of commands sent without waiting for their response.
+    #[builder(setter(skip))]
+    pending_discard: HashSet<i32>,
+}
+
+impl<T> ConnectionBuilder<T> {
+    fn validate(&self) -> std::result::Result<(), String> {
+        if let Some(framing) = &self.framing {
+            if !(1..=2).contains(&framing.trailing_nulls) {
+                return Err("trailing_nulls must be 1 or 2".to_string());
+            }
+        }
+
+        if let Some(pacing) = &self.pacing {
+            pacing.check()?;
+        }
+
+        if let Some(strategy) = self.id_strategy {
+            let sentinel = self
+                .quirks
+                .unwrap_or_default()
+                .uses_sentinel(self.multiple_responses.unwrap_or(false));
+
+            strategy.check(self.keepalive_id.flatten(), sentinel)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl<T> Connection<T>
 where
     T: Unpin + AsyncRead + AsyncWrite,
 {
+    /// Returns the current state of the connection.
+    pub fn state(&self) -> State {
+        self.shared.state()
+    }
+
+    /// Whether the server accepted the password, and the connection has not
+    /// been reconnected or closed since.
+    pub fn is_authenticated(&self) -> bool {
+        self.state() == State::Authenticated
+    }
+
+    /// Returns a snapshot of the connection's traffic counters.
+    pub fn stats(&self) -> Stats {
+        self.shared.stats()
+    }
+
+    /// Returns the address of the server, if the connection was made over TCP
+    /// by this crate.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.shared.peer_addr()
+    }
+
+    /// Returns a read-only view of this connection's state and stats that can
+    /// be cloned and used from other tasks.
+    pub fn monitor(&self) -> ConnectionMonitor {
+        ConnectionMonitor::new(self.shared.clone())
+    }
+
+    /// Returns a stream of the packets that answer no request, such as chat
+    /// and log lines servers push to RCON clients, instead of letting them
+    /// be mistaken for responses.
+    ///
+    /// Packets are read while commands run, or all the time once the
+    /// connection is turned into an [`RconClient`]. They are also passed to
+    /// event subscribers as [`Event::Unsolicited`], unless `strict_ids` makes
+    /// them fail the command instead. Up to 256 packets are buffered; later
+    /// ones are dropped until the stream is read. Calling this again replaces
+    /// the previous stream.
+    ///
+    /// The stream outlives reconnects by `auto_reconnect` and
+    /// `reconnect_on_desync`: each is marked with a
+    /// [`ConsoleItem::Reconnected`], and the builder's `console_command` is
+    /// run again so the server keeps pushing output.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::{Connection, ConsoleItem};
+    ///
+    /// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+    /// let mut console = connection.console_messages();
+    ///
+    /// tokio::spawn(async move {
+    ///     while let Some(item) = console.recv().await {
+    ///         match item {
+    ///             ConsoleItem::Packet(packet) => {
+    ///                 println!("{}", String::from_utf8_lossy(packet.as_bytes()))
+    ///             }
+    ///             ConsoleItem::Reconnected => println!("-- reconnected --"),
+    ///         }
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn console_messages(&self) -> tokio::sync::mpsc::Receiver<ConsoleItem> {
+        self.shared.console()
+    }
+
+    /// Returns a description of the error that made the last operation fail,
+    /// or `None` if it succeeded.
+    ///
+    /// Authenticating, executing commands, reconnecting and closing all update
+    /// it.
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.last_error()
+    }
+
+    /// Wraps the connection so that commands can be executed through a shared
+    /// reference, one at a time.
+    pub fn shared(self) -> SharedConnection<T> {
+        SharedConnection::new(self)
+    }
+
+    /// Splits the connection into halves that send and receive
+    /// independently, like [`TcpStream::into_split`](tokio::net::TcpStream::into_split),
+    /// so one task can keep sending commands while another reads responses.
+    ///
+    /// Both halves draw on the same packet-id counter, so ids returned by
+    /// [`ConnectionSender::send_command`] never collide. Commands sent by
+    /// the sender still pass through the interceptors' `before_send`, but
+    /// the packets read by the receiver are not matched to commands, so
+    /// `after_receive` is not called.
+    pub fn split(self) -> (ConnectionSender<T>, ConnectionReceiver<T>) {
+        split::split(self)
+    }
+
+    /// Turns the connection into a [`Stream`](futures_core::Stream) of the
+    /// packets it receives and a [`Sink`](futures_sink::Sink) of packets to
+    /// send, for use with stream combinators and `select` loops.
+    #[cfg(feature = "stream")]
+    pub fn into_stream(self) -> PacketStream<T> {
+        PacketStream::new(self)
+    }
+
+    /// Hands the connection to background tasks and returns a cloneable
+    /// handle that runs commands concurrently, matching responses by packet
+    /// id.
+    ///
+    /// Must be called from within a Tokio runtime. Fails with
+    /// [`Error::Unsupported`] if the connection uses
+    /// [`IdStrategy::Constant`], since its responses could not be told apart.
+    #[cfg(feature = "client")]
+    pub fn client(self) -> Result<RconClient>
+    where
+        T: Send + 'static,
+    {
+        self.check_pipelining()?;
+        Ok(RconClient::new(self))
+    }
+
     /// Authenticates with the server.
+    ///
+    /// If the server rejects the password this returns [`Error::Authentication`]
+    /// and the connection moves to [`State::Failed`]. Most servers close the
+    /// socket after a failed attempt, so the io should be considered unusable:
+    /// call [`reconnect`](Self::reconnect) before authenticating again, or use
+    /// [`authenticate_retry`](Self::authenticate_retry). The packet id counter
+    /// is left untouched.
+    ///
+    /// Packets before the authentication response, such as the empty
+    /// `SERVERDATA_RESPONSE_VALUE` Source servers send first, are skipped. At
+    /// most `max_auth_packets` packets are read; if none of them is the
+    /// response, the attempt fails with [`Error::Protocol`]. With
+    /// `strict_auth` set, a packet of an unknown type fails the attempt with
+    /// [`Error::UnexpectedPacketType`] instead of being skipped. Io errors,
+    /// such as the server closing the connection, are returned as they occur.
+    ///
+    /// Fails with [`Error::Timeout`] if a `timeout` is configured and the
+    /// attempt takes longer, and with [`Error::AlreadyAuthenticated`],
+    /// without sending anything, if the connection already authenticated.
     pub async fn authenticate(&mut self, password: &str) -> Result<()> {
+        if self.is_authenticated() {
+            return self.track(Err(Error::AlreadyAuthenticated));
+        }
+
+        let timeout = self.timeout;
+        let attempt = async {
+            self.send(PacketType::Authentication, password.to_string())
+                .await?;
+            self.receive_authentication().await
+        };
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("authenticate", latency_ms = tracing::field::Empty);
+        #[cfg(feature = "tracing")]
+        let (attempt, started) = (
+            tracing::Instrument::instrument(attempt, span.clone()),
+            Instant::now(),
+        );
+
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, attempt)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => attempt.await,
+        };
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok(()) => {
+                span.record("latency_ms", started.elapsed().as_secs_f64() * 1000.0);
+            }
+            Err(error) => tracing::debug!(parent: &span, %error, "authentication failed"),
+        }
+
+        match &result {
+            Ok(()) => self.password = Some(Password::new(password)),
+            Err(Error::Authentication) => {
+                if let Some(metrics) = self.shared.metrics() {
+                    metrics.auth_failed();
+                }
+            }
+            Err(_) => {}
+        }
+
+        self.track(result)
+    }
+
+    /// Authenticates like [`authenticate`](Self::authenticate), with a
+    /// password kept in a [`SecretString`](secrecy::SecretString).
+    ///
+    /// With the `secrecy` feature, every copy of the password the connection
+    /// makes, in the packet sent and the copy kept for re-authenticating, is
+    /// zeroed when dropped.
+    #[cfg(feature = "secrecy")]
+    pub async fn authenticate_secret(
+        &mut self,
+        password: impl Into<secrecy::SecretString>,
+    ) -> Result<()> {
+        let password = password.into();
+        self.authenticate(secrecy::ExposeSecret::expose_secret(&password))
+            .await
+    }
+
+    /// Checks whether the server requires authentication, by sending an empty
+    /// command without authenticating first.
+    ///
+    /// An error packet (id -1) in reply, a closed connection or no reply within
+    /// `drain_timeout` mean authentication is required; any other response
+    /// means it is not. This consumes one command, and strict servers may
+    /// refuse or even drop the connection, so it is best done on a connection
+    /// that is reconnected afterwards.
+    pub async fn probe_auth_required(&mut self) -> Result<bool> {
+        self.send(PacketType::Message, String::new()).await?;
+
+        match tokio::time::timeout(self.drain_timeout, self.receive_packet()).await {
+            Ok(Ok(packet)) => Ok(packet.is_error()),
+            Ok(Err(Error::Io(err))) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(true),
+            Ok(Err(err)) => Err(err),
+            Err(_) => Ok(true),
+        }
+    }
+
+    /// Authenticates with the server and probes what it supports in the same
+    /// exchange.
+    ///
+    /// An empty `SERVERDATA_RESPONSE_VALUE` is queued right behind the
+    /// authentication packet, so the server answers both in one round trip.
+    /// Servers that mirror it support multi-packet responses. Once
+    /// authenticated, the builder's `handshake_probe` command is sent, if
+    /// set, and the largest packet of its response is taken as the fragment
+    /// size. Packets that follow the authentication response are read until
+    /// none arrives within `drain_timeout`.
+    pub async fn handshake(&mut self, password: &str) -> Result<ServerCapabilities> {
+        let result = self.run_handshake(password).await;
+        self.track(result)
+    }
+
+    async fn run_handshake(&mut self, password: &str) -> Result<ServerCapabilities> {
+        if self.is_authenticated() {
+            return Err(Error::AlreadyAuthenticated);
+        }
+
         self.send(PacketType::Authentication, password.to_string())
             .await?;
 
+        let probe = Packet::new(self.new_packet_id(), PacketType::Response, String::new());
+        let probe_id = probe.id;
+        self.send_packet(probe).await?;
+
+        self.receive_authentication().await?;
+        self.password = Some(Password::new(password));
+
+        let command_id = match self.handshake_probe.clone() {
+            Some(command) => {
+                let command = Packet::new(
+                    self.new_packet_id(),
+                    PacketType::Message,
+                    self.encode(&command)?,
+                );
+                let id = command.id;
+                self.send_packet(command).await?;
+                Some(id)
+            }
+            None => None,
+        };
+
+        let mut capabilities = ServerCapabilities {
+            multi_response: false,
+            max_fragment: 0,
+        };
+
+        while let Ok(packet) = tokio::time::timeout(self.drain_timeout, self.receive_packet()).await
+        {
+            let packet = packet?;
+
+            if packet.id == probe_id {
+                capabilities.multi_response = true;
+            }
+
+            if Some(packet.id) == command_id {
+                capabilities.max_fragment = capabilities.max_fragment.max(packet.payload.len());
+            }
+        }
+
+        Ok(capabilities)
+    }
+
+    async fn receive_authentication(&mut self) -> Result<()> {
+        let mut packets_left = self.max_auth_packets;
+
         let packet = loop {
-            let packet = self.receive_packet().await;
+            if packets_left == 0 {
+                return Err(Error::Protocol("no authentication response"));
+            }
 
-            if let Some(packet) = packet.ok() {
-                if packet.packet_type == PacketType::AuthenticationResponse {
-                    break packet;
+            packets_left -= 1;
+
+            let packet = self.receive_packet().await?;
+
+            match packet.packet_type {
+                PacketType::AuthenticationResponse => break packet,
+                PacketType::Unknown(value) if self.strict_auth => {
+                    return Err(Error::UnexpectedPacketType(value));
                 }
+                _ => {}
             }
         };
 
         if packet.is_error() {
+            self.shared.set_state(State::Failed);
             Err(Error::Authentication)
         } else {
+            self.shared.set_state(State::Authenticated);
             Ok(())
         }
     }
 
+    /// Authenticates with the server, reconnecting through the configured
+    /// [`Connector`] and trying again up to `retries` times if the attempt,
+    /// or the dial before it, fails with an error that
+    /// [may pass](Error::is_retriable), such as an io error or a timeout.
+    /// Attempts are spaced out by the `auto_reconnect` [`Backoff`], or the
+    /// default one.
+    ///
+    /// A rejected password is returned at once, with the connection in
+    /// [`State::Failed`]: sending it again cannot succeed, and servers ban
+    /// addresses that fail too often. Otherwise the last attempt's error is
+    /// returned, and without a connector the first attempt's.
+    pub async fn authenticate_retry(&mut self, password: &str, retries: usize) -> Result<()> {
+        let backoff = self.auto_reconnect.unwrap_or_default();
+        let mut result = self.authenticate(password).await;
+        let mut retried = 0;
+
+        loop {
+            let error = match result {
+                Err(error) if error.is_retriable() => error,
+                result => return result,
+            };
+
+            if retried >= retries || self.connector.is_none() {
+                return Err(error);
+            }
+
+            retried += 1;
+            tokio::time::sleep(backoff.delay(retried)).await;
+
+            // A dial that fails uses up a retry like a failed attempt.
+            result = match self.reconnect().await {
+                Ok(()) => self.authenticate(password).await,
+                Err(error) => Err(error),
+            };
+        }
+    }
+
+    /// Replaces the underlying io with a fresh one from the configured
+    /// [`Connector`], moving the connection back to [`State::Connected`].
+    ///
+    /// The connection must be authenticated again afterwards.
+    pub async fn reconnect(&mut self) -> Result<()> {
+        let result = match &self.connector {
+            Some(connector) => connector.connect().await.map_err(Error::Io),
+            None => Err(Error::NoConnector),
+        };
+
+        self.io = self.track(result)?;
+        self.shared.set_state(State::Connected);
+        if let Some(metrics) = self.shared.metrics() {
+            metrics.reconnected();
+        }
+        self.received_packet = false;
+        self.read_buffer.clear();
+        self.owed_nulls = 0;
+        self.pending_discard.clear();
+        self.cvars.clear();
+
+        Ok(())
+    }
+
     /// Executes a command on the server.
+    ///
+    /// If a `command_prefix` is configured it is prepended, unless the command
+    /// already starts with it.
     pub async fn execute_command(&mut self, command: &str) -> Result<Vec<String>> {
-        if command.len() > self.max_payload_size {
-            return Err(Error::PayloadSize);
+        let options = self.exec_options();
+        self.execute_command_with(command, options).await
+    }
+
+    /// Executes a command on the server like
+    /// [`execute_command`](Self::execute_command), returning the response
+    /// with the packet ids and round-trip time it arrived with.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::Connection;
+    ///
+    /// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+    /// let response = connection.execute("status").await?;
+    ///
+    /// println!("{} packets in {:?}", response.packets(), response.latency);
+    /// assert!(response.starts_with("hostname"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute(&mut self, command: &str) -> Result<Response> {
+        let command = self.prefixed(command);
+        let options = self.exec_options();
+
+        let result = match self.execute_packets(&command, options).await {
+            Ok(exchange) => {
+                let response_ids = exchange.packets.iter().map(|packet| packet.id).collect();
+
+                self.responses(&exchange.command, exchange.packets)
+                    .map(|payloads| Response {
+                        body: payloads.concat(),
+                        payloads,
+                        request_id: exchange.request_id,
+                        response_ids,
+                        latency: exchange.latency,
+                    })
+            }
+            Err(error) => Err(error),
+        };
+
+        self.track(result)
+    }
+
+    /// Executes a typed [`Command`] and parses its response.
+    pub async fn run<C: Command>(&mut self, command: &C) -> Result<C::Output> {
+        let response = self.execute_command(&command.to_command_string()).await?;
+
+        C::parse_response(&response.concat())
+    }
+
+    /// Executes a command on the server with per-call `options` in place of
+    /// the connection's `multiple_responses` and `command_deadline`.
+    ///
+    /// Returns an empty vector without reading anything if
+    /// `expect_response` is `false`.
+    pub async fn execute_command_with(
+        &mut self,
+        command: &str,
+        options: ExecOptions,
+    ) -> Result<Vec<String>> {
+        self.execute_payloads(command, options, true).await
+    }
+
+    /// Returns every setting that affects the wire format, for debugging
+    /// protocol mismatches.
+    pub fn wire_config(&self) -> WireConfig {
+        WireConfig {
+            endianness: "little",
+            framing: self.framing,
+            packet_types: PacketTypeIds::new(),
+            encoding: self.charset.name(),
+            max_payload_size: self.max_payload_size,
+            max_plausible_length: packet::MAX_PLAUSIBLE_LENGTH,
+            max_incoming_packet_size: self.max_incoming_packet_size,
+            validate_first_packet: self.validate_first_packet,
+            multiple_responses: self.multiple_responses,
+            quirks: self.quirks,
+            command_prefix: self.command_prefix.clone(),
+        }
+    }
+
+    /// Returns the options [`execute_command`](Self::execute_command) uses,
+    /// as configured on the connection.
+    pub fn exec_options(&self) -> ExecOptions {
+        ExecOptions {
+            expect_response: true,
+            multi: self.quirks.uses_sentinel(self.multiple_responses),
+            timeout: self.command_deadline.or(self.timeout),
+            correlation: None,
+        }
+    }
+
+    /// Executes a command on the server like
+    /// [`execute_command`](Self::execute_command), failing with
+    /// [`Error::Timeout`] if it takes longer than `timeout`.
+    ///
+    /// The timeout replaces any `command_deadline` for this call. Like the
+    /// connection-wide timeouts, it leaves a partially received packet
+    /// buffered, to be completed by the next read.
+    pub async fn execute_command_timeout(
+        &mut self,
+        command: &str,
+        timeout: Duration,
+    ) -> Result<Vec<String>> {
+        let options = ExecOptions {
+            timeout: Some(timeout),
+            ..self.exec_options()
+        };
+
+        self.execute_command_with(command, options).await
+    }
+
+    /// Executes a command on the server and returns its response joined into
+    /// one string, or [`Error::ServerError`] with the error packet's payload
+    /// if the server answered with an error packet.
+    pub async fn execute_checked(&mut self, command: &str) -> Result<String> {
+        let command = self.prefixed(command);
+        let options = self.exec_options();
+
+        let Exchange {
+            command, packets, ..
+        } = match self.execute_packets(&command, options).await {
+            Ok(exchange) => exchange,
+            Err(error) => return self.track(Err(error)),
+        };
+
+        let result = match packets.iter().find(|packet| packet.is_error()) {
+            Some(packet) => {
+                self.failed_header = Some(packet.header());
+                self.charset
+                    .decode(&packet.payload)
+                    .map_err(Error::from)
+                    .and_then(|payload| Err(Error::ServerError(payload)))
+            }
+            None => self
+                .responses(&command, packets)
+                .map(|payloads| payloads.concat()),
+        };
+        let result = self.in_context(result, &command);
+
+        self.track(result)
+    }
+
+    /// Checks that the session is still usable, returning the round-trip
+    /// time.
+    ///
+    /// Sends the `ping_command` if one is configured, or else an empty
+    /// `SERVERDATA_RESPONSE_VALUE`, which servers mirror back without running
+    /// anything. Fails with [`Error::Disconnected`] if the server has gone
+    /// away, and with [`Error::Timeout`] if it does not answer within the
+    /// `command_deadline` or `timeout`.
+    pub async fn ping(&mut self) -> Result<Duration> {
+        let started = Instant::now();
+        let timeout = self.exec_options().timeout;
+
+        let ping = async {
+            match self.ping_command.clone() {
+                Some(command) => {
+                    self.execute_command(&command).await?;
+                }
+                None => {
+                    let id = self.new_packet_id();
+                    self.send_packet(Packet::new(id, PacketType::Response, String::new()))
+                        .await?;
+                    self.receive_response(&[id]).await?;
+
+                    // Source servers follow the mirror with a marker packet.
+                    self.pending_discard.insert(id);
+                }
+            }
+
+            Ok(started.elapsed())
+        };
+
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, ping)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => ping.await,
+        };
+
+        let result = result.map_err(|error| match error {
+            error if error.is_disconnect() => Error::Disconnected,
+            error => error,
+        });
+
+        self.track(result)
+    }
+
+    /// Whether a [`ping`](Self::ping) succeeds.
+    pub async fn is_alive(&mut self) -> bool {
+        self.ping().await.is_ok()
+    }
+
+    /// Sends a command without waiting for its response.
+    ///
+    /// The response is discarded by the next command that reads one, matched
+    /// by packet id, so it cannot be mistaken for that command's output. With
+    /// [`IdStrategy::Constant`] it could, so this fails with
+    /// [`Error::Unsupported`].
+    pub async fn fire(&mut self, command: &str) -> Result<()> {
+        let options = ExecOptions {
+            expect_response: false,
+            ..self.exec_options()
+        };
+
+        self.execute_command_with(command, options).await?;
+        Ok(())
+    }
+
+    /// Executes each command in turn and returns the `(command, response)`
+    /// pairs whose response passes `keep`, such as
+    /// `|response| !response.trim().is_empty()` to drop empty acknowledgements.
+    ///
+    /// Each response is the concatenation of its payloads. Stops at the first
+    /// command that fails.
+    pub async fn execute_commands_filtered<I, S, F>(
+        &mut self,
+        commands: I,
+        mut keep: F,
+    ) -> Result<Vec<(String, String)>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+        F: FnMut(&str) -> bool,
+    {
+        let mut kept = Vec::new();
+
+        for command in commands {
+            let command = command.as_ref();
+            let response = self.execute_command(command).await?.concat();
+
+            if keep(&response) {
+                kept.push((command.to_string(), response));
+            }
+        }
+
+        Ok(kept)
+    }
+
+    /// Executes several commands with one round trip, returning their
+    /// responses in the order of `commands`.
+    ///
+    /// Every command is written before any response is read, and responses
+    /// are matched to their commands by packet id, so over a slow link the
+    /// batch takes little longer than a single command. The connection's
+    /// `command_prefix`, `multiple_responses` and `min_command_interval`
+    /// apply to each command, while a `command_deadline` bounds the whole
+    /// batch.
+    ///
+    /// Nothing is sent if any command is too long to send, or if there is
+    /// more than one command and the connection uses
+    /// [`IdStrategy::Constant`], which fails with [`Error::Unsupported`].
+    pub async fn execute_commands<I, S>(&mut self, commands: I) -> Result<Vec<Vec<String>>>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let commands = commands
+            .into_iter()
+            .map(|command| {
+                let mut command = self.prefixed(command.as_ref());
+                self.interceptors.before_send(&mut command)?;
+                Ok(command)
+            })
+            .collect::<Result<Vec<String>>>();
+        let commands = self.track(commands)?;
+
+        let options = self.exec_options();
+        let result = match options.timeout {
+            Some(deadline) => tokio::time::timeout(deadline, self.run_batch(&commands, &options))
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => self.run_batch(&commands, &options).await,
+        };
+
+        if self.reconnect_on_desync && result.as_ref().is_err_and(Error::is_desync) {
+            self.resync().await;
         }
 
-        self.send(PacketType::Message, command.to_string()).await?;
+        let result = result.and_then(|responses| {
+            responses
+                .into_iter()
+                .zip(&commands)
+                .map(|(packets, command)| self.responses(command, packets))
+                .collect()
+        });
+
+        self.track(result)
+    }
+
+    /// Executes a command on the server exactly as given, ignoring any
+    /// `command_prefix`.
+    ///
+    /// Fails with [`Error::Timeout`] if a `command_deadline` is configured and
+    /// the command takes longer.
+    pub async fn execute_unprefixed(&mut self, command: &str) -> Result<Vec<String>> {
+        self.execute_payloads(command, self.exec_options(), false)
+            .await
+    }
+
+    /// Executes a command given as bytes, returning the bytes of every
+    /// packet of the response joined together, exactly as the server sent
+    /// them, for servers whose output is not text in any [`Charset`].
+    ///
+    /// The command is sent as given, without any `command_prefix` or
+    /// interceptors, and the response is not passed through any
+    /// `response_transform`, `strip_formatting` or interceptors.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::Connection;
+    ///
+    /// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+    /// let response = connection.execute_command_raw(b"users").await?;
+    ///
+    /// println!("{}", String::from_utf8_lossy(&response));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn execute_command_raw(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let command: String = command.iter().copied().map(char::from).collect();
+        let options = self.exec_options();
+
+        self.failed_header = None;
+        self.raw = true;
+        let result = self.execute_retrying(&command, &options).await;
+        self.raw = false;
+        let result = self.in_context(result, &command);
 
-        let response = self.recieve().await?;
+        let result = result.map(|exchange| {
+            exchange
+                .packets
+                .iter()
+                .flat_map(|packet| packet.as_bytes())
+                .copied()
+                .collect()
+        });
 
-        Ok(response)
+        self.track(result)
+    }
+
+    fn prefixed(&self, command: &str) -> String {
+        match &self.command_prefix {
+            Some(prefix) if !command.starts_with(prefix.as_str()) => {
+                format!("{}{}", prefix, command)
+            }
+            _ => command.to_string(),
+        }
     }
 
-    /// Sends a payload to the server.
+    /// Executes `command`, line by line if it is too long and
+    /// `split_long_commands` is set, applying the `command_prefix` to each
+    /// line if `prefix`.
+    async fn execute_payloads(
+        &mut self,
+        command: &str,
+        options: ExecOptions,
+        prefix: bool,
+    ) -> Result<Vec<String>> {
+        let prefixed = |connection: &Self, command: &str| match prefix {
+            true => connection.prefixed(command),
+            false => command.to_string(),
+        };
+
+        let whole = prefixed(self, command);
+
+        // The limit is on the bytes sent, as in `check_command`.
+        if !self.split_long_commands
+            || self.command_charset().encode(&whole)?.len() <= self.max_command_len()
+        {
+            return self.execute_payloads_once(&whole, options).await;
+        }
+
+        let mut payloads = Vec::new();
+
+        for line in command.lines().filter(|line| !line.trim().is_empty()) {
+            let line = prefixed(self, line);
+            payloads.extend(self.execute_payloads_once(&line, options.clone()).await?);
+        }
+
+        Ok(payloads)
+    }
+
+    async fn execute_payloads_once(
+        &mut self,
+        command: &str,
+        options: ExecOptions,
+    ) -> Result<Vec<String>> {
+        let result = self
+            .execute_packets(command, options)
+            .await
+            .and_then(|exchange| self.responses(&exchange.command, exchange.packets));
+
+        self.track(result)
+    }
+
+    async fn execute_packets(&mut self, command: &str, options: ExecOptions) -> Result<Exchange> {
+        // Left set if a raw command was cancelled.
+        self.raw = false;
+
+        self.failed_header = None;
+
+        let mut command = command.to_string();
+        self.interceptors.before_send(&mut command)?;
+
+        let result = self.execute_retrying(&command, &options).await;
+        self.in_context(result, &command)
+    }
+
+    /// Wraps an error in [`Error::Context`] if `error_context` is set.
+    fn in_context<R>(&mut self, result: Result<R>, command: &str) -> Result<R> {
+        match result {
+            Err(error) if self.error_context => Err(Error::Context {
+                source: Box::new(error),
+                command: command.to_string(),
+                header: self.failed_header.take(),
+            }),
+            result => result,
+        }
+    }
+
+    /// Executes the command, retrying as the `retry_policy` says.
+    async fn execute_retrying(&mut self, command: &str, options: &ExecOptions) -> Result<Exchange> {
+        let mut attempt = 1;
+
+        loop {
+            let result = self.execute_recovering(command, options).await;
+
+            let Some(policy) = self.retry_policy else {
+                return result;
+            };
+
+            match result {
+                Err(error) if error.is_retriable() && attempt < policy.max_attempts => {
+                    tokio::time::sleep(policy.delay(attempt)).await;
+                    attempt += 1;
+
+                    if error.is_disconnect() && self.connector.is_some() {
+                        match self.reestablish().await {
+                            Err(error) if !error.is_retriable() => return Err(error),
+                            // A failed redial leaves the next attempt to fail too.
+                            _ => {}
+                        }
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Executes the command once, or twice if it fails because the server
+    /// went away and `auto_reconnect` re-establishes the connection.
+    async fn execute_recovering(
+        &mut self,
+        command: &str,
+        options: &ExecOptions,
+    ) -> Result<Exchange> {
+        let result = self.execute_once(command, options).await;
+
+        match (&result, self.auto_reconnect) {
+            (Err(error), Some(backoff)) if error.is_disconnect() && self.connector.is_some() => {
+                self.recover(backoff).await?;
+                self.execute_once(command, options).await
+            }
+            _ => result,
+        }
+    }
+
+    async fn execute_once(&mut self, command: &str, options: &ExecOptions) -> Result<Exchange> {
+        self.check_command(command)?;
+
+        self.pace().await;
+        self.shared.record_command();
+        let started = Instant::now();
+
+        let timeout = options.timeout;
+        let run = self.run_command(command, options);
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!(
+            "command",
+            command,
+            correlation = options.correlation.as_deref(),
+            request_id = tracing::field::Empty,
+            packets = tracing::field::Empty,
+            payload_length = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+        );
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::instrument(run, span.clone());
+
+        let result = match timeout {
+            Some(deadline) => tokio::time::timeout(deadline, run)
+                .await
+                .unwrap_or(Err(Error::Timeout)),
+            None => run.await,
+        };
+
+        #[cfg(feature = "tracing")]
+        match &result {
+            Ok((request_id, packets)) => {
+                let payload_length: usize = packets.iter().map(|packet| packet.payload.len()).sum();
+
+                span.record("request_id", request_id);
+                span.record("packets", packets.len());
+                span.record("payload_length", payload_length);
+                span.record("latency_ms", started.elapsed().as_secs_f64() * 1000.0);
+            }
+            Err(error) => {
+                tracing::debug!(parent: &span, %error, "command failed");
+            }
+        }
+
+        if let Some(metrics) = self.shared.metrics() {
+            let name = metrics::command_name(command);
+
+            match &result {
+                Ok(_) => metrics.command_completed(name, started.elapsed()),
+                Err(error) => metrics.command_failed(name, error),
+            }
+        }
+
+        if self.reconnect_on_desync && result.as_ref().is_err_and(Error::is_desync) {
+            self.resync().await;
+        }
+
+        result.map(|(request_id, packets)| Exchange {
+            command: command.to_string(),
+            request_id,
+            packets,
+            latency: started.elapsed(),
+        })
+    }
+
+    /// Re-establishes the stream after a desync. Errors are left for the next
+    /// operation.
+    async fn resync(&mut self) {
+        if self.connector.is_none() {
+            return;
+        }
+
+        self.shared.emit(Event::Reconnecting);
+
+        if self.reestablish().await.is_ok() {
+            self.reestablished().await;
+        }
+    }
+
+    /// Redials after the server went away, waiting between attempts as
+    /// `backoff` says. Gives up early if the password is rejected.
+    async fn recover(&mut self, backoff: Backoff) -> Result<()> {
+        self.shared.emit(Event::Reconnecting);
+
+        let mut attempt = 1;
+
+        loop {
+            match self.reestablish().await {
+                Ok(()) => {
+                    self.reestablished().await;
+                    return Ok(());
+                }
+                Err(error @ Error::Authentication) => return Err(error),
+                Err(error) if attempt >= backoff.max_attempts => return Err(error),
+                Err(_) => {
+                    tokio::time::sleep(backoff.delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+
+    /// Tells subscribers and the console stream about a reconnect, and runs
+    /// the `console_command` again if the stream is open. A failure is left
+    /// for the next command to find.
+    async fn reestablished(&mut self) {
+        self.shared.emit(Event::Reconnected);
+
+        if !self.shared.console_reconnected() {
+            return;
+        }
+
+        if let Some(command) = self.console_command.clone() {
+            let options = self.exec_options();
+            let _ = self.run_command(&command, &options).await;
+        }
+    }
+
+    /// Reconnects, and re-authenticates with the last accepted password.
+    async fn reestablish(&mut self) -> Result<()> {
+        self.reconnect().await?;
+
+        if let Some(password) = self.password.clone() {
+            self.authenticate(password.expose()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_command(
+        &mut self,
+        command: &str,
+        options: &ExecOptions,
+    ) -> Result<(i32, Vec<Packet>)> {
+        if !options.expect_response || options.multi {
+            self.check_pipelining()?;
+        }
+
+        let id = self.new_packet_id();
+        let mut packets = vec![Packet::new(id, PacketType::Message, self.encode(command)?)];
+
+        // The sentinel goes out in the same write as the command.
+        let sentinel = (options.expect_response && options.multi).then(|| self.new_packet_id());
+        if let Some(sentinel) = sentinel {
+            packets.push(Packet::new(sentinel, PacketType::Response, String::new()));
+        }
+
+        self.send_packets(packets).await?;
+
+        if !options.expect_response {
+            self.pending_discard.insert(id);
+            return Ok((id, Vec::new()));
+        }
+
+        let packets = match sentinel {
+            Some(sentinel) => self.receive_until_sentinel(Some(id), sentinel).await?,
+            None => vec![self.receive_response(&[id]).await?],
+        };
+
+        Ok((id, packets))
+    }
+
+    /// Writes every command, then collects the packets answering each one.
+    async fn run_batch(
+        &mut self,
+        commands: &[String],
+        options: &ExecOptions,
+    ) -> Result<Vec<Vec<Packet>>> {
+        self.raw = false;
+
+        if commands.len() > 1 || options.multi {
+            self.check_pipelining()?;
+        }
+
+        for command in commands {
+            self.check_command(command)?;
+        }
+
+        // Packet id to the index of the command it answers, and whether it
+        // is that command's sentinel.
+        let mut slots = HashMap::new();
+
+        // Written together, unless pacing has to space the commands out.
+        let paced = self.pacing.is_active();
+        let mut packets = Vec::new();
+
+        for (index, command) in commands.iter().enumerate() {
+            self.pace().await;
+            self.shared.record_command();
+
+            let id = self.new_packet_id();
+            slots.insert(id, (index, false));
+            packets.push(Packet::new(id, PacketType::Message, self.encode(command)?));
+
+            if options.multi {
+                let sentinel = self.new_packet_id();
+                slots.insert(sentinel, (index, true));
+                packets.push(Packet::new(sentinel, PacketType::Response, String::new()));
+            }
+
+            if paced {
+                self.send_packets(std::mem::take(&mut packets)).await?;
+            }
+        }
+
+        if !packets.is_empty() {
+            self.send_packets(packets).await?;
+        }
+
+        let ids: Vec<i32> = slots.keys().copied().collect();
+        let mut responses = vec![Vec::new(); commands.len()];
+        let mut done = vec![false; commands.len()];
+        let mut remaining = commands.len();
+
+        while remaining > 0 {
+            let packet = self.receive_response(&ids).await?;
+
+            let Some(&(index, sentinel)) = slots.get(&packet.id) else {
+                let expected = ids[0];
+                return Err(Error::IdMismatch {
+                    expected,
+                    received: packet.id,
+                });
+            };
+
+            if done[index] {
+                // The marker after a mirrored sentinel, or a fragment beyond
+                // the first without `multiple_responses`.
+                if !sentinel {
+                    self.shared.unsolicited(packet);
+                }
+                continue;
+            }
+
+            if sentinel {
+                done[index] = true;
+                remaining -= 1;
+                self.pending_discard.insert(packet.id);
+            } else {
+                responses[index].push(packet);
+
+                if !options.multi {
+                    done[index] = true;
+                    remaining -= 1;
+                }
+            }
+        }
+
+        Ok(responses)
+    }
+
+    /// Fails with [`Error::Unsupported`] if more than one packet may be
+    /// waiting for an answer at once, which a constant packet id cannot
+    /// match responses to.
+    fn check_pipelining(&self) -> Result<()> {
+        match self.id_strategy {
+            IdStrategy::Constant(_) => Err(Error::Unsupported(
+                "several packets in flight with a constant packet id",
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Waits until `min_command_interval` has passed since the last command,
+    /// and for a token from any `rate_limit`.
+    async fn pace(&mut self) {
+        self.pacing.wait().await;
+    }
+
+    /// Returns the payloads of the `packets` answering `command`, passed
+    /// through any `response_transform`, decoded with the `charset`, and
+    /// passed through `strip_formatting` and interceptors.
+    fn responses(&self, command: &str, packets: Vec<Packet>) -> Result<Vec<String>> {
+        let mut payloads: Vec<Bytes> = packets.into_iter().map(|packet| packet.payload).collect();
+
+        // Joined before decoding, so characters split between fragments
+        // survive.
+        if self.quirks.joins_fragments() && payloads.len() > 1 {
+            payloads = vec![payloads.concat().into()];
+        }
+
+        let mut payloads =
+            transform::decode_payloads(self.response_transform.as_ref(), self.charset, payloads)
+                .map(|payloads| text::strip_payloads(self.strip_formatting, payloads))?;
+
+        self.interceptors.after_receive(command, &mut payloads)?;
+        Ok(payloads)
+    }
+
+    /// Sends a payload to the server, encoded with the `charset`.
     pub async fn send(&mut self, packet_type: PacketType, payload: String) -> Result<()> {
+        let payload = self.charset.encode_string(payload)?;
         let packet = packet::Packet::new(self.new_packet_id(), packet_type, payload);
         self.send_packet(packet).await
     }
 
+    /// The `charset`, or Latin-1 for
+    /// [`execute_command_raw`](Self::execute_command_raw), which turns its
+    /// widened command back into the bytes it was given.
+    fn command_charset(&self) -> Charset {
+        match self.raw {
+            true => Charset::Latin1,
+            false => self.charset,
+        }
+    }
+
+    /// Encodes a command with the [`command_charset`](Self::command_charset).
+    fn encode(&self, command: &str) -> Result<Bytes> {
+        Ok(self.command_charset().encode(command)?.into_owned().into())
+    }
+
+    /// Closes the connection by flushing and shutting down the io.
+    ///
+    /// If a `disconnect_command` is configured it is sent first, and its
+    /// response is awaited for up to `drain_timeout` and ignored. Errors while
+    /// logging out are ignored too, since the server may close the socket in
+    /// response to the command. With a `close_wait`, the server is then
+    /// given that long to close its side, and anything it sends meanwhile is
+    /// discarded.
+    ///
+    /// Afterwards the connection is in [`State::Closed`] and every operation
+    /// other than [`reconnect`](Self::reconnect) fails with
+    /// [`Error::ConnectionClosed`]. Closing again does nothing.
+    pub async fn close(&mut self) -> Result<()> {
+        if self.state() == State::Closed {
+            return Ok(());
+        }
+
+        if let Some(command) = self.disconnect_command.clone() {
+            if self.send(PacketType::Message, command).await.is_ok() {
+                let _ = tokio::time::timeout(self.drain_timeout, self.receive_packet()).await;
+            }
+        }
+
+        self.shared.set_state(State::Closed);
+        let result = async {
+            self.io.flush().await?;
+            self.io.shutdown().await?;
+
+            if let Some(wait) = self.close_wait {
+                let _ = tokio::time::timeout(wait, self.wait_for_eof()).await;
+            }
+
+            Ok(())
+        }
+        .await;
+
+        self.track(result)
+    }
+
+    /// Reads and discards bytes until the server closes its side.
+    async fn wait_for_eof(&mut self) -> io::Result<()> {
+        let mut buffer = [0; 512];
+
+        while self.io.read(&mut buffer).await? > 0 {}
+
+        Ok(())
+    }
+
+    /// Reads and discards packets until none arrives within `drain_timeout`,
+    /// returning how many were discarded.
+    ///
+    /// Useful for skipping banners and other packets the server sends without
+    /// being asked.
+    pub async fn drain(&mut self) -> Result<usize> {
+        let mut drained = 0;
+
+        while let Ok(packet) = tokio::time::timeout(self.drain_timeout, self.receive_packet()).await
+        {
+            packet?;
+            drained += 1;
+        }
+
+        Ok(drained)
+    }
+
+    /// Decodes and returns every complete packet that has already been read
+    /// from the io, without waiting for more.
+    ///
+    /// Returns an empty vector if no complete packet is buffered. Packets
+    /// before a malformed one are returned, leaving it buffered, so the next
+    /// call fails with the error, such as [`Error::MalformedPacket`], and
+    /// records it for [`last_error`](Self::last_error).
+    pub fn try_read_buffered_packets(&mut self) -> Result<Vec<Packet>> {
+        let mut packets = Vec::new();
+
+        loop {
+            match self.decode_buffered() {
+                Ok(Some(packet)) => packets.push(packet),
+                Ok(None) => return Ok(packets),
+                Err(_) if !packets.is_empty() => return Ok(packets),
+                Err(error) => return self.track(Err(error)),
+            }
+        }
+    }
+
     /// Receives payload(s) from the server.
     pub async fn recieve(&mut self) -> Result<Vec<String>> {
-        if self.multiple_responses {
+        if self.quirks.uses_sentinel(self.multiple_responses) {
             self.recieve_multi_response().await
         } else {
             let reponse = self.recieve_single_response().await?;
@@ -116,52 +1949,367 @@ where
     }
 
     /// Receives multiple payloads from the server.
+    ///
+    /// Sends an empty [`PacketType::Response`] packet as a sentinel and
+    /// collects payloads until the server mirrors it back. Servers answer
+    /// packets in order, so the mirror arrives after the whole response, and
+    /// empty payloads within the response are kept. The `00 01 00 00` marker
+    /// some servers send after the mirror is discarded by the next read.
     pub async fn recieve_multi_response(&mut self) -> Result<Vec<String>> {
-        let mut responses = Vec::new();
+        let sentinel = self.new_packet_id();
+        let packet = Packet::new(sentinel, PacketType::Response, String::new());
+        self.send_packet(packet).await?;
+
+        let packets = self.receive_until_sentinel(None, sentinel).await?;
+
+        packets
+            .iter()
+            .map(|packet| Ok(self.charset.decode(&packet.payload)?))
+            .collect()
+    }
 
+    /// Receives packets answering `id`, or any packets if `id` is `None`,
+    /// until the `sentinel` already sent is mirrored back.
+    async fn receive_until_sentinel(
+        &mut self,
+        id: Option<i32>,
+        sentinel: i32,
+    ) -> Result<Vec<Packet>> {
+        let ids = match id {
+            Some(id) => vec![id, sentinel],
+            None => Vec::new(),
+        };
+        let mut packets = Vec::new();
+
+        loop {
+            let packet = self.receive_response(&ids).await?;
+
+            if packet.id == sentinel {
+                self.pending_discard.insert(sentinel);
+                return Ok(packets);
+            }
+
+            packets.push(packet);
+        }
+    }
+
+    /// Receives the next packet answering a request with one of `ids`, or
+    /// any packet if `ids` is empty.
+    ///
+    /// Responses to [fired](Self::fire) commands are discarded. Packets with
+    /// any other id, such as console output that arrived after the previous
+    /// response was complete, are passed to event subscribers as
+    /// [`Event::Unsolicited`] instead, or fail with [`Error::IdMismatch`] if
+    /// `strict_ids` is set. Error packets, with the id -1 servers use for a
+    /// failed authentication, are always returned.
+    async fn receive_response(&mut self, ids: &[i32]) -> Result<Packet> {
         loop {
-            let response = self.recieve_single_response().await?;
-            responses.push(response);
+            let packet = self.receive_packet().await?;
+
+            if self.is_keepalive(&packet, ids) {
+                continue;
+            }
 
-            if let Some(last) = responses.last() {
-                if last.is_empty() {
-                    break;
+            if ids.is_empty() || ids.contains(&packet.id) || packet.is_error() {
+                // Responses arrive in order, so every fired command
+                // before this one has been answered.
+                if !ids.is_empty() {
+                    self.pending_discard.clear();
                 }
+
+                return Ok(packet);
+            }
+
+            if self.pending_discard.contains(&packet.id) {
+                continue;
+            }
+
+            if self.strict_ids {
+                self.failed_header = Some(packet.header());
+                return Err(Error::IdMismatch {
+                    expected: ids[0],
+                    received: packet.id,
+                });
             }
+
+            self.shared.unsolicited(packet);
         }
+    }
 
-        Ok(responses)
+    fn is_keepalive(&self, packet: &Packet, ids: &[i32]) -> bool {
+        if !packet.payload.is_empty() {
+            return false;
+        }
+
+        Some(packet.id) == self.keepalive_id
+            || (!packet.is_error() && !ids.is_empty() && !ids.contains(&packet.id))
     }
 
     /// Receives a single payload from the server.
     pub async fn recieve_single_response(&mut self) -> Result<String> {
         let packet = self.receive_packet().await?;
 
-        Ok(packet.payload.into())
+        Ok(self.charset.decode(&packet.payload)?)
+    }
+
+    /// Sends a packet as it is, for protocol extensions with their own
+    /// packet types, such as `PacketType::Unknown(5)`.
+    ///
+    /// Nothing is waited for; the answer, if any, is read with
+    /// [`receive_packet`](Self::receive_packet). The packet should take its
+    /// id from [`new_packet_id`](Self::new_packet_id), so it cannot be
+    /// mistaken for the answer to a command.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::{Connection, Packet, PacketType};
+    ///
+    /// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+    ///
+    /// let id = connection.new_packet_id();
+    /// let packet = Packet::new(id, PacketType::Unknown(100), "subscribe chat".to_string());
+    /// connection.send_packet(packet).await?;
+    ///
+    /// let answer = connection.receive_packet().await?;
+    /// assert_eq!(answer.id, id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn send_packet(&mut self, packet: Packet) -> Result<()> {
+        self.send_packets(vec![packet]).await
     }
 
-    async fn send_packet(&mut self, packet: Packet) -> Result<()> {
-        match packet.write_to_io(&mut self.io).await {
-            Ok(_) => Ok(()),
+    /// Sends `packets` in a single write and flush.
+    async fn send_packets(&mut self, packets: Vec<Packet>) -> Result<()> {
+        let packets: Vec<_> = packets.into_iter().map(packet::Outgoing).collect();
+
+        if self.state() == State::Closed {
+            return Err(Error::ConnectionClosed);
+        }
+
+        let timeout = self.timeout;
+        let write = self.write_packets(&packets);
+        let written = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, write)
+                .await
+                .map_err(|_| Error::Timeout)?,
+            None => write.await,
+        };
+
+        match written {
+            Ok(written) => {
+                for (_packet, written) in packets.iter().zip(written) {
+                    #[cfg(feature = "tracing")]
+                    _packet.trace_sent(written);
+
+                    self.shared.record_sent(written);
+                }
+                Ok(())
+            }
             Err(err) => Err(Error::Io(err)),
         }
     }
 
-    async fn receive_packet(&mut self) -> Result<Packet> {
-        match Packet::read_from_io(&mut self.io).await {
-            Ok(packet) => Ok(packet),
-            Err(err) => Err(Error::Io(err)),
+    /// Writes `packets` to the io, returning the number of bytes each took.
+    async fn write_packets(&mut self, packets: &[packet::Outgoing]) -> io::Result<Vec<usize>> {
+        let codec = self.codec();
+        let buffer = self.write_buffer.start();
+        let written: Vec<usize> = packets
+            .iter()
+            .map(|packet| codec.encode_packet(packet, buffer))
+            .collect::<io::Result<_>>()?;
+
+        let result = self.io.write_all(self.write_buffer.bytes()).await;
+
+        if result.is_ok() {
+            frame::emit_sent(
+                self.on_frame.as_ref(),
+                packets.iter().map(|packet| &**packet),
+                self.write_buffer.bytes(),
+                &written,
+                self.framing,
+            );
+        }
+
+        self.write_buffer.clear();
+        result?;
+        self.io.flush().await?;
+
+        Ok(written)
+    }
+
+    /// Receives the next packet, failing with [`Error::Timeout`] if it does not
+    /// arrive completely within `timeout`.
+    ///
+    /// `read_timeout` still applies between chunks. A packet that is only
+    /// partly received when the timeout fires stays buffered, to be completed
+    /// by the next read.
+    pub async fn receive_packet_timeout(&mut self, timeout: Duration) -> Result<Packet> {
+        tokio::time::timeout(timeout, self.receive_packet())
+            .await
+            .unwrap_or(Err(Error::Timeout))
+    }
+
+    /// Polls for the next packet, for event loops that drive the connection
+    /// by hand instead of awaiting it.
+    ///
+    /// Registers `cx` to be woken when more bytes arrive. `read_timeout` does
+    /// not apply, since the caller decides how long to keep polling.
+    pub fn poll_next_packet(&mut self, cx: &mut Context<'_>) -> Poll<Result<Packet>> {
+        if self.state() == State::Closed {
+            return Poll::Ready(Err(Error::ConnectionClosed));
+        }
+
+        loop {
+            if let Some(packet) = self.decode_buffered()? {
+                return Poll::Ready(Ok(packet));
+            }
+
+            let mut chunk = [0; 4096];
+            let mut buf = ReadBuf::new(&mut chunk);
+            ready!(Pin::new(&mut self.io).poll_read(cx, &mut buf))?;
+
+            let read = buf.filled().len();
+
+            if read == 0 {
+                return Poll::Ready(Err(Error::Io(io::ErrorKind::UnexpectedEof.into())));
+            }
+
+            self.read_buffer.extend_from_slice(buf.filled());
+            self.shared.record_bytes_received(read);
+        }
+    }
+
+    /// Receives the next packet, whatever its id and type.
+    ///
+    /// Unlike the command methods, it skips nothing: keep-alives, mirrored
+    /// packets and the leftovers of commands sent without waiting are all
+    /// returned. Types the connection does not know are
+    /// [`PacketType::Unknown`], and 2 is read as
+    /// [`PacketType::AuthenticationResponse`]. An unknown type on the very
+    /// first packet still fails with [`Error::NotRconServer`] unless
+    /// `validate_first_packet` is turned off.
+    pub async fn receive_packet(&mut self) -> Result<Packet> {
+        if self.state() == State::Closed {
+            return Err(Error::ConnectionClosed);
+        }
+
+        loop {
+            if let Some(packet) = self.decode_buffered()? {
+                return Ok(packet);
+            }
+
+            let read = match self.read_timeout {
+                Some(timeout) => {
+                    tokio::time::timeout(timeout, self.io.read_buf(&mut self.read_buffer))
+                        .await
+                        .map_err(|_| Error::Timeout)??
+                }
+                None => self.io.read_buf(&mut self.read_buffer).await?,
+            };
+
+            if read == 0 {
+                return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            self.shared.record_bytes_received(read);
         }
     }
 
-    fn new_packet_id(&mut self) -> i32 {
-        let id = self.current_packet_id;
+    fn decode_buffered(&mut self) -> Result<Option<Packet>> {
+        if self.validate_first_packet && !self.received_packet {
+            if let Some(header) = Header::peek(&self.read_buffer, self.framing) {
+                if !header.is_plausible(self.framing) {
+                    self.failed_header = Some(header.fields());
+                    return Err(Error::NotRconServer(header.raw));
+                }
+            }
+        }
+
+        let buffered = frame::wanted(self.on_frame.as_ref()).then(|| self.read_buffer.to_vec());
+        let mut codec = self.codec().owing(self.owed_nulls);
+        let decoded = codec.decode_packet(&mut self.read_buffer);
+        self.owed_nulls = codec.owed_nulls();
+
+        if decoded.is_err() {
+            self.failed_header =
+                Header::peek(&self.read_buffer, self.framing).map(|header| header.fields());
+        }
+
+        let packet = decoded?;
+
+        #[cfg(feature = "tracing")]
+        if let Some(packet) = &packet {
+            packet.trace_received();
+        }
+
+        if let (Some(packet), Some(buffered)) = (&packet, &buffered) {
+            let consumed = buffered.len() - self.read_buffer.len();
+            frame::emit(
+                self.on_frame.as_ref(),
+                FrameDirection::Received,
+                packet,
+                &buffered[..consumed],
+                self.framing,
+            );
+        }
+
+        if packet.is_some() {
+            self.received_packet = true;
+            self.shared.record_packet_received();
+        }
+
+        Ok(packet)
+    }
 
-        self.current_packet_id = self
-            .current_packet_id
-            .checked_add(1)
-            .unwrap_or(self.default_packet_id);
+    /// The longest command that will be sent, as limited by
+    /// `max_payload_size` and the server's `quirks`.
+    pub(crate) fn max_command_len(&self) -> usize {
+        match self.quirks.max_command_len() {
+            Some(max) => max.min(self.max_payload_size),
+            None => self.max_payload_size,
+        }
+    }
+
+    /// Fails a command that is too long, or with `reject_control_characters`,
+    /// one holding control characters, or with `require_authentication`, any
+    /// command before authenticating.
+    fn check_command(&self, command: &str) -> Result<()> {
+        if self.require_authentication && !self.is_authenticated() {
+            return Err(Error::NotAuthenticated);
+        }
+
+        // The limit is on the bytes sent, not the UTF-8 of `command`.
+        if self.command_charset().encode(command)?.len() > self.max_command_len() {
+            return Err(Error::PayloadSize);
+        }
+
+        if self.reject_control_characters && !self.raw {
+            quirks::check_control_characters(command, "command")?;
+        }
+
+        Ok(())
+    }
+
+    fn codec(&self) -> RconCodec {
+        RconCodec::new(self.framing).with_max_incoming_packet_size(self.max_incoming_packet_size)
+    }
+
+    fn track<R>(&self, result: Result<R>) -> Result<R> {
+        self.shared
+            .set_last_error(result.as_ref().err().map(ToString::to_string));
+
+        result
+    }
 
-        id
+    /// Returns the next packet id from the `id_strategy`, never the
+    /// `keepalive_id`, for packets sent with
+    /// [`send_packet`](Self::send_packet).
+    pub fn new_packet_id(&mut self) -> i32 {
+        self.id_strategy.next(
+            &mut self.current_packet_id,
+            self.default_packet_id,
+            self.keepalive_id,
+        )
     }
 }
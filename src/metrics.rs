@@ -0,0 +1,106 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use crate::{monitor::Shared, ConnectionBuilder, Error};
+
+/// Receives a connection's counters and timings as they happen, for
+/// exporting to a metrics system.
+///
+/// Set on a connection with [`ConnectionBuilder::metrics`]. Every method
+/// does nothing by default, so implementations only override what they
+/// export. The methods are called on the connection's task, so they should
+/// not block.
+///
+/// ```
+/// use std::{
+///     sync::atomic::{AtomicU64, Ordering},
+///     time::Duration,
+/// };
+///
+/// use specul::Metrics;
+///
+/// #[derive(Default)]
+/// struct Counters {
+///     commands: AtomicU64,
+///     slowest_ms: AtomicU64,
+/// }
+///
+/// impl Metrics for Counters {
+///     fn command_sent(&self) {
+///         self.commands.fetch_add(1, Ordering::Relaxed);
+///     }
+///
+///     fn command_completed(&self, _name: &str, latency: Duration) {
+///         self.slowest_ms
+///             .fetch_max(latency.as_millis() as u64, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+pub trait Metrics: Send + Sync {
+    /// A command was sent.
+    fn command_sent(&self) {}
+
+    /// A command's whole response arrived, `latency` after it was sent.
+    ///
+    /// `name` is the command's first word, such as `status` or `sm_kick`,
+    /// which keeps the number of distinct labels small.
+    fn command_completed(&self, name: &str, latency: Duration) {
+        let _ = (name, latency);
+    }
+
+    /// A command failed, such as by timing out.
+    fn command_failed(&self, name: &str, error: &Error) {
+        let _ = (name, error);
+    }
+
+    /// A packet of `bytes` bytes was written.
+    fn packet_sent(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    /// A packet was decoded.
+    fn packet_received(&self) {}
+
+    /// `bytes` bytes were read.
+    fn bytes_received(&self, bytes: usize) {
+        let _ = bytes;
+    }
+
+    /// The server rejected the password.
+    fn auth_failed(&self) {}
+
+    /// The io was replaced with a fresh connection.
+    fn reconnected(&self) {}
+}
+
+/// The [`Metrics`] a connection reports to, if any.
+#[derive(Clone, Default)]
+pub(crate) struct MetricsHook(Option<Arc<dyn Metrics>>);
+
+impl MetricsHook {
+    pub(crate) fn get(&self) -> Option<&dyn Metrics> {
+        self.0.as_deref()
+    }
+}
+
+impl fmt::Debug for MetricsHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MetricsHook")
+            .field(&self.0.is_some())
+            .finish()
+    }
+}
+
+/// Returns the first word of `command`, as passed to [`Metrics`].
+pub(crate) fn command_name(command: &str) -> &str {
+    command.split_whitespace().next().unwrap_or_default()
+}
+
+impl<T> ConnectionBuilder<T> {
+    /// Reports the connection's counters and timings to `metrics`, as well
+    /// as counting them for [`Connection::stats`](crate::Connection::stats).
+    pub fn metrics(mut self, metrics: impl Metrics + 'static) -> Self {
+        let hook = MetricsHook(Some(Arc::new(metrics)));
+        self.shared = Some(Arc::new(Shared::with_metrics(hook)));
+        self
+    }
+}
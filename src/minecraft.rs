@@ -0,0 +1,459 @@
+//! Typed [`Command`]s for Minecraft's built-in commands.
+//!
+//! Connections to Minecraft servers should be built with
+//! [`Quirks::Minecraft`](crate::Quirks::Minecraft).
+//!
+//! ```no_run
+//! # async fn run(connection: &mut specul::Connection<tokio::net::TcpStream>) -> specul::Result<()> {
+//! use specul::minecraft::{Color, Component, GetGameRule, ListPlayers, Tellraw};
+//!
+//! let list = connection.run(&ListPlayers).await?;
+//! println!("{} of {} online", list.online, list.max);
+//!
+//! let keep_inventory = connection.run(&GetGameRule::<bool>::new("keepInventory")).await?;
+//!
+//! let message = Component::text("Restarting in 5 minutes").color(Color::Red).bold(true);
+//! connection.run(&Tellraw::new("@a", message)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{fmt, marker::PhantomData, str::FromStr};
+
+use crate::{
+    parse::{parse_minecraft_list, MinecraftPlayer},
+    Command, Error, Result,
+};
+
+fn unexpected(response: &str) -> Error {
+    Error::UnexpectedResponse(response.to_string())
+}
+
+/// The players online, as listed by `list`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerList {
+    pub online: u32,
+    pub max: u32,
+    pub players: Vec<MinecraftPlayer>,
+}
+
+/// `list`, which names the players online.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListPlayers;
+
+impl Command for ListPlayers {
+    type Output = PlayerList;
+
+    fn to_command_string(&self) -> String {
+        "list".to_string()
+    }
+
+    /// Parses `There are 2 of a max of 20 players online: Steve, Alex`,
+    /// and the `There are 2/20 players online:` form of older servers.
+    fn parse_response(response: &str) -> Result<PlayerList> {
+        let (counts, _) = response
+            .split_once(':')
+            .ok_or_else(|| unexpected(response))?;
+
+        let numbers: Vec<u32> = counts
+            .split(|c: char| !c.is_ascii_digit())
+            .filter_map(|number| number.parse().ok())
+            .collect();
+
+        let [online, max] = numbers[..] else {
+            return Err(unexpected(response));
+        };
+
+        Ok(PlayerList {
+            online,
+            max,
+            players: parse_minecraft_list(response).players,
+        })
+    }
+}
+
+/// Declares a command taking a player name whose response says whether
+/// anything changed.
+macro_rules! player_command {
+    ($(#[$doc:meta])* $name:ident, $command:literal, changed: $changed:literal, unchanged: [$($unchanged:literal),*]) => {
+        $(#[$doc])*
+        ///
+        /// Returns whether anything changed, and fails with
+        /// [`Error::UnexpectedResponse`] for unknown players.
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub struct $name(pub String);
+
+        impl $name {
+            pub fn new(player: impl Into<String>) -> Self {
+                $name(player.into())
+            }
+        }
+
+        impl Command for $name {
+            type Output = bool;
+
+            fn to_command_string(&self) -> String {
+                format!(concat!($command, " {}"), self.0)
+            }
+
+            fn parse_response(response: &str) -> Result<bool> {
+                if response.starts_with($changed) {
+                    Ok(true)
+                } else if [$($unchanged),*].iter().any(|unchanged| response.starts_with(unchanged)) {
+                    Ok(false)
+                } else {
+                    Err(unexpected(response))
+                }
+            }
+        }
+    };
+}
+
+player_command!(
+    /// `whitelist add <player>`.
+    WhitelistAdd,
+    "whitelist add",
+    changed: "Added ",
+    unchanged: ["Player is already whitelisted"]
+);
+
+player_command!(
+    /// `whitelist remove <player>`.
+    WhitelistRemove,
+    "whitelist remove",
+    changed: "Removed ",
+    unchanged: ["Player is not whitelisted"]
+);
+
+player_command!(
+    /// `op <player>`, which makes the player a server operator.
+    Op,
+    "op",
+    changed: "Made ",
+    unchanged: ["Nothing changed"]
+);
+
+player_command!(
+    /// `deop <player>`, which revokes the player's operator status.
+    Deop,
+    "deop",
+    changed: "Made ",
+    unchanged: ["Nothing changed"]
+);
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for bool {}
+    impl Sealed for i32 {}
+}
+
+/// A type a game rule can hold: `bool` or `i32`.
+pub trait GameRuleValue: sealed::Sealed + FromStr + fmt::Display {}
+
+impl GameRuleValue for bool {}
+impl GameRuleValue for i32 {}
+
+/// Returns the value after `: ` in a game rule response.
+fn game_rule_value<V: GameRuleValue>(response: &str) -> Result<V> {
+    response
+        .rsplit_once(": ")
+        .and_then(|(_, value)| value.trim().parse().ok())
+        .ok_or_else(|| unexpected(response))
+}
+
+/// `gamerule <rule>`, which reads a game rule.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GetGameRule<V> {
+    pub rule: String,
+    value: PhantomData<V>,
+}
+
+impl<V> GetGameRule<V> {
+    pub fn new(rule: impl Into<String>) -> Self {
+        GetGameRule {
+            rule: rule.into(),
+            value: PhantomData,
+        }
+    }
+}
+
+impl<V: GameRuleValue> Command for GetGameRule<V> {
+    type Output = V;
+
+    fn to_command_string(&self) -> String {
+        format!("gamerule {}", self.rule)
+    }
+
+    /// Parses `Gamerule keepInventory is currently set to: false`.
+    fn parse_response(response: &str) -> Result<V> {
+        game_rule_value(response)
+    }
+}
+
+/// `gamerule <rule> <value>`, which changes a game rule and returns the new
+/// value.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SetGameRule<V> {
+    pub rule: String,
+    pub value: V,
+}
+
+impl<V> SetGameRule<V> {
+    pub fn new(rule: impl Into<String>, value: V) -> Self {
+        SetGameRule {
+            rule: rule.into(),
+            value,
+        }
+    }
+}
+
+impl<V: GameRuleValue> Command for SetGameRule<V> {
+    type Output = V;
+
+    fn to_command_string(&self) -> String {
+        format!("gamerule {} {}", self.rule, self.value)
+    }
+
+    /// Parses `Gamerule keepInventory is now set to: true`.
+    fn parse_response(response: &str) -> Result<V> {
+        game_rule_value(response)
+    }
+}
+
+/// `save-all`, which writes the world to disk, waiting for the chunks to be
+/// written first with `flush`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct SaveAll {
+    pub flush: bool,
+}
+
+impl Command for SaveAll {
+    type Output = ();
+
+    fn to_command_string(&self) -> String {
+        match self.flush {
+            true => "save-all flush".to_string(),
+            false => "save-all".to_string(),
+        }
+    }
+
+    /// Succeeds once the server reports `Saved the game`, or `Saved the
+    /// world` on older versions.
+    fn parse_response(response: &str) -> Result<()> {
+        match response.contains("Saved the") {
+            true => Ok(()),
+            false => Err(unexpected(response)),
+        }
+    }
+}
+
+/// A chat color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    Black,
+    DarkBlue,
+    DarkGreen,
+    DarkAqua,
+    DarkRed,
+    DarkPurple,
+    Gold,
+    Gray,
+    DarkGray,
+    Blue,
+    Green,
+    Aqua,
+    Red,
+    LightPurple,
+    Yellow,
+    White,
+}
+
+impl Color {
+    fn name(self) -> &'static str {
+        match self {
+            Color::Black => "black",
+            Color::DarkBlue => "dark_blue",
+            Color::DarkGreen => "dark_green",
+            Color::DarkAqua => "dark_aqua",
+            Color::DarkRed => "dark_red",
+            Color::DarkPurple => "dark_purple",
+            Color::Gold => "gold",
+            Color::Gray => "gray",
+            Color::DarkGray => "dark_gray",
+            Color::Blue => "blue",
+            Color::Green => "green",
+            Color::Aqua => "aqua",
+            Color::Red => "red",
+            Color::LightPurple => "light_purple",
+            Color::Yellow => "yellow",
+            Color::White => "white",
+        }
+    }
+}
+
+/// A JSON text component, as shown by `tellraw`.
+///
+/// Displayed as its JSON:
+///
+/// ```
+/// use specul::minecraft::{Color, Component};
+///
+/// let component = Component::text("Hello ")
+///     .color(Color::Gold)
+///     .extra(Component::text("world").italic(true));
+///
+/// assert_eq!(
+///     component.to_string(),
+///     r#"{"text":"Hello ","color":"gold","extra":[{"text":"world","italic":true}]}"#
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Component {
+    text: String,
+    color: Option<Color>,
+    bold: Option<bool>,
+    italic: Option<bool>,
+    underlined: Option<bool>,
+    strikethrough: Option<bool>,
+    obfuscated: Option<bool>,
+    extra: Vec<Component>,
+}
+
+impl Component {
+    /// A component showing `text`.
+    pub fn text(text: impl Into<String>) -> Self {
+        Component {
+            text: text.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn bold(mut self, bold: bool) -> Self {
+        self.bold = Some(bold);
+        self
+    }
+
+    pub fn italic(mut self, italic: bool) -> Self {
+        self.italic = Some(italic);
+        self
+    }
+
+    pub fn underlined(mut self, underlined: bool) -> Self {
+        self.underlined = Some(underlined);
+        self
+    }
+
+    pub fn strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = Some(strikethrough);
+        self
+    }
+
+    pub fn obfuscated(mut self, obfuscated: bool) -> Self {
+        self.obfuscated = Some(obfuscated);
+        self
+    }
+
+    /// Appends a component shown after this one, inheriting its style.
+    pub fn extra(mut self, component: Component) -> Self {
+        self.extra.push(component);
+        self
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("{\"text\":")?;
+        write_json_string(f, &self.text)?;
+
+        if let Some(color) = self.color {
+            write!(f, ",\"color\":\"{}\"", color.name())?;
+        }
+
+        let styles = [
+            ("bold", self.bold),
+            ("italic", self.italic),
+            ("underlined", self.underlined),
+            ("strikethrough", self.strikethrough),
+            ("obfuscated", self.obfuscated),
+        ];
+
+        for (name, value) in styles {
+            if let Some(value) = value {
+                write!(f, ",\"{}\":{}", name, value)?;
+            }
+        }
+
+        if !self.extra.is_empty() {
+            f.write_str(",\"extra\":[")?;
+
+            for (i, component) in self.extra.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(",")?;
+                }
+                write!(f, "{}", component)?;
+            }
+
+            f.write_str("]")?;
+        }
+
+        f.write_str("}")
+    }
+}
+
+fn write_json_string(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    f.write_str("\"")?;
+
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{}", c)?,
+        }
+    }
+
+    f.write_str("\"")
+}
+
+/// `tellraw <target> <component>`, which shows a message to players chosen
+/// by a name or selector such as `@a`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Tellraw {
+    pub target: String,
+    pub component: Component,
+}
+
+impl Tellraw {
+    pub fn new(target: impl Into<String>, component: Component) -> Self {
+        Tellraw {
+            target: target.into(),
+            component,
+        }
+    }
+}
+
+impl Command for Tellraw {
+    type Output = ();
+
+    fn to_command_string(&self) -> String {
+        format!("tellraw {} {}", self.target, self.component)
+    }
+
+    /// Succeeds on the empty response `tellraw` gives when it shows the
+    /// message, and fails on anything else, such as `No player was found`.
+    fn parse_response(response: &str) -> Result<()> {
+        match response.trim().is_empty() {
+            true => Ok(()),
+            false => Err(unexpected(response)),
+        }
+    }
+}
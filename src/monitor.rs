@@ -0,0 +1,271 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use tokio::sync::{broadcast, mpsc};
+
+use crate::{metrics::MetricsHook, Metrics, Packet, State};
+
+/// How many packets a console stream buffers before dropping new ones.
+const CONSOLE_CAPACITY: usize = 256;
+
+/// Something that happened to a connection, delivered to
+/// [`ConnectionMonitor::events`] subscribers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Event {
+    /// The connection is being re-established after a failure.
+    Reconnecting,
+    /// The connection was re-established, and re-authenticated if it had
+    /// been authenticated before.
+    Reconnected,
+    /// A packet arrived that does not answer the command being executed,
+    /// such as late console output queued behind the previous response.
+    Unsolicited(Packet),
+    /// The connection was found to be gone, by a failed keep-alive or the
+    /// server closing it. Only emitted by `RconClient`.
+    Disconnected,
+}
+
+/// An item of a [`console_messages`](crate::Connection::console_messages)
+/// stream.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ConsoleItem {
+    /// A packet that answers no request.
+    Packet(Packet),
+    /// The connection was re-established, so anything the server pushed
+    /// while it was down is lost. The `console_command` is run again right
+    /// after this.
+    Reconnected,
+}
+
+/// Traffic counters for a connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Stats {
+    /// Commands executed.
+    pub commands: u64,
+    /// Packets written to the io.
+    pub packets_sent: u64,
+    /// Packets decoded from the io.
+    pub packets_received: u64,
+    /// Bytes written to the io.
+    pub bytes_sent: u64,
+    /// Bytes read from the io.
+    pub bytes_received: u64,
+}
+
+/// State shared between a connection and its monitors.
+#[derive(Debug)]
+pub(crate) struct Shared {
+    state: AtomicU8,
+    commands: AtomicU64,
+    packets_sent: AtomicU64,
+    packets_received: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    peer_addr: Mutex<Option<SocketAddr>>,
+    last_error: Mutex<Option<String>>,
+    events: broadcast::Sender<Event>,
+    console: Mutex<Option<mpsc::Sender<ConsoleItem>>>,
+    metrics: MetricsHook,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Shared::with_metrics(MetricsHook::default())
+    }
+}
+
+impl Shared {
+    pub fn with_metrics(metrics: MetricsHook) -> Self {
+        Shared {
+            state: AtomicU8::default(),
+            commands: AtomicU64::default(),
+            packets_sent: AtomicU64::default(),
+            packets_received: AtomicU64::default(),
+            bytes_sent: AtomicU64::default(),
+            bytes_received: AtomicU64::default(),
+            peer_addr: Mutex::default(),
+            last_error: Mutex::default(),
+            events: broadcast::channel(16).0,
+            console: Mutex::default(),
+            metrics,
+        }
+    }
+
+    pub fn state(&self) -> State {
+        match self.state.load(Ordering::Relaxed) {
+            0 => State::Connected,
+            1 => State::Authenticated,
+            2 => State::Failed,
+            _ => State::Closed,
+        }
+    }
+
+    pub fn set_state(&self, state: State) {
+        let value = match state {
+            State::Connected => 0,
+            State::Authenticated => 1,
+            State::Failed => 2,
+            State::Closed => 3,
+        };
+
+        self.state.store(value, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats {
+            commands: self.commands.load(Ordering::Relaxed),
+            packets_sent: self.packets_sent.load(Ordering::Relaxed),
+            packets_received: self.packets_received.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn record_command(&self) {
+        self.commands.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics() {
+            metrics.command_sent();
+        }
+    }
+
+    pub fn record_sent(&self, bytes: usize) {
+        self.packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics() {
+            metrics.packet_sent(bytes);
+        }
+    }
+
+    pub fn record_packet_received(&self) {
+        self.packets_received.fetch_add(1, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics() {
+            metrics.packet_received();
+        }
+    }
+
+    pub fn record_bytes_received(&self, bytes: usize) {
+        self.bytes_received
+            .fetch_add(bytes as u64, Ordering::Relaxed);
+        if let Some(metrics) = self.metrics() {
+            metrics.bytes_received(bytes);
+        }
+    }
+
+    /// Returns the [`Metrics`] to report to beyond the built-in counters.
+    pub fn metrics(&self) -> Option<&dyn Metrics> {
+        self.metrics.get()
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        *self.peer_addr.lock().unwrap()
+    }
+
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error.lock().unwrap().clone()
+    }
+
+    pub fn set_last_error(&self, error: Option<String>) {
+        *self.last_error.lock().unwrap() = error;
+    }
+
+    pub fn emit(&self, event: Event) {
+        // Nobody listening is fine.
+        let _ = self.events.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.events.subscribe()
+    }
+
+    /// Passes a packet that answers no request to the console stream, if
+    /// there is one with room, and to event subscribers.
+    pub fn unsolicited(&self, packet: Packet) {
+        let mut console = self.console.lock().unwrap();
+
+        // A full stream drops the packet rather than stall commands.
+        if let Some(sender) = console.as_ref() {
+            let item = ConsoleItem::Packet(packet.clone());
+
+            if let Err(mpsc::error::TrySendError::Closed(_)) = sender.try_send(item) {
+                *console = None;
+            }
+        }
+
+        drop(console);
+        self.emit(Event::Unsolicited(packet));
+    }
+
+    /// Marks a reconnect in the console stream, returning whether there is
+    /// one still being read.
+    pub fn console_reconnected(&self) -> bool {
+        let mut console = self.console.lock().unwrap();
+
+        if let Some(sender) = console.as_ref() {
+            match sender.try_send(ConsoleItem::Reconnected) {
+                Err(mpsc::error::TrySendError::Closed(_)) => *console = None,
+                _ => return true,
+            }
+        }
+
+        false
+    }
+
+    pub fn console(&self) -> mpsc::Receiver<ConsoleItem> {
+        let (sender, receiver) = mpsc::channel(CONSOLE_CAPACITY);
+        *self.console.lock().unwrap() = Some(sender);
+        receiver
+    }
+
+    #[cfg_attr(not(feature = "tcp"), allow(dead_code))]
+    pub fn set_peer_addr(&self, addr: Option<SocketAddr>) {
+        *self.peer_addr.lock().unwrap() = addr;
+    }
+}
+
+/// A cheaply cloneable, read-only view of a [`Connection`](crate::Connection).
+///
+/// Obtained from [`Connection::monitor`](crate::Connection::monitor), it is
+/// updated by the connection as it operates, so metrics and state can be
+/// observed without access to the connection itself.
+#[derive(Debug, Clone)]
+pub struct ConnectionMonitor {
+    shared: Arc<Shared>,
+}
+
+impl ConnectionMonitor {
+    pub(crate) fn new(shared: Arc<Shared>) -> Self {
+        ConnectionMonitor { shared }
+    }
+
+    /// Returns the current state of the connection.
+    pub fn state(&self) -> State {
+        self.shared.state()
+    }
+
+    /// Returns a snapshot of the connection's traffic counters.
+    pub fn stats(&self) -> Stats {
+        self.shared.stats()
+    }
+
+    /// Returns the address of the server, if the connection was made over TCP
+    /// by this crate.
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.shared.peer_addr()
+    }
+
+    /// Returns a description of the error that made the connection's last
+    /// operation fail, or `None` if it succeeded.
+    pub fn last_error(&self) -> Option<String> {
+        self.shared.last_error()
+    }
+
+    /// Subscribes to the connection's [`Event`]s from now on.
+    pub fn events(&self) -> broadcast::Receiver<Event> {
+        self.shared.subscribe()
+    }
+}
@@ -1,18 +1,224 @@
-use std::io;
+use std::{borrow::Cow, fmt, io, ops::Deref};
 
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, BufWriter};
+use bytes::{Bytes, BytesMut};
 
+use crate::Quirks;
+
+/// The width of the length prefix in front of every packet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrefixWidth {
+    /// A 2-byte prefix, used by some nonstandard forks.
+    Two,
+    /// The standard 4-byte prefix.
+    #[default]
+    Four,
+}
+
+/// Settings that control how packets are framed on the wire.
+///
+/// The length prefix only counts the bytes that follow it, so the id, type
+/// and terminator bytes are added to the payload length whatever the prefix
+/// width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Framing {
+    pub prefix_width: PrefixWidth,
+    /// The number of NUL bytes terminating each packet, either 1 or 2. The
+    /// same count is used when writing and reading, so the two sides cannot
+    /// disagree. The standard is 2.
+    pub trailing_nulls: u8,
+    /// Reject packets whose terminator bytes are not NUL.
+    ///
+    /// By default such bytes are assumed to belong to the next packet, sent by
+    /// a server that counts the terminator in the length but omits it, and
+    /// are left in place for the next read.
+    pub strict_terminator: bool,
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing {
+            prefix_width: PrefixWidth::default(),
+            trailing_nulls: 2,
+            strict_terminator: false,
+        }
+    }
+}
+
+impl Framing {
+    /// The number of bytes counted by the length prefix besides the payload.
+    pub(crate) fn overhead(self) -> usize {
+        8 + self.trailing_nulls as usize
+    }
+}
+
+impl PrefixWidth {
+    pub(crate) fn len(self) -> usize {
+        match self {
+            PrefixWidth::Two => 2,
+            PrefixWidth::Four => 4,
+        }
+    }
+}
+
+/// The numeric packet types written and accepted on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PacketTypeIds {
+    pub auth: i32,
+    pub auth_response: i32,
+    pub exec_command: i32,
+    pub response_value: i32,
+}
+
+/// Everything that affects what a connection puts on and expects from the
+/// wire, as returned by [`Connection::wire_config`](crate::Connection::wire_config).
+///
+/// Comparing the output for a server that works with one that does not is a
+/// quick way to find a protocol mismatch. With the `serde` feature it can be
+/// serialized for logging.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct WireConfig {
+    /// The byte order of every integer, always `"little"`.
+    pub endianness: &'static str,
+    pub framing: Framing,
+    pub packet_types: PacketTypeIds,
+    /// The encoding of payloads, the [`Charset::name`](crate::Charset::name)
+    /// of the `charset`.
+    pub encoding: &'static str,
+    /// The largest command payload that will be sent.
+    pub max_payload_size: usize,
+    /// The largest length prefix accepted in the first packet when
+    /// `validate_first_packet` is set.
+    pub max_plausible_length: i32,
+    /// The largest length prefix accepted in any packet.
+    pub max_incoming_packet_size: usize,
+    pub validate_first_packet: bool,
+    pub multiple_responses: bool,
+    pub quirks: Quirks,
+    pub command_prefix: Option<String>,
+}
+
+impl PacketTypeIds {
+    pub(crate) fn new() -> Self {
+        PacketTypeIds {
+            auth: PacketType::Authentication.format(),
+            auth_response: PacketType::AuthenticationResponse.format(),
+            exec_command: PacketType::Message.format(),
+            response_value: PacketType::Response.format(),
+        }
+    }
+}
+
+/// The largest length a well-behaved server is expected to send.
+pub(crate) const MAX_PLAUSIBLE_LENGTH: i32 = 64 * 1024;
+
+/// The fields before a packet's payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PacketHeader {
+    /// The length field, counting everything after it.
+    pub length: i32,
+    pub id: i32,
+    pub packet_type: PacketType,
+}
+
+/// The fixed-size start of a packet, as read from the wire.
+#[derive(Debug, Clone)]
+pub(crate) struct Header {
+    pub length: i32,
+    pub id: i32,
+    pub packet_type: PacketType,
+    pub raw: Vec<u8>,
+}
+
+impl Header {
+    /// Parses the header at the front of `buffer` without consuming it,
+    /// returning `None` if not enough bytes are buffered.
+    pub fn peek(buffer: &[u8], framing: Framing) -> Option<Header> {
+        let prefix = framing.prefix_width.len();
+        let raw = buffer.get(..prefix + 8)?;
+
+        let length = match framing.prefix_width {
+            PrefixWidth::Two => u16::from_le_bytes([raw[0], raw[1]]) as i32,
+            PrefixWidth::Four => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]),
+        };
+        let id = i32::from_le_bytes([
+            raw[prefix],
+            raw[prefix + 1],
+            raw[prefix + 2],
+            raw[prefix + 3],
+        ]);
+        let packet_type = i32::from_le_bytes([
+            raw[prefix + 4],
+            raw[prefix + 5],
+            raw[prefix + 6],
+            raw[prefix + 7],
+        ]);
+
+        Some(Header {
+            length,
+            id,
+            packet_type: PacketType::parse(packet_type, true),
+            raw: raw.to_vec(),
+        })
+    }
+
+    pub fn fields(&self) -> PacketHeader {
+        PacketHeader {
+            length: self.length,
+            id: self.id,
+            packet_type: self.packet_type,
+        }
+    }
+
+    /// Whether the header looks like it came from an RCON server.
+    pub fn is_plausible(&self, framing: Framing) -> bool {
+        (framing.overhead() as i32..=MAX_PLAUSIBLE_LENGTH).contains(&self.length)
+            && !matches!(self.packet_type, PacketType::Unknown(_))
+    }
+}
+
+/// The type of a packet.
+///
+/// `SERVERDATA_RESPONSE_VALUE` (0) is used both for command output and for
+/// the empty packet a server mirrors back when sent one, so the two cannot be
+/// told apart by type. Detecting the end of a multi-packet response relies on
+/// the packet id and an empty payload instead.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Ord, PartialOrd, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PacketType {
+    /// `SERVERDATA_AUTH` (3).
     Authentication,
+    /// `SERVERDATA_AUTH_RESPONSE` (2), as received.
     AuthenticationResponse,
+    /// `SERVERDATA_EXECCOMMAND` (2), as sent.
     Message,
+    /// `SERVERDATA_RESPONSE_VALUE` (0), for command output and mirrored
+    /// empty packets alike.
     Response,
+    /// Any other type.
     Unknown(i32),
 }
 
 impl PacketType {
-    fn parse(value: i32, response: bool) -> Self {
+    /// Returns the type of the number `value`, which means
+    /// [`AuthenticationResponse`](PacketType::AuthenticationResponse) rather
+    /// than [`Message`](PacketType::Message) for 2 if the packet comes from
+    /// the server.
+    ///
+    /// ```
+    /// use specul::PacketType;
+    ///
+    /// assert_eq!(PacketType::parse(2, true), PacketType::AuthenticationResponse);
+    /// assert_eq!(PacketType::parse(2, false), PacketType::Message);
+    /// assert_eq!(PacketType::parse(100, true), PacketType::Unknown(100));
+    /// assert_eq!(i32::from(PacketType::Unknown(100)), 100);
+    /// ```
+    pub fn parse(value: i32, response: bool) -> Self {
         match value {
             3 => PacketType::Authentication,
             2 if response => PacketType::AuthenticationResponse,
@@ -22,7 +228,7 @@ impl PacketType {
         }
     }
 
-    fn format(&self) -> i32 {
+    pub(crate) fn format(&self) -> i32 {
         match self {
             PacketType::Authentication => 3,
             PacketType::AuthenticationResponse => 2,
@@ -33,16 +239,36 @@ impl PacketType {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
+/// The number written on the wire for the type.
+impl From<PacketType> for i32 {
+    fn from(packet_type: PacketType) -> i32 {
+        packet_type.format()
+    }
+}
+
+/// A packet as written to or read from the wire.
+///
+/// The payload is kept as the bytes received, sliced out of the
+/// connection's read buffer without copying, and only decoded as text when
+/// asked with [`to_str`](Packet::to_str) or when a command's response is
+/// built.
+///
+/// With the `serde` feature, packets serialize with the payload as a string
+/// if it is UTF-8 and as an array of bytes if not, for logging them as JSON.
+/// Authentication packets hold the password in the clear.
+#[derive(Clone, PartialEq, Eq, Ord, PartialOrd, Hash)]
 pub struct Packet {
     pub id: i32,
     pub length: i32,
     pub packet_type: PacketType,
-    pub payload: String,
+    pub payload: Bytes,
 }
 
 impl Packet {
-    pub fn new(id: i32, packet_type: PacketType, payload: String) -> Self {
+    /// Creates a packet of any payload, such as a `String` or a
+    /// `&'static [u8]`.
+    pub fn new(id: i32, packet_type: PacketType, payload: impl Into<Bytes>) -> Self {
+        let payload = payload.into();
         let length = 10 + payload.len() as i32;
         Packet {
             id,
@@ -56,50 +282,173 @@ impl Packet {
         self.id < 0
     }
 
-    pub(crate) async fn write_to_io<T: Unpin + AsyncWrite>(&self, io: &mut T) -> io::Result<()> {
-        let mut writer = BufWriter::new(io);
+    /// Returns the fields before the payload.
+    pub fn header(&self) -> PacketHeader {
+        PacketHeader {
+            length: self.length,
+            id: self.id,
+            packet_type: self.packet_type,
+        }
+    }
 
-        writer.write_i32_le(self.length).await?;
-        writer.write_i32_le(self.id).await?;
-        writer.write_i32_le(self.packet_type.format()).await?;
-        writer.write_all(self.payload.as_bytes()).await?;
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.payload
+    }
 
-        // Ending empty strings
-        writer.write_all(&[0x00, 0x00]).await?;
+    /// Returns the payload as text, failing with an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error if it is not UTF-8.
+    ///
+    /// ```
+    /// use specul::{Packet, PacketType};
+    ///
+    /// let packet = Packet::new(1, PacketType::Response, "hostname: My Server");
+    /// assert_eq!(packet.to_str()?, "hostname: My Server");
+    ///
+    /// let packet = Packet::new(1, PacketType::Response, &b"caf\xe9"[..]);
+    /// assert!(packet.to_str().is_err());
+    /// # Ok::<(), specul::Error>(())
+    /// ```
+    pub fn to_str(&self) -> crate::Result<&str> {
+        std::str::from_utf8(&self.payload).map_err(|_| {
+            crate::Error::Io(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid UTF-8 payload",
+            ))
+        })
+    }
 
-        writer.flush().await?;
+    pub(crate) fn holds_password(&self) -> bool {
+        self.packet_type == PacketType::Authentication
+    }
 
-        Ok(())
+    /// Emits a debug event for the packet having been written, as `bytes`
+    /// on the wire.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace_sent(&self, bytes: usize) {
+        tracing::debug!(
+            id = self.id,
+            packet_type = ?self.packet_type,
+            length = self.length,
+            bytes,
+            payload = &*self.shown_payload(),
+            "sent packet"
+        );
     }
 
-    pub(crate) async fn read_from_io<T: Unpin + AsyncRead>(io: &mut T) -> io::Result<Self> {
-        let mut reader = BufReader::new(io);
+    #[cfg(feature = "tracing")]
+    pub(crate) fn trace_received(&self) {
+        tracing::debug!(
+            id = self.id,
+            packet_type = ?self.packet_type,
+            length = self.length,
+            payload = &*self.shown_payload(),
+            "received packet"
+        );
+    }
 
-        let length = reader.read_i32_le().await?;
-        let id = reader.read_i32_le().await?;
-        let packet_type = PacketType::parse(reader.read_i32_le().await?, true);
+    /// The payload, unless it is a password, with anything that is not
+    /// UTF-8 replaced.
+    fn shown_payload(&self) -> Cow<'_, str> {
+        match self.holds_password() {
+            true => Cow::Borrowed("<redacted>"),
+            false => String::from_utf8_lossy(&self.payload),
+        }
+    }
+}
 
-        let mut buffer = vec![0; length as usize - 10];
-        reader.read_exact(&mut buffer).await?;
+/// Shows the payload of authentication packets as `<redacted>`.
+impl fmt::Debug for Packet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Packet")
+            .field("id", &self.id)
+            .field("length", &self.length)
+            .field("packet_type", &self.packet_type)
+            .field("payload", &self.shown_payload())
+            .finish()
+    }
+}
 
-        let payload = String::from_utf8(buffer);
+/// A packet being sent, whose payload is zeroed when it is dropped if it is
+/// a password, with the `secrecy` feature.
+pub(crate) struct Outgoing(pub Packet);
 
-        let payload = match payload {
-            Ok(payload) => payload,
-            Err(_) => Err(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid UTF-8 payload",
-            ))?,
-        };
+impl Deref for Outgoing {
+    type Target = Packet;
 
-        // Skip ending empty strings
-        reader.read_u16_le().await?;
+    fn deref(&self) -> &Packet {
+        &self.0
+    }
+}
 
-        Ok(Packet {
-            id,
-            length,
-            packet_type,
-            payload,
-        })
+#[cfg(feature = "secrecy")]
+impl Drop for Outgoing {
+    fn drop(&mut self) {
+        if !self.0.holds_password() {
+            return;
+        }
+
+        // Payloads made from a `String` are not shared, so can be zeroed.
+        if let Ok(mut payload) = std::mem::take(&mut self.0.payload).try_into_mut() {
+            zeroize::Zeroize::zeroize(&mut payload[..]);
+        }
+    }
+}
+
+/// A buffer packets are encoded into, kept for the life of a connection so
+/// its allocation is reused.
+///
+/// It is emptied after every write, and zeroed first with the `secrecy`
+/// feature, since it may hold a password.
+#[derive(Default)]
+pub(crate) struct WireBuffer(BytesMut);
+
+impl WireBuffer {
+    /// Empties the buffer, returning it to encode the next packet into.
+    pub fn start(&mut self) -> &mut BytesMut {
+        self.clear();
+        &mut self.0
+    }
+
+    /// Returns the buffer to encode another packet after those queued.
+    #[cfg_attr(not(feature = "stream"), allow(dead_code))]
+    pub fn append(&mut self) -> &mut BytesMut {
+        &mut self.0
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Drops the first `written` bytes, once they were written.
+    #[cfg_attr(not(feature = "stream"), allow(dead_code))]
+    pub fn consume(&mut self, written: usize) {
+        #[cfg(feature = "secrecy")]
+        zeroize::Zeroize::zeroize(&mut self.0[..written]);
+
+        bytes::Buf::advance(&mut self.0, written);
+    }
+
+    pub fn clear(&mut self) {
+        #[cfg(feature = "secrecy")]
+        zeroize::Zeroize::zeroize(&mut self.0[..]);
+
+        self.0.clear();
+    }
+}
+
+/// Bytes left by a cancelled write are zeroed too.
+impl Drop for WireBuffer {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Shows only the size, since the buffer may hold a password.
+impl fmt::Debug for WireBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WireBuffer")
+            .field("len", &self.0.len())
+            .field("capacity", &self.0.capacity())
+            .finish()
     }
 }
@@ -0,0 +1,445 @@
+//! Helpers for pulling structured data out of command responses.
+
+use std::{collections::HashMap, fmt, net::SocketAddr, str::FromStr};
+
+/// The separators recognised by most Source and Minecraft commands.
+pub const DEFAULT_SEPARATORS: &[char] = &[':', '='];
+
+/// Parses `key: value` and `"key" = "value"` style lines into a map.
+///
+/// Each line is split at the first of `separators` that is not inside
+/// quotes. Keys and values are trimmed, and quoted values are unquoted with
+/// anything after the closing quote ignored, so cvar output such as
+/// `"sv_cheats" = "0" ( def. "0" )` yields `sv_cheats` => `0`. Lines without a
+/// separator or with an empty key are skipped, and later keys overwrite
+/// earlier ones.
+///
+/// ```
+/// use specul::parse::{parse_pairs, DEFAULT_SEPARATORS};
+///
+/// let pairs = parse_pairs(
+///     "hostname: My Server\n\"sv_cheats\" = \"0\" ( def. \"0\" )\n",
+///     DEFAULT_SEPARATORS,
+/// );
+///
+/// assert_eq!(pairs["hostname"], "My Server");
+/// assert_eq!(pairs["sv_cheats"], "0");
+/// ```
+pub fn parse_pairs(text: &str, separators: &[char]) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| split_unquoted(line, separators))
+        .map(|(key, value)| (unquote(key).to_string(), unquote(value).to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect()
+}
+
+fn split_unquoted<'a>(line: &'a str, separators: &[char]) -> Option<(&'a str, &'a str)> {
+    let mut quoted = false;
+
+    for (index, c) in line.char_indices() {
+        if c == '"' {
+            quoted = !quoted;
+        } else if !quoted && separators.contains(&c) {
+            return Some((&line[..index], &line[index + c.len_utf8()..]));
+        }
+    }
+
+    None
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+
+    match value.strip_prefix('"') {
+        Some(rest) => match rest.find('"') {
+            Some(end) => &rest[..end],
+            None => rest,
+        },
+        None => value,
+    }
+}
+
+/// An error from parsing an id.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParseIdError {
+    input: String,
+}
+
+impl fmt::Display for ParseIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid id {:?}", self.input)
+    }
+}
+
+impl std::error::Error for ParseIdError {}
+
+impl ParseIdError {
+    fn new(input: &str) -> Self {
+        ParseIdError {
+            input: input.to_string(),
+        }
+    }
+}
+
+/// The offset between an individual account's 32-bit id and its SteamID64.
+const STEAM_ID64_BASE: u64 = 76561197960265728;
+
+/// A Steam account id.
+///
+/// Parses the legacy `STEAM_X:Y:Z`, the Steam3 `[U:1:N]` and the 64-bit
+/// forms, and displays as the 64-bit form.
+///
+/// ```
+/// use specul::parse::SteamId;
+///
+/// let legacy: SteamId = "STEAM_1:0:11101".parse().unwrap();
+/// let steam3: SteamId = "[U:1:22202]".parse().unwrap();
+///
+/// assert_eq!(legacy, steam3);
+/// assert_eq!(legacy.to_string(), "76561197960287930");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SteamId(u64);
+
+impl SteamId {
+    /// Returns the 64-bit form of the id.
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// Returns the 32-bit account id, as used in the Steam3 form.
+    pub fn account_id(self) -> u32 {
+        self.0.wrapping_sub(STEAM_ID64_BASE) as u32
+    }
+}
+
+impl FromStr for SteamId {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseIdError::new(s);
+
+        if let Some(rest) = s.strip_prefix("STEAM_") {
+            let mut parts = rest.split(':');
+            let _universe = parts.next().ok_or_else(error)?;
+            let low: u64 = parts
+                .next()
+                .and_then(|p| p.parse().ok())
+                .ok_or_else(error)?;
+            // The account id, `high * 2 + low`, is 32 bits.
+            let high: u32 = parts
+                .next()
+                .and_then(|p| p.parse().ok())
+                .filter(|&high| high <= u32::MAX / 2)
+                .ok_or_else(error)?;
+
+            if low > 1 || parts.next().is_some() {
+                return Err(error());
+            }
+
+            Ok(SteamId(STEAM_ID64_BASE + high as u64 * 2 + low))
+        } else if let Some(rest) = s.strip_prefix("[U:").and_then(|r| r.strip_suffix(']')) {
+            let account: u32 = rest
+                .split_once(':')
+                .and_then(|(_, account)| account.parse().ok())
+                .ok_or_else(error)?;
+
+            Ok(SteamId(STEAM_ID64_BASE + account as u64))
+        } else {
+            match s.parse::<u64>() {
+                Ok(id) if id >= STEAM_ID64_BASE => Ok(SteamId(id)),
+                _ => Err(error()),
+            }
+        }
+    }
+}
+
+impl fmt::Display for SteamId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A UUID, such as a Minecraft player id.
+///
+/// Parses the hyphenated and plain 32-digit forms, and displays as lowercase
+/// hyphenated.
+///
+/// ```
+/// use specul::parse::Uuid;
+///
+/// let uuid: Uuid = "069A79F444E94726A5BEFCA90E38AAF5".parse().unwrap();
+///
+/// assert_eq!(uuid.to_string(), "069a79f4-44e9-4726-a5be-fca90e38aaf5");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uuid(u128);
+
+impl Uuid {
+    /// Returns the UUID as a 128-bit integer.
+    pub fn as_u128(self) -> u128 {
+        self.0
+    }
+}
+
+impl FromStr for Uuid {
+    type Err = ParseIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hyphenated = s.len() == 36
+            && s.char_indices()
+                .all(|(i, c)| matches!(i, 8 | 13 | 18 | 23) == (c == '-'));
+        let digits: String = s.chars().filter(|&c| c != '-').collect();
+
+        if (!hyphenated && s.len() != 32)
+            || digits.len() != 32
+            || !digits.chars().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(ParseIdError::new(s));
+        }
+
+        u128::from_str_radix(&digits, 16)
+            .map(Uuid)
+            .map_err(|_| ParseIdError::new(s))
+    }
+}
+
+impl fmt::Display for Uuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let hex = format!("{:032x}", self.0);
+        write!(
+            f,
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        )
+    }
+}
+
+/// Players parsed from a response, along with the lines that looked like
+/// player entries but could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Players<P> {
+    pub players: Vec<P>,
+    pub malformed: Vec<String>,
+}
+
+impl<P> Default for Players<P> {
+    fn default() -> Self {
+        Players {
+            players: Vec::new(),
+            malformed: Vec::new(),
+        }
+    }
+}
+
+/// A player listed by the Source `status` command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatusPlayer {
+    pub userid: u32,
+    pub name: String,
+    /// Not listed by CS2.
+    pub steam_id: Option<SteamId>,
+    /// How long the player has been connected, as shown, such as `01:23`.
+    pub connected: String,
+    pub ping: u32,
+    pub loss: u32,
+    /// The player's `ip:port`, unless the server does not show it, such as
+    /// for the listen server's own player.
+    pub address: Option<SocketAddr>,
+}
+
+/// Parses the player table of a Source `status` response: CS:GO, TF2 and
+/// Garry's Mod lines such as
+/// `# 2 "Name" STEAM_1:0:11101 00:35 50 0 active 10.0.0.7:27005`, or the
+/// rows of CS2's table, which have the name last and no SteamID.
+///
+/// Bots and connections still being set up are skipped, and player lines
+/// whose userid, id or numbers cannot be parsed are collected in
+/// [`Players::malformed`]. [`source::Status`](crate::source::Status) uses
+/// this for its player list.
+///
+/// ```
+/// use specul::parse::parse_source_status;
+///
+/// let status = "hostname: My Server\n\
+///               ## userid name uniqueid connected ping loss state\n\
+///               ##      2 \"Gordon\" STEAM_1:0:11101 00:35 50 0 active\n\
+///               ##      3 \"Alyx\" [U:1:nope] 01:10 40 0 active\n\
+///               ##      4 \"Bot\" BOT active\n";
+///
+/// let players = parse_source_status(status);
+///
+/// assert_eq!(players.players.len(), 1);
+/// assert_eq!(players.players[0].name, "Gordon");
+/// assert_eq!(players.malformed.len(), 1);
+/// ```
+pub fn parse_source_status(text: &str) -> Players<StatusPlayer> {
+    let mut players = Players::default();
+    let mut in_table = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim();
+
+        if trimmed == "#end" {
+            break;
+        }
+
+        if trimmed.starts_with("---") {
+            in_table = true;
+            continue;
+        }
+
+        match trimmed.strip_prefix('#') {
+            Some(rest) => {
+                in_table = true;
+                parse_source_line(line, rest, &mut players);
+            }
+            None if in_table && !trimmed.is_empty() => parse_cs2_line(line, &mut players),
+            None => {}
+        }
+    }
+
+    players
+}
+
+/// Parses `# 2 "Gordon" STEAM_1:0:11101 00:35 50 0 active 10.0.0.7:27005`,
+/// with extra columns such as CS:GO's slot and rate allowed.
+fn parse_source_line(line: &str, rest: &str, players: &mut Players<StatusPlayer>) {
+    // The header line has no quoted name.
+    let Some((userid, rest)) = rest.split_once('"') else {
+        return;
+    };
+    let Some((name, rest)) = rest.rsplit_once('"') else {
+        players.malformed.push(line.to_string());
+        return;
+    };
+
+    let columns: Vec<&str> = rest.split_whitespace().collect();
+
+    if columns.first() == Some(&"BOT") {
+        return;
+    }
+
+    let player = userid
+        .split_whitespace()
+        .next()
+        .and_then(|userid| userid.parse().ok())
+        .and_then(|userid| status_player(userid, name, &columns, true));
+
+    match player {
+        Some(player) => players.players.push(player),
+        None => players.malformed.push(line.to_string()),
+    }
+}
+
+/// Parses CS2's `    2    01:23   50    0      active 786432 10.0.0.7:27005
+/// 'Gordon'`.
+fn parse_cs2_line(line: &str, players: &mut Players<StatusPlayer>) {
+    let Some((columns, name)) = line.split_once('\'') else {
+        return;
+    };
+    let name = name.strip_suffix('\'').unwrap_or(name);
+    let columns: Vec<&str> = columns.split_whitespace().collect();
+
+    // The column header.
+    if columns.first() == Some(&"id") {
+        return;
+    }
+
+    // Bots, and connections not yet in the game.
+    if matches!(columns.get(1), Some(&"BOT") | Some(&"[NoChan]")) || columns.is_empty() {
+        return;
+    }
+
+    let player = columns
+        .first()
+        .and_then(|userid| userid.parse().ok())
+        .and_then(|userid| status_player(userid, name, &columns[1..], false));
+
+    match player {
+        Some(player) => players.players.push(player),
+        None => players.malformed.push(line.to_string()),
+    }
+}
+
+/// Builds a player from the columns after the userid and name: the SteamID
+/// if `with_steam_id`, then the connected time, ping and loss, with the
+/// address last.
+fn status_player(
+    userid: u32,
+    name: &str,
+    columns: &[&str],
+    with_steam_id: bool,
+) -> Option<StatusPlayer> {
+    let (steam_id, columns) = match with_steam_id {
+        true => (Some(columns.first()?.parse().ok()?), &columns[1..]),
+        false => (None, columns),
+    };
+
+    Some(StatusPlayer {
+        userid,
+        name: name.to_string(),
+        steam_id,
+        connected: columns.first()?.to_string(),
+        ping: columns.get(1)?.parse().ok()?,
+        loss: columns.get(2)?.parse().ok()?,
+        address: columns.last().and_then(|adr| adr.parse().ok()),
+    })
+}
+
+/// A player listed by the Minecraft `list` command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MinecraftPlayer {
+    pub name: String,
+    /// Only present in the output of `list uuids`.
+    pub uuid: Option<Uuid>,
+}
+
+/// Parses the players of a Minecraft `list` or `list uuids` response, such
+/// as `There are 2 of a max of 20 players online: Steve, Alex`.
+///
+/// Entries with an unparsable UUID are collected in [`Players::malformed`].
+///
+/// ```
+/// use specul::parse::parse_minecraft_list;
+///
+/// let list = "There are 2 of a max of 20 players online: \
+///             Steve (069a79f4-44e9-4726-a5be-fca90e38aaf5), Alex (not-a-uuid)";
+///
+/// let players = parse_minecraft_list(list);
+///
+/// assert_eq!(players.players[0].name, "Steve");
+/// assert!(players.players[0].uuid.is_some());
+/// assert_eq!(players.malformed, vec!["Alex (not-a-uuid)".to_string()]);
+/// ```
+pub fn parse_minecraft_list(text: &str) -> Players<MinecraftPlayer> {
+    let mut players = Players::default();
+
+    let Some((_, names)) = text.split_once(':') else {
+        return players;
+    };
+
+    for entry in names.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let player = match entry.strip_suffix(')').and_then(|e| e.rsplit_once(" (")) {
+            Some((name, uuid)) => uuid.parse().map(|uuid| MinecraftPlayer {
+                name: name.to_string(),
+                uuid: Some(uuid),
+            }),
+            None => Ok(MinecraftPlayer {
+                name: entry.to_string(),
+                uuid: None,
+            }),
+        };
+
+        match player {
+            Ok(player) => players.players.push(player),
+            Err(_) => players.malformed.push(entry.to_string()),
+        }
+    }
+
+    players
+}
@@ -0,0 +1,321 @@
+//! A pool of authenticated connections to one server, for tools that run
+//! many commands at once.
+//!
+//! ```no_run
+//! # async fn run() -> specul::Result<()> {
+//! use specul::pool::{Pool, PoolOptionsBuilder};
+//!
+//! let options = PoolOptionsBuilder::default()
+//!     .min_size(2)
+//!     .max_size(8)
+//!     .health_check("echo")
+//!     .build()
+//!     .unwrap();
+//! let pool = Pool::connect("127.0.0.1:27015", "password", options).await?;
+//!
+//! println!("{:?}", pool.execute("status").await?);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use derive_builder::Builder;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{Semaphore, SemaphorePermit},
+};
+
+use crate::{Connection, Result, State};
+
+type ConnectFuture<T> = Pin<Box<dyn Future<Output = Result<Connection<T>>> + Send>>;
+
+/// How a [`Pool`] sizes and checks its connections.
+#[derive(Debug, Clone, Builder)]
+#[builder(pattern = "owned", build_fn(validate = "Self::validate"))]
+pub struct PoolOptions {
+    /// Connections opened up front, and kept open by [`Pool::fill`].
+    #[builder(default = "1")]
+    min_size: usize,
+    /// The most connections open at once. Callers beyond this wait their
+    /// turn.
+    #[builder(default = "4")]
+    max_size: usize,
+    /// A command run on a connection before it is handed out, once it has
+    /// been idle for `health_check_after`. Connections it fails on are
+    /// replaced.
+    #[builder(default, setter(into, strip_option))]
+    health_check: Option<String>,
+    #[builder(default = "Duration::from_secs(30)")]
+    health_check_after: Duration,
+}
+
+impl PoolOptionsBuilder {
+    fn validate(&self) -> std::result::Result<(), String> {
+        let min_size = self.min_size.unwrap_or(1);
+        let max_size = self.max_size.unwrap_or(4);
+
+        if max_size == 0 {
+            return Err("max_size must be at least 1".to_string());
+        }
+
+        if min_size > max_size {
+            return Err("min_size must not exceed max_size".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        PoolOptionsBuilder::default()
+            .build()
+            .expect("default pool options are valid")
+    }
+}
+
+/// How many connections a [`Pool`] holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoolStatus {
+    /// Connections open, idle or in use.
+    pub size: usize,
+    /// Connections waiting to be checked out.
+    pub idle: usize,
+}
+
+struct Idle<T> {
+    connection: Connection<T>,
+    since: Instant,
+}
+
+/// A pool of authenticated connections, opened on demand up to
+/// `max_size`.
+///
+/// Checkout is fair: callers waiting for a connection are served in the
+/// order they asked, and idle connections are handed out in turn. A
+/// connection that is closed, fails its health check or fails a command
+/// with an error that may leave its stream out of step is dropped, and the
+/// next checkout opens a replacement.
+pub struct Pool<T> {
+    connect: Box<dyn Fn() -> ConnectFuture<T> + Send + Sync>,
+    options: PoolOptions,
+    idle: Mutex<VecDeque<Idle<T>>>,
+    permits: Semaphore,
+    size: AtomicUsize,
+}
+
+impl<T> Pool<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    /// Creates a pool whose connections are opened, and authenticated, by
+    /// `connect`, and opens `min_size` of them.
+    pub async fn new<F, Fut>(options: PoolOptions, connect: F) -> Result<Self>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Connection<T>>> + Send + 'static,
+    {
+        let pool = Pool {
+            connect: Box::new(move || Box::pin(connect())),
+            permits: Semaphore::new(options.max_size),
+            options,
+            idle: Mutex::new(VecDeque::new()),
+            size: AtomicUsize::new(0),
+        };
+
+        pool.fill().await?;
+
+        Ok(pool)
+    }
+
+    /// Executes a command on the next free connection.
+    pub async fn execute(&self, command: &str) -> Result<Vec<String>> {
+        let mut connection = self.get().await?;
+
+        connection.reusable = false;
+        let result = connection.execute_command(command).await;
+        connection.reusable = match &result {
+            Ok(_) => true,
+            Err(error) => error.leaves_connection_usable(),
+        };
+
+        result
+    }
+
+    /// Checks out a connection, waiting for one to be free if `max_size` are
+    /// in use. It returns to the pool when the guard is dropped, unless it
+    /// is no longer authenticated.
+    pub async fn get(&self) -> Result<PooledConnection<'_, T>> {
+        let permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("the pool's semaphore is never closed");
+
+        while let Some(idle) = self.pop_idle() {
+            // Not counted while being checked, so a cancelled check cannot
+            // leave the count too high.
+            self.size.fetch_sub(1, Ordering::Relaxed);
+            let mut connection = idle.connection;
+
+            if self.is_alive(&mut connection, idle.since).await {
+                return Ok(self.check_out(connection, permit));
+            }
+        }
+
+        let connection = (self.connect)().await?;
+        Ok(self.check_out(connection, permit))
+    }
+
+    /// Opens connections until the pool holds `min_size`, such as from a
+    /// periodic task after the server restarts.
+    pub async fn fill(&self) -> Result<()> {
+        while self.size.load(Ordering::Relaxed) < self.options.min_size {
+            let connection = (self.connect)().await?;
+            self.size.fetch_add(1, Ordering::Relaxed);
+            self.idle.lock().unwrap().push_back(Idle {
+                connection,
+                since: Instant::now(),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns how many connections are open and idle.
+    pub fn status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.size.load(Ordering::Relaxed),
+            idle: self.idle.lock().unwrap().len(),
+        }
+    }
+
+    async fn is_alive(&self, connection: &mut Connection<T>, idle_since: Instant) -> bool {
+        if connection.state() != State::Authenticated {
+            return false;
+        }
+
+        match &self.options.health_check {
+            Some(command) if idle_since.elapsed() >= self.options.health_check_after => {
+                connection.execute_command(command).await.is_ok()
+            }
+            _ => true,
+        }
+    }
+
+    fn pop_idle(&self) -> Option<Idle<T>> {
+        self.idle.lock().unwrap().pop_front()
+    }
+
+    fn check_out<'a>(
+        &'a self,
+        connection: Connection<T>,
+        permit: SemaphorePermit<'a>,
+    ) -> PooledConnection<'a, T> {
+        self.size.fetch_add(1, Ordering::Relaxed);
+
+        PooledConnection {
+            pool: self,
+            connection: Some(connection),
+            reusable: true,
+            _permit: permit,
+        }
+    }
+}
+
+#[cfg(feature = "tcp")]
+impl Pool<tokio::net::TcpStream> {
+    /// Creates a pool of connections made with
+    /// [`Connection::connect`](Connection::connect).
+    pub async fn connect(addr: &str, password: &str, options: PoolOptions) -> Result<Self> {
+        let addr = addr.to_string();
+        let password = password.to_string();
+
+        Self::new(options, move || {
+            let addr = addr.clone();
+            let password = password.clone();
+            async move { Connection::connect(addr.as_str(), &password).await }
+        })
+        .await
+    }
+}
+
+impl<T> fmt::Debug for Pool<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pool")
+            .field("options", &self.options)
+            .field("size", &self.size.load(Ordering::Relaxed))
+            .finish_non_exhaustive()
+    }
+}
+
+/// A connection checked out of a [`Pool`], returned to it on drop.
+pub struct PooledConnection<'a, T> {
+    pool: &'a Pool<T>,
+    connection: Option<Connection<T>>,
+    /// Cleared while a command is in flight, so a cancelled command does not
+    /// leave its response for the next caller.
+    reusable: bool,
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> PooledConnection<'_, T> {
+    /// Drops the connection instead of returning it, such as after an error
+    /// that leaves it in an unknown state.
+    pub fn discard(mut self) {
+        self.reusable = false;
+    }
+}
+
+impl<T> Deref for PooledConnection<'_, T> {
+    type Target = Connection<T>;
+
+    fn deref(&self) -> &Connection<T> {
+        self.connection
+            .as_ref()
+            .expect("connection is present until dropped")
+    }
+}
+
+impl<T> DerefMut for PooledConnection<'_, T> {
+    fn deref_mut(&mut self) -> &mut Connection<T> {
+        self.connection
+            .as_mut()
+            .expect("connection is present until dropped")
+    }
+}
+
+impl<T> Drop for PooledConnection<'_, T> {
+    fn drop(&mut self) {
+        let Some(connection) = self.connection.take() else {
+            return;
+        };
+
+        if self.reusable && connection.shared.state() == State::Authenticated {
+            self.pool.idle.lock().unwrap().push_back(Idle {
+                connection,
+                since: Instant::now(),
+            });
+        } else {
+            self.pool.size.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> fmt::Debug for PooledConnection<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PooledConnection").finish_non_exhaustive()
+    }
+}
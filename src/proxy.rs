@@ -0,0 +1,310 @@
+//! Tunnelling connections through a SOCKS5 or HTTP CONNECT proxy, for game
+//! servers that are only reachable through a bastion host.
+
+use std::{io, net::IpAddr};
+
+use base64::Engine;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+};
+
+use crate::{Connection, Connector, Result};
+
+/// The largest HTTP response head accepted from a proxy.
+const MAX_RESPONSE_HEAD: usize = 8 * 1024;
+
+/// Credentials for a proxy that requires them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProxyAuth {
+    pub username: String,
+    pub password: String,
+}
+
+/// A proxy to tunnel connections through.
+///
+/// The target's host name is resolved by the proxy, so hosts only the
+/// bastion can resolve work too.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProxyConfig {
+    /// A SOCKS5 proxy at `addr`, with username/password authentication if
+    /// `auth` is set.
+    Socks5 {
+        addr: String,
+        auth: Option<ProxyAuth>,
+    },
+    /// An HTTP proxy at `addr` that supports the `CONNECT` method, with
+    /// basic authentication if `auth` is set.
+    HttpConnect {
+        addr: String,
+        auth: Option<ProxyAuth>,
+    },
+}
+
+impl ProxyConfig {
+    /// A SOCKS5 proxy without authentication.
+    pub fn socks5(addr: impl Into<String>) -> Self {
+        ProxyConfig::Socks5 {
+            addr: addr.into(),
+            auth: None,
+        }
+    }
+
+    /// An HTTP CONNECT proxy without authentication.
+    pub fn http_connect(addr: impl Into<String>) -> Self {
+        ProxyConfig::HttpConnect {
+            addr: addr.into(),
+            auth: None,
+        }
+    }
+
+    /// Authenticates with the proxy as `username`.
+    pub fn with_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        let credentials = Some(ProxyAuth {
+            username: username.into(),
+            password: password.into(),
+        });
+
+        match &mut self {
+            ProxyConfig::Socks5 { auth, .. } | ProxyConfig::HttpConnect { auth, .. } => {
+                *auth = credentials
+            }
+        }
+
+        self
+    }
+
+    /// Connects to the proxy and opens a tunnel to `target`, given as
+    /// `host:port`.
+    ///
+    /// Fails with a [`PermissionDenied`](io::ErrorKind::PermissionDenied) io
+    /// error if the proxy rejects the credentials, and with
+    /// [`ConnectionRefused`](io::ErrorKind::ConnectionRefused) if it refuses
+    /// to reach the target.
+    pub async fn tunnel(&self, target: &str) -> io::Result<TcpStream> {
+        let (host, port) = split_host_port(target)?;
+
+        match self {
+            ProxyConfig::Socks5 { addr, auth } => {
+                let mut stream = TcpStream::connect(addr.as_str()).await?;
+                socks5_handshake(&mut stream, host, port, auth.as_ref()).await?;
+                Ok(stream)
+            }
+            ProxyConfig::HttpConnect { addr, auth } => {
+                let mut stream = TcpStream::connect(addr.as_str()).await?;
+                http_connect(&mut stream, target, auth.as_ref()).await?;
+                Ok(stream)
+            }
+        }
+    }
+}
+
+impl Connection<TcpStream> {
+    /// Connects to `target` through `proxy` and returns an authenticated
+    /// connection that is ready for commands, like
+    /// [`connect_and_ready`](Connection::connect_and_ready).
+    ///
+    /// The connection's [`Connector`] opens a fresh tunnel, so
+    /// [`reconnect`](Connection::reconnect) goes through the proxy too.
+    /// [`peer_addr`](Connection::peer_addr) is the proxy's address, as the
+    /// proxy alone knows where the target is.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::proxy::ProxyConfig;
+    /// use specul::Connection;
+    ///
+    /// let proxy = ProxyConfig::socks5("bastion:1080").with_auth("admin", "hunter2");
+    /// let mut connection = Connection::connect_via_proxy(&proxy, "10.0.0.5:27015", "password").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_via_proxy(
+        proxy: &ProxyConfig,
+        target: &str,
+        password: &str,
+    ) -> Result<Self> {
+        let stream = proxy.tunnel(target).await?;
+
+        let proxy = proxy.clone();
+        let target = target.to_string();
+        let connector = Connector::new(move || {
+            let proxy = proxy.clone();
+            let target = target.clone();
+            async move { proxy.tunnel(&target).await }
+        });
+
+        let peer_addr = stream.peer_addr().ok();
+        let mut connection = Connection::dialled(stream, connector, peer_addr, |builder| builder)?;
+        connection.ready(password).await?;
+
+        Ok(connection)
+    }
+}
+
+fn split_host_port(target: &str) -> io::Result<(&str, u16)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "target must be host:port");
+
+    let (host, port) = target.rsplit_once(':').ok_or_else(invalid)?;
+    let host = host
+        .strip_prefix('[')
+        .and_then(|host| host.strip_suffix(']'))
+        .unwrap_or(host);
+    let port = port.parse().map_err(|_| invalid())?;
+
+    Ok((host, port))
+}
+
+async fn socks5_handshake(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&ProxyAuth>,
+) -> io::Result<()> {
+    // Greeting: version 5 and the offered methods, "none" or
+    // "username/password".
+    let methods: &[u8] = if auth.is_some() { &[0x02] } else { &[0x00] };
+    let mut greeting = vec![0x05, methods.len() as u8];
+    greeting.extend_from_slice(methods);
+    stream.write_all(&greeting).await?;
+
+    let mut choice = [0; 2];
+    stream.read_exact(&mut choice).await?;
+
+    match (choice, auth) {
+        ([0x05, 0x00], _) => {}
+        ([0x05, 0x02], Some(auth)) => {
+            let mut request = vec![0x01];
+            push_short(&mut request, auth.username.as_bytes())?;
+            push_short(&mut request, auth.password.as_bytes())?;
+            stream.write_all(&request).await?;
+
+            let mut status = [0; 2];
+            stream.read_exact(&mut status).await?;
+
+            if status[1] != 0x00 {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "SOCKS5 proxy rejected the credentials",
+                ));
+            }
+        }
+        ([0x05, 0xFF], _) => {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "SOCKS5 proxy accepts none of the offered authentication methods",
+            ))
+        }
+        _ => return Err(invalid_data("unexpected SOCKS5 method selection")),
+    }
+
+    let mut request = vec![0x05, 0x01, 0x00];
+
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(0x01);
+            request.extend_from_slice(&ip.octets());
+        }
+        Ok(IpAddr::V6(ip)) => {
+            request.push(0x04);
+            request.extend_from_slice(&ip.octets());
+        }
+        Err(_) => {
+            request.push(0x03);
+            push_short(&mut request, host.as_bytes())?;
+        }
+    }
+
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut reply = [0; 4];
+    stream.read_exact(&mut reply).await?;
+
+    if reply[0] != 0x05 {
+        return Err(invalid_data("unexpected SOCKS5 reply version"));
+    }
+
+    if reply[1] != 0x00 {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("SOCKS5 proxy could not connect, reply code {}", reply[1]),
+        ));
+    }
+
+    // The bound address, which is of no use here, then the port.
+    let length = match reply[3] {
+        0x01 => 4,
+        0x04 => 16,
+        0x03 => stream.read_u8().await? as usize,
+        _ => return Err(invalid_data("unexpected SOCKS5 address type")),
+    };
+
+    let mut bound = vec![0; length + 2];
+    stream.read_exact(&mut bound).await?;
+
+    Ok(())
+}
+
+/// Appends `bytes` with a one-byte length, as SOCKS5 encodes strings.
+fn push_short(buffer: &mut Vec<u8>, bytes: &[u8]) -> io::Result<()> {
+    let length = u8::try_from(bytes.len()).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "SOCKS5 strings are at most 255 bytes",
+        )
+    })?;
+
+    buffer.push(length);
+    buffer.extend_from_slice(bytes);
+    Ok(())
+}
+
+async fn http_connect(
+    stream: &mut TcpStream,
+    target: &str,
+    auth: Option<&ProxyAuth>,
+) -> io::Result<()> {
+    let mut request = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n", target);
+
+    if let Some(auth) = auth {
+        let credentials = base64::engine::general_purpose::STANDARD
+            .encode(format!("{}:{}", auth.username, auth.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", credentials));
+    }
+
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read one byte at a time, so nothing past the head is consumed.
+    let mut head = Vec::new();
+
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_RESPONSE_HEAD {
+            return Err(invalid_data("HTTP proxy response head too long"));
+        }
+
+        head.push(stream.read_u8().await?);
+    }
+
+    let status = std::str::from_utf8(&head)
+        .ok()
+        .and_then(|head| head.split_whitespace().nth(1))
+        .ok_or_else(|| invalid_data("malformed HTTP proxy response"))?;
+
+    match status {
+        status if status.starts_with('2') => Ok(()),
+        "407" => Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "HTTP proxy requires authentication",
+        )),
+        status => Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!("HTTP proxy could not connect, status {}", status),
+        )),
+    }
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
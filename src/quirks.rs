@@ -0,0 +1,153 @@
+use crate::{Error, Result};
+
+/// Server-specific deviations from the Source protocol, selected with the
+/// builder's `quirks`.
+///
+/// With the `serde` feature, written as `source`, `minecraft` or
+/// `factorio`, as in [`ConnectionConfig`](crate::ConnectionConfig) URLs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Quirks {
+    /// The protocol as Source servers implement it.
+    #[default]
+    Source,
+    /// Minecraft's implementation, which splits responses longer than 4096
+    /// bytes over several packets without marking the end, and drops
+    /// commands longer than 1446 bytes.
+    ///
+    /// Every command is followed by a sentinel, which Minecraft answers with
+    /// `Unknown request 0` once the response is complete, and the fragments
+    /// before it are joined into one string.
+    Minecraft,
+    /// Factorio's implementation, which answers the empty
+    /// `SERVERDATA_RESPONSE_VALUE` sentinel with an error instead of
+    /// mirroring it, but sends every response in one packet, however long.
+    ///
+    /// Responses are read as single packets even if `multiple_responses` is
+    /// set, and commands are limited to what fits in a 4096-byte packet.
+    Factorio,
+}
+
+impl Quirks {
+    /// The longest command the server accepts, if it is stricter than
+    /// `max_payload_size`.
+    pub(crate) fn max_command_len(self) -> Option<usize> {
+        match self {
+            Quirks::Source => None,
+            Quirks::Minecraft => Some(1446),
+            Quirks::Factorio => Some(4096 - 10),
+        }
+    }
+
+    /// Whether responses are collected up to a sentinel, given the
+    /// connection's `multiple_responses`.
+    pub(crate) fn uses_sentinel(self, multiple_responses: bool) -> bool {
+        match self {
+            Quirks::Source => multiple_responses,
+            Quirks::Minecraft => true,
+            Quirks::Factorio => false,
+        }
+    }
+
+    /// Whether a response's packets are fragments of one string.
+    pub(crate) fn joins_fragments(self) -> bool {
+        matches!(self, Quirks::Minecraft)
+    }
+
+    /// Makes `argument`, such as a player name, safe to put in a command as
+    /// one argument, so it cannot end the command or start another.
+    ///
+    /// Source has no escapes, so arguments holding `"` are rejected, and
+    /// others are quoted if they hold spaces, `;` or characters the console
+    /// splits on. Minecraft quotes arguments that are not plain words,
+    /// escaping `"` and `\`. Factorio passes the rest of the line to its
+    /// commands as it is, so arguments are only checked. Control characters,
+    /// such as newlines, fail with [`Error::InvalidCommand`] for every game.
+    ///
+    /// ```
+    /// use specul::Quirks;
+    ///
+    /// assert_eq!(Quirks::Source.escape_argument("Gordon")?, "Gordon");
+    /// assert_eq!(Quirks::Source.escape_argument("noob; quit")?, "\"noob; quit\"");
+    /// assert!(Quirks::Source.escape_argument("\"; quit").is_err());
+    ///
+    /// assert_eq!(Quirks::Minecraft.escape_argument("say \"hi\"")?, r#""say \"hi\"""#);
+    /// assert!(Quirks::Minecraft.escape_argument("one\ntwo").is_err());
+    /// # Ok::<(), specul::Error>(())
+    /// ```
+    pub fn escape_argument(self, argument: &str) -> Result<String> {
+        self.escape(argument, "argument")
+    }
+
+    /// [`escape_argument`](Quirks::escape_argument), naming `argument` as
+    /// `what` in errors.
+    pub(crate) fn escape(self, argument: &str, what: &str) -> Result<String> {
+        if self == Quirks::Factorio {
+            check_control_characters(argument, what)?;
+            return Ok(argument.to_string());
+        }
+
+        let escaped = self.escape_quoted(argument, what)?;
+        let quote = match self {
+            Quirks::Source => {
+                argument.is_empty()
+                    || argument.contains("//")
+                    || argument
+                        .chars()
+                        .any(|c| c.is_whitespace() || ";{}()':".contains(c))
+            }
+            Quirks::Minecraft => {
+                argument.is_empty()
+                    || !argument
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c))
+            }
+            Quirks::Factorio => false,
+        };
+
+        Ok(match quote {
+            true => format!("\"{}\"", escaped),
+            false => escaped,
+        })
+    }
+
+    /// Makes `argument` safe to put between quotes the command already has,
+    /// without adding any. Minecraft escapes `"` and `\`, while Source and
+    /// Factorio, which have no escapes, reject `"`.
+    pub(crate) fn escape_quoted(self, argument: &str, what: &str) -> Result<String> {
+        check_control_characters(argument, what)?;
+
+        match self {
+            Quirks::Minecraft => {
+                let mut escaped = String::with_capacity(argument.len());
+                for c in argument.chars() {
+                    if matches!(c, '"' | '\\') {
+                        escaped.push('\\');
+                    }
+                    escaped.push(c);
+                }
+                Ok(escaped)
+            }
+            Quirks::Source | Quirks::Factorio if argument.contains('"') => {
+                Err(Error::InvalidCommand(format!(
+                    "{} contains '\"', which {:?} cannot escape",
+                    what, self
+                )))
+            }
+            Quirks::Source | Quirks::Factorio => Ok(argument.to_string()),
+        }
+    }
+}
+
+/// Fails with [`Error::InvalidCommand`] if `text` holds a control character
+/// other than a tab, naming it as `what`.
+pub(crate) fn check_control_characters(text: &str, what: &str) -> Result<()> {
+    match text.chars().find(|&c| c.is_control() && c != '\t') {
+        Some(c) => Err(Error::InvalidCommand(format!(
+            "{} contains the control character {:?}",
+            what, c
+        ))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::ConnectionBuilder;
+
+/// A token bucket holding up to `burst` commands, refilled at `rate` a
+/// second.
+#[derive(Debug, Clone)]
+pub(crate) struct TokenBucket {
+    rate: f64,
+    burst: u32,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, burst: u32) -> Self {
+        TokenBucket {
+            rate,
+            burst,
+            tokens: f64::from(burst),
+            refilled: Instant::now(),
+        }
+    }
+
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if !(self.rate.is_finite() && self.rate > 0.0) {
+            return Err("rate_limit needs a positive number of commands per second".to_string());
+        }
+
+        if self.burst == 0 {
+            return Err("rate_limit needs a burst of at least 1".to_string());
+        }
+
+        Ok(())
+    }
+
+    /// Waits until a command may be sent, and takes its token.
+    pub(crate) async fn acquire(&mut self) {
+        self.refill();
+
+        if self.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / self.rate);
+            tokio::time::sleep(wait).await;
+            self.refill();
+        }
+
+        // Rounding can leave a sliver short of a whole token.
+        self.tokens = (self.tokens - 1.0).max(0.0);
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let earned = now.duration_since(self.refilled).as_secs_f64() * self.rate;
+
+        self.tokens = (self.tokens + earned).min(f64::from(self.burst));
+        self.refilled = now;
+    }
+}
+
+/// How commands are spaced out, shared by a [`Connection`](crate::Connection)
+/// and the halves and clients made from it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Pacing {
+    min_interval: Option<Duration>,
+    rate_limit: Option<TokenBucket>,
+    last: Option<Instant>,
+}
+
+impl Pacing {
+    pub(crate) fn check(&self) -> Result<(), String> {
+        match &self.rate_limit {
+            Some(bucket) => bucket.check(),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether commands may have to wait, so they cannot all be written at
+    /// once.
+    pub(crate) fn is_active(&self) -> bool {
+        self.min_interval.is_some() || self.rate_limit.is_some()
+    }
+
+    /// Waits until `min_command_interval` has passed since the last command,
+    /// and for a token from any `rate_limit`.
+    pub(crate) async fn wait(&mut self) {
+        if let (Some(interval), Some(last)) = (self.min_interval, self.last) {
+            tokio::time::sleep_until(last + interval).await;
+        }
+
+        if let Some(bucket) = &mut self.rate_limit {
+            bucket.acquire().await;
+        }
+
+        self.last = Some(Instant::now());
+    }
+}
+
+impl<T> ConnectionBuilder<T> {
+    /// The shortest time between the start of two commands. Commands issued
+    /// sooner wait until the interval has elapsed, for servers whose
+    /// anti-flood settings penalize faster commands. Like
+    /// [`rate_limit`](Self::rate_limit), this also paces clients and the
+    /// split sender.
+    pub fn min_command_interval(mut self, interval: Duration) -> Self {
+        self.pacing.get_or_insert_with(Pacing::default).min_interval = Some(interval);
+        self
+    }
+
+    /// Paces commands to `commands_per_second` on average, letting up to
+    /// `burst` through at once after a quiet spell, for servers that kick
+    /// clients sending too fast.
+    ///
+    /// Commands beyond the limit wait their turn, whether sent one at a time,
+    /// in a batch, from a [`client`](crate::Connection::client) or through
+    /// the [`split`](crate::Connection::split) sender. Fails to build if
+    /// either number is not positive.
+    pub fn rate_limit(mut self, commands_per_second: f64, burst: u32) -> Self {
+        self.pacing.get_or_insert_with(Pacing::default).rate_limit =
+            Some(TokenBucket::new(commands_per_second, burst));
+        self
+    }
+}
@@ -0,0 +1,185 @@
+use std::{fmt, future::Future, io, pin::Pin, sync::Arc, time::Duration};
+
+type ConnectFuture<T> = Pin<Box<dyn Future<Output = io::Result<T>> + Send>>;
+
+/// A factory that dials a fresh io for a [`Connection`](crate::Connection),
+/// used whenever the connection has to be re-established.
+pub struct Connector<T> {
+    connect: Arc<dyn Fn() -> ConnectFuture<T> + Send + Sync>,
+}
+
+impl<T> Connector<T> {
+    /// Creates a connector from an async function returning a new io.
+    ///
+    /// ```no_run
+    /// use specul::Connector;
+    /// use tokio::net::TcpStream;
+    ///
+    /// let connector = Connector::new(|| TcpStream::connect("127.0.0.1:27015"));
+    /// ```
+    pub fn new<F, Fut>(connect: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = io::Result<T>> + Send + 'static,
+    {
+        Connector {
+            connect: Arc::new(move || Box::pin(connect())),
+        }
+    }
+
+    pub(crate) async fn connect(&self) -> io::Result<T> {
+        (self.connect)().await
+    }
+}
+
+impl<T> Clone for Connector<T> {
+    fn clone(&self) -> Self {
+        Connector {
+            connect: self.connect.clone(),
+        }
+    }
+}
+
+impl<T> fmt::Debug for Connector<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connector").finish_non_exhaustive()
+    }
+}
+
+/// A password kept for re-authenticating, hidden from `Debug` output, and
+/// zeroed when dropped with the `secrecy` feature.
+#[derive(Clone)]
+pub(crate) struct Password(Secret);
+
+#[cfg(feature = "secrecy")]
+type Secret = secrecy::SecretString;
+#[cfg(not(feature = "secrecy"))]
+type Secret = String;
+
+impl Password {
+    pub fn new(password: &str) -> Self {
+        Password(password.into())
+    }
+
+    #[cfg(feature = "secrecy")]
+    pub fn expose(&self) -> &str {
+        secrecy::ExposeSecret::expose_secret(&self.0)
+    }
+
+    #[cfg(not(feature = "secrecy"))]
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Password(..)")
+    }
+}
+
+/// How often, and how patiently, a connection redials after the server goes
+/// away.
+///
+/// The first attempt is immediate. After each failed attempt the connection
+/// waits, starting at `initial` and multiplying the wait by `multiplier` up to
+/// `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct Backoff {
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::duration"))]
+    pub initial: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::duration"))]
+    pub max: Duration,
+    pub multiplier: u32,
+    /// Attempts before giving up, including the first.
+    pub max_attempts: usize,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff {
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(10),
+            multiplier: 2,
+            max_attempts: 5,
+        }
+    }
+}
+
+impl Backoff {
+    /// The wait after the failed attempt number `attempt`, counting from 1.
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let factor = self
+            .multiplier
+            .saturating_pow(attempt.saturating_sub(1) as u32);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// How often, and how patiently, a command is retried after an error that
+/// [may pass](crate::Error::is_retriable), such as a timeout.
+///
+/// The first attempt is immediate. After each failed attempt the connection
+/// waits, starting at `initial` and multiplying the wait by `multiplier` up to
+/// `max`, then shortens the wait by a random fraction of up to `jitter`, so
+/// many clients retrying at once spread out. Errors that are not retriable,
+/// such as [`Error::Authentication`](crate::Error::Authentication), are
+/// returned at once.
+///
+/// Retrying a command runs it again, so commands that must not run twice
+/// should not be sent through a connection with a retry policy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct RetryPolicy {
+    /// Attempts before giving up, including the first.
+    pub max_attempts: usize,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::duration"))]
+    pub initial: Duration,
+    #[cfg_attr(feature = "serde", serde(with = "crate::serialize::duration"))]
+    pub max: Duration,
+    pub multiplier: u32,
+    /// The largest fraction, from 0 to 1, taken off each wait.
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial: Duration::from_millis(100),
+            max: Duration::from_secs(2),
+            multiplier: 2,
+            jitter: 0.2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait after the failed attempt number `attempt`, counting from 1.
+    pub(crate) fn delay(&self, attempt: usize) -> Duration {
+        let backoff = Backoff {
+            initial: self.initial,
+            max: self.max,
+            multiplier: self.multiplier,
+            max_attempts: self.max_attempts,
+        };
+
+        let delay = backoff.delay(attempt);
+        let jitter = self.jitter.clamp(0.0, 1.0) * random_fraction();
+        delay.mul_f64(1.0 - jitter)
+    }
+}
+
+/// A random number from 0 to 1, good enough to spread out retries.
+fn random_fraction() -> f64 {
+    use std::{
+        collections::hash_map::RandomState,
+        hash::{BuildHasher, Hasher},
+    };
+
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
@@ -0,0 +1,396 @@
+//! Recording the bytes of a session and replaying them, for turning a
+//! session with a real server into a test that needs no server.
+//!
+//! [`RecordingTransport`] wraps a connection's io and records what passes
+//! through it. The [`Recording`] is saved as text, with a line per 32 bytes:
+//! `>` and the bytes in hex for bytes sent to the server, `<` for bytes
+//! received, and `#` for comments. [`ReplayTransport`] then plays the
+//! server's side back, checking that the client sends what it sent before.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! use specul::recording::{Recording, RecordingTransport, ReplayTransport};
+//! use specul::ConnectionBuilder;
+//! use tokio::net::TcpStream;
+//!
+//! let transport = RecordingTransport::new(TcpStream::connect("10.0.0.5:27015").await?);
+//! let recorder = transport.recorder();
+//!
+//! let mut connection = ConnectionBuilder::default().io(transport).build()?;
+//! connection.authenticate("password").await?;
+//! connection.execute_command("status").await?;
+//! recorder.recording().save("status.rcon")?;
+//!
+//! // Later, in a test:
+//! let replay = ReplayTransport::new(Recording::load("status.rcon")?);
+//! let mut connection = ConnectionBuilder::default().io(replay).build()?;
+//! connection.authenticate("password").await?;
+//! let response = connection.execute_command("status").await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Recordings hold the password as it was sent, so replace it before
+//! sharing one.
+
+use std::{
+    fmt, fs, io,
+    path::Path,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    task::{ready, Context, Poll, Waker},
+};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// How many bytes go on each line of a saved recording.
+const LINE_BYTES: usize = 32;
+
+/// Bytes that went one way.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Chunk {
+    /// Sent to the server.
+    Sent(Vec<u8>),
+    /// Received from the server.
+    Received(Vec<u8>),
+}
+
+impl Chunk {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Chunk::Sent(bytes) | Chunk::Received(bytes) => bytes,
+        }
+    }
+}
+
+/// The bytes of a session, in the order they were sent and received.
+///
+/// Bytes going the same way one after another are kept as one chunk, since
+/// how they were split into reads and writes is not part of the session.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Recording {
+    chunks: Vec<Chunk>,
+}
+
+impl Recording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn chunks(&self) -> &[Chunk] {
+        &self.chunks
+    }
+
+    /// Adds a chunk, joining it to the last one if it went the same way.
+    pub fn push(&mut self, chunk: Chunk) {
+        match (self.chunks.last_mut(), chunk) {
+            (_, chunk) if chunk.bytes().is_empty() => {}
+            (Some(Chunk::Sent(last)), Chunk::Sent(bytes))
+            | (Some(Chunk::Received(last)), Chunk::Received(bytes)) => last.extend(bytes),
+            (_, chunk) => self.chunks.push(chunk),
+        }
+    }
+
+    /// Parses a recording in the saved format, failing with an
+    /// [`InvalidData`](io::ErrorKind::InvalidData) error on lines that are
+    /// not.
+    pub fn parse(text: &str) -> io::Result<Self> {
+        let mut recording = Recording::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let invalid = |message: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} of the recording {}", number + 1, message),
+                )
+            };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut chars = line.chars();
+            let direction = chars.next();
+            let hex: String = chars.as_str().split_whitespace().collect();
+
+            // Checked first, since a `+` would parse and a multi-byte
+            // character would split.
+            if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                return Err(invalid("is not hex"));
+            }
+
+            if !hex.len().is_multiple_of(2) {
+                return Err(invalid("has an odd number of hex digits"));
+            }
+
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<u8>, _>>()
+                .map_err(|_| invalid("is not hex"))?;
+
+            recording.push(match direction {
+                Some('>') => Chunk::Sent(bytes),
+                Some('<') => Chunk::Received(bytes),
+                _ => return Err(invalid("does not start with > or <")),
+            });
+        }
+
+        Ok(recording)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+/// Formats the recording as it is saved.
+impl fmt::Display for Recording {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# specul recording: > sent, < received")?;
+
+        for chunk in &self.chunks {
+            let direction = match chunk {
+                Chunk::Sent(_) => '>',
+                Chunk::Received(_) => '<',
+            };
+
+            for line in chunk.bytes().chunks(LINE_BYTES) {
+                write!(f, "{} ", direction)?;
+                for byte in line {
+                    write!(f, "{:02x}", byte)?;
+                }
+                writeln!(f)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Recording {
+    type Err = io::Error;
+
+    fn from_str(text: &str) -> io::Result<Self> {
+        Self::parse(text)
+    }
+}
+
+/// Reads the recording of a [`RecordingTransport`], after it was moved into
+/// a connection.
+#[derive(Debug, Clone)]
+pub struct Recorder {
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl Recorder {
+    /// Returns what was recorded so far.
+    pub fn recording(&self) -> Recording {
+        self.recording.lock().unwrap().clone()
+    }
+}
+
+/// Io that records every byte read from and written to the io it wraps.
+#[derive(Debug)]
+pub struct RecordingTransport<T> {
+    io: T,
+    recording: Arc<Mutex<Recording>>,
+}
+
+impl<T> RecordingTransport<T> {
+    pub fn new(io: T) -> Self {
+        RecordingTransport {
+            io,
+            recording: Arc::default(),
+        }
+    }
+
+    /// Returns a handle to the recording, which stays usable once the
+    /// transport is moved into a connection.
+    pub fn recorder(&self) -> Recorder {
+        Recorder {
+            recording: self.recording.clone(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+
+    fn record(&self, chunk: Chunk) {
+        self.recording.lock().unwrap().push(chunk);
+    }
+}
+
+impl<T: AsyncRead + Unpin> AsyncRead for RecordingTransport<T> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+
+        ready!(Pin::new(&mut this.io).poll_read(cx, buf))?;
+
+        this.record(Chunk::Received(buf.filled()[before..].to_vec()));
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> AsyncWrite for RecordingTransport<T> {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let written = ready!(Pin::new(&mut this.io).poll_write(cx, buf))?;
+
+        this.record(Chunk::Sent(buf[..written].to_vec()));
+        Poll::Ready(Ok(written))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.get_mut().io).poll_shutdown(cx)
+    }
+}
+
+/// Io that plays the server's side of a [`Recording`].
+///
+/// Reads return the received bytes once the client has written everything
+/// it sent before them, and end when the recording does. Writes that differ
+/// from what was sent fail with an
+/// [`InvalidData`](io::ErrorKind::InvalidData) error, as do writes past the
+/// end with a [`BrokenPipe`](io::ErrorKind::BrokenPipe) one.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    chunks: Vec<Chunk>,
+    index: usize,
+    offset: usize,
+    reader: Option<Waker>,
+}
+
+impl ReplayTransport {
+    pub fn new(recording: Recording) -> Self {
+        ReplayTransport {
+            chunks: recording.chunks,
+            index: 0,
+            offset: 0,
+            reader: None,
+        }
+    }
+
+    /// Whether the whole recording was played.
+    pub fn is_finished(&self) -> bool {
+        self.index == self.chunks.len()
+    }
+
+    fn advance(&mut self, count: usize) {
+        self.offset += count;
+
+        if self.offset == self.chunks[self.index].bytes().len() {
+            self.index += 1;
+            self.offset = 0;
+        }
+    }
+
+    fn diverged(&self, message: String) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("replay diverged at chunk {}: {}", self.index + 1, message),
+        )
+    }
+}
+
+impl AsyncRead for ReplayTransport {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        match this.chunks.get(this.index) {
+            None => Poll::Ready(Ok(())),
+            Some(Chunk::Received(bytes)) => {
+                let bytes = &bytes[this.offset..];
+                let count = bytes.len().min(buf.remaining());
+
+                buf.put_slice(&bytes[..count]);
+                this.advance(count);
+                Poll::Ready(Ok(()))
+            }
+            Some(Chunk::Sent(_)) => {
+                this.reader = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl AsyncWrite for ReplayTransport {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        let expected = match this.chunks.get(this.index) {
+            None => {
+                return Poll::Ready(Err(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the recording has ended",
+                )))
+            }
+            Some(Chunk::Received(_)) => {
+                return Poll::Ready(Err(this.diverged(format!(
+                    "wrote {:02x?} where the server's response was recorded",
+                    buf
+                ))))
+            }
+            Some(Chunk::Sent(bytes)) => &bytes[this.offset..],
+        };
+
+        let count = buf.len().min(expected.len());
+
+        if buf[..count] != expected[..count] {
+            return Poll::Ready(Err(this.diverged(format!(
+                "expected {:02x?}, wrote {:02x?}",
+                &expected[..count],
+                &buf[..count]
+            ))));
+        }
+
+        this.advance(count);
+
+        if let Some(reader) = this.reader.take() {
+            reader.wake();
+        }
+
+        Poll::Ready(Ok(count))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::HashMap, fmt, ops::Deref, time::Duration};
+
+use crate::parse;
+
+/// A command's response, as returned by
+/// [`Connection::execute`](crate::Connection::execute).
+///
+/// Dereferences to the joined body, so it can be used as a `&str`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Response {
+    /// Every payload joined into one string.
+    pub body: String,
+    /// The payload of each packet, after any `response_transform`, or one
+    /// payload of the joined fragments for servers whose quirks join them.
+    pub payloads: Vec<String>,
+    /// The id of the command packet.
+    pub request_id: i32,
+    /// The id of each packet received, in order. They differ from
+    /// `request_id` only for error packets.
+    pub response_ids: Vec<i32>,
+    /// From sending the command to receiving the last packet of its
+    /// response.
+    pub latency: Duration,
+}
+
+impl Response {
+    /// Returns how many packets the response arrived in.
+    pub fn packets(&self) -> usize {
+        self.response_ids.len()
+    }
+
+    /// Returns the joined body as UTF-8 bytes.
+    ///
+    /// The bytes as the server sent them are only kept by
+    /// [`Packet::payload`](crate::Packet::payload) and
+    /// [`Connection::execute_command_raw`](crate::Connection::execute_command_raw).
+    pub fn as_bytes(&self) -> &[u8] {
+        self.body.as_bytes()
+    }
+
+    /// Returns the joined body.
+    pub fn to_str(&self) -> &str {
+        &self.body
+    }
+
+    /// Parses the body's `key: value` and `"key" = "value"` lines, as
+    /// [`parse_pairs`](parse::parse_pairs) does.
+    ///
+    /// ```no_run
+    /// # async fn run(connection: &mut specul::Connection<tokio::net::TcpStream>) -> specul::Result<()> {
+    /// use specul::parse::DEFAULT_SEPARATORS;
+    ///
+    /// let status = connection.execute("status").await?;
+    /// let fields = status.parse_pairs(DEFAULT_SEPARATORS);
+    /// println!("map: {}", fields["map"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn parse_pairs(&self, separators: &[char]) -> HashMap<String, String> {
+        parse::parse_pairs(&self.body, separators)
+    }
+}
+
+impl Deref for Response {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.body
+    }
+}
+
+impl AsRef<str> for Response {
+    fn as_ref(&self) -> &str {
+        &self.body
+    }
+}
+
+impl fmt::Display for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.body)
+    }
+}
+
+impl From<Response> for String {
+    fn from(response: Response) -> Self {
+        response.body
+    }
+}
+
+impl PartialEq<str> for Response {
+    fn eq(&self, other: &str) -> bool {
+        self.body == other
+    }
+}
+
+impl PartialEq<&str> for Response {
+    fn eq(&self, other: &&str) -> bool {
+        self.body == *other
+    }
+}
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite};
+
+use crate::{Connection, Response, Result};
+
+/// What [`run_script`](Connection::run_script) does after a command fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum OnError {
+    /// Stop at the failed command.
+    #[default]
+    Stop,
+    /// Go on with the next command, unless the failure left the connection
+    /// unusable, such as a timeout or the server going away.
+    Continue,
+}
+
+/// How [`run_script_with`](Connection::run_script_with) runs a script.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ScriptOptions {
+    /// How long to wait between commands, for servers that drop commands
+    /// sent too quickly.
+    pub delay: Duration,
+    pub on_error: OnError,
+}
+
+/// A command of a script and how it went.
+#[derive(Debug)]
+pub struct ScriptLine {
+    /// The line the command is on, counting from 1.
+    pub line: usize,
+    pub command: String,
+    pub result: Result<Response>,
+}
+
+impl<T> Connection<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    /// Executes the commands of a script, one per line, stopping at the
+    /// first that fails. Blank lines and comments, lines starting with `#`
+    /// or `//`, are skipped.
+    ///
+    /// Returns the result of each command executed, so the last one is the
+    /// failure if there was one. Fails only if reading the script does.
+    ///
+    /// ```no_run
+    /// # async fn run(connection: &mut specul::Connection<tokio::net::TcpStream>) -> specul::Result<()> {
+    /// let script = "// Reset the match\n\
+    ///               mp_restartgame 1\n\
+    ///               say Good luck\n";
+    ///
+    /// for line in connection.run_script(script.as_bytes()).await? {
+    ///     if let Err(error) = line.result {
+    ///         eprintln!("line {}: {}: {}", line.line, line.command, error);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn run_script<R>(&mut self, script: R) -> Result<Vec<ScriptLine>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        self.run_script_with(script, ScriptOptions::default()).await
+    }
+
+    /// Executes the commands of a script, as
+    /// [`run_script`](Connection::run_script) does, with a delay between
+    /// commands and a choice of whether to go on after failures.
+    pub async fn run_script_with<R>(
+        &mut self,
+        script: R,
+        options: ScriptOptions,
+    ) -> Result<Vec<ScriptLine>>
+    where
+        R: AsyncBufRead + Unpin,
+    {
+        let mut lines = script.lines();
+        let mut results = Vec::new();
+        let mut number = 0;
+
+        while let Some(line) = lines.next_line().await? {
+            number += 1;
+            let command = line.trim();
+
+            if command.is_empty() || command.starts_with('#') || command.starts_with("//") {
+                continue;
+            }
+
+            if !results.is_empty() && !options.delay.is_zero() {
+                tokio::time::sleep(options.delay).await;
+            }
+
+            let result = self.execute(command).await;
+            let stop = match &result {
+                Ok(_) => false,
+                Err(error) => {
+                    options.on_error == OnError::Stop || !error.leaves_connection_usable()
+                }
+            };
+
+            results.push(ScriptLine {
+                line: number,
+                command: command.to_string(),
+                result,
+            });
+
+            if stop {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,205 @@
+//! The `serde` support that derives cannot give: durations written as
+//! `5s` or `500ms` rather than seconds and nanoseconds, and packets whose
+//! payload is text when it can be.
+
+use std::{fmt, time::Duration};
+
+use bytes::Bytes;
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{url::duration_from_str, Packet, PacketType};
+
+/// Formats a duration the way [`duration_from_str`] parses it, in the
+/// largest unit that keeps it exact.
+fn duration_to_string(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+
+    match duration.subsec_nanos() {
+        0 if seconds > 0 && seconds.is_multiple_of(3600) => format!("{}h", seconds / 3600),
+        0 if seconds > 0 && seconds.is_multiple_of(60) => format!("{}m", seconds / 60),
+        0 => format!("{}s", seconds),
+        nanos if nanos.is_multiple_of(1_000_000) => format!("{}ms", duration.as_millis()),
+        _ => format!("{}s", duration.as_secs_f64()),
+    }
+}
+
+struct DurationVisitor;
+
+impl<'de> Visitor<'de> for DurationVisitor {
+    type Value = Duration;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a duration such as \"5s\" or \"500ms\", or a number of seconds")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+        duration_from_str(value).ok_or_else(|| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+
+    fn visit_u64<E: de::Error>(self, seconds: u64) -> Result<Duration, E> {
+        Ok(Duration::from_secs(seconds))
+    }
+
+    fn visit_i64<E: de::Error>(self, seconds: i64) -> Result<Duration, E> {
+        u64::try_from(seconds)
+            .map(Duration::from_secs)
+            .map_err(|_| E::invalid_value(de::Unexpected::Signed(seconds), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, seconds: f64) -> Result<Duration, E> {
+        Duration::try_from_secs_f64(seconds)
+            .map_err(|_| E::invalid_value(de::Unexpected::Float(seconds), &self))
+    }
+}
+
+/// For `#[serde(with)]` on `Duration` fields.
+pub(crate) mod duration {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&duration_to_string(*duration))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+/// For `#[serde(with)]` on `Option<Duration>` fields, with `default` so
+/// they may be left out.
+pub(crate) mod option_duration {
+    use super::*;
+
+    #[derive(Deserialize)]
+    struct Wrapper(#[serde(with = "duration")] Duration);
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match duration {
+            Some(duration) => serializer.serialize_some(&duration_to_string(*duration)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<Wrapper>::deserialize(deserializer)?.map(|Wrapper(duration)| duration))
+    }
+}
+
+/// A payload, serialized as a string if it is UTF-8 and as bytes if not.
+struct Payload(Bytes);
+
+impl Serialize for Payload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match std::str::from_utf8(&self.0) {
+            Ok(text) => serializer.serialize_str(text),
+            Err(_) => serializer.serialize_bytes(&self.0),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Payload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct PayloadVisitor;
+
+        impl<'de> Visitor<'de> for PayloadVisitor {
+            type Value = Payload;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a string or an array of bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, text: &str) -> Result<Payload, E> {
+                Ok(Payload(Bytes::copy_from_slice(text.as_bytes())))
+            }
+
+            fn visit_string<E: de::Error>(self, text: String) -> Result<Payload, E> {
+                Ok(Payload(text.into()))
+            }
+
+            fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Payload, E> {
+                Ok(Payload(Bytes::copy_from_slice(bytes)))
+            }
+
+            fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Payload, E> {
+                Ok(Payload(bytes.into()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Payload, A::Error> {
+                let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+
+                while let Some(byte) = seq.next_element()? {
+                    bytes.push(byte);
+                }
+
+                Ok(Payload(bytes.into()))
+            }
+        }
+
+        deserializer.deserialize_any(PayloadVisitor)
+    }
+}
+
+#[derive(Serialize)]
+struct PacketRef<'a> {
+    id: i32,
+    length: i32,
+    packet_type: PacketType,
+    payload: &'a Payload,
+}
+
+#[derive(Deserialize)]
+struct PacketFields {
+    id: i32,
+    #[serde(default)]
+    length: Option<i32>,
+    packet_type: PacketType,
+    payload: Payload,
+}
+
+/// Serializes the packet's fields, with the payload as a string if it is
+/// UTF-8 and as bytes if not. The payload of an authentication packet is
+/// the password, so it is masked with `*`s, as in a [`Frame`](crate::Frame).
+impl Serialize for Packet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let payload = match self.holds_password() {
+            true => Bytes::from(vec![b'*'; self.payload.len()]),
+            false => self.payload.clone(),
+        };
+
+        PacketRef {
+            id: self.id,
+            length: self.length,
+            packet_type: self.packet_type,
+            payload: &Payload(payload),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes what [`Serialize`] writes. The length may be left out, to
+/// be worked out from the payload.
+impl<'de> Deserialize<'de> for Packet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = PacketFields::deserialize(deserializer)?;
+        let mut packet = Packet::new(fields.id, fields.packet_type, fields.payload.0);
+
+        if let Some(length) = fields.length {
+            packet.length = length;
+        }
+
+        Ok(packet)
+    }
+}
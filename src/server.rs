@@ -0,0 +1,239 @@
+//! The server side of the protocol, for game-server wrappers that accept RCON
+//! connections themselves.
+//!
+//! ```no_run
+//! # async fn run() -> std::io::Result<()> {
+//! use specul::server::{CommandHandler, Server};
+//!
+//! struct Echo;
+//!
+//! impl CommandHandler for Echo {
+//!     async fn handle(&self, command: &str) -> String {
+//!         format!("you said {}", command)
+//!     }
+//! }
+//!
+//! let server = Server::bind("127.0.0.1:27015", "password".to_string(), Echo).await?;
+//! server.run().await
+//! # }
+//! ```
+
+use std::{future::Future, io, net::SocketAddr, sync::Arc};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, ToSocketAddrs},
+};
+
+use crate::{codec::RconCodec, Framing, Packet, PacketType};
+
+/// The largest payload sent in one response packet, as in the Source engine.
+pub const DEFAULT_MAX_FRAGMENT: usize = 4096;
+
+/// Decides whether a password is accepted.
+///
+/// Implemented for `String`, which accepts exactly that password, and for
+/// closures taking the password.
+pub trait Auth: Send + Sync + 'static {
+    fn check(&self, password: &str) -> bool;
+}
+
+impl Auth for String {
+    fn check(&self, password: &str) -> bool {
+        self == password
+    }
+}
+
+impl<F> Auth for F
+where
+    F: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    fn check(&self, password: &str) -> bool {
+        self(password)
+    }
+}
+
+/// Produces the response to a command from an authenticated client.
+pub trait CommandHandler: Send + Sync + 'static {
+    fn handle(&self, command: &str) -> impl Future<Output = String> + Send;
+}
+
+/// An RCON server accepting TCP connections.
+///
+/// Each connection is served on its own task and must authenticate before
+/// running commands; a failed attempt or a command sent before
+/// authenticating closes the connection, as Source servers do. Responses
+/// longer than `max_fragment` bytes are split over several packets, and an
+/// empty `SERVERDATA_RESPONSE_VALUE` is mirrored back followed by the
+/// `00 01 00 00` marker, so clients can detect the end of a split response.
+#[derive(Debug)]
+pub struct Server<A, H> {
+    listener: TcpListener,
+    session: Session<A, H>,
+}
+
+#[derive(Debug)]
+struct Session<A, H> {
+    auth: A,
+    handler: H,
+    framing: Framing,
+    max_fragment: usize,
+}
+
+impl<A: Auth, H: CommandHandler> Server<A, H> {
+    /// Binds a listener to `addr`.
+    pub async fn bind(addr: impl ToSocketAddrs, auth: A, handler: H) -> io::Result<Self> {
+        Ok(Server {
+            listener: TcpListener::bind(addr).await?,
+            session: Session {
+                auth,
+                handler,
+                framing: Framing::default(),
+                max_fragment: DEFAULT_MAX_FRAGMENT,
+            },
+        })
+    }
+
+    /// Sets the largest payload sent in one response packet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_fragment` is 0.
+    pub fn max_fragment(mut self, max_fragment: usize) -> Self {
+        assert!(max_fragment > 0, "max_fragment must be positive");
+        self.session.max_fragment = max_fragment;
+        self
+    }
+
+    /// Sets how packets are framed on the wire.
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.session.framing = framing;
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Accepts connections until accepting fails, serving each one on its
+    /// own task.
+    pub async fn run(self) -> io::Result<()> {
+        let session = Arc::new(self.session);
+
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let session = session.clone();
+
+            tokio::spawn(async move {
+                let _ = session.serve(stream).await;
+            });
+        }
+    }
+}
+
+impl<A: Auth, H: CommandHandler> Session<A, H> {
+    /// Serves one connection until the client disconnects or is rejected.
+    async fn serve<T: Unpin + AsyncRead + AsyncWrite>(&self, mut io: T) -> io::Result<()> {
+        let mut codec = RconCodec::new(self.framing);
+        let mut read_buffer = BytesMut::new();
+        let mut authenticated = false;
+
+        loop {
+            let request = match codec.decode_packet(&mut read_buffer) {
+                Ok(Some(packet)) => packet,
+                Ok(None) => {
+                    if io.read_buf(&mut read_buffer).await? == 0 {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Err(_) => return Ok(()),
+            };
+
+            // Like a malformed packet, a payload that is not UTF-8 ends the
+            // session.
+            let Ok(payload) = request.to_str() else {
+                return Ok(());
+            };
+
+            let mut responses = Vec::new();
+
+            match request.packet_type {
+                PacketType::Authentication => {
+                    authenticated = self.auth.check(payload);
+                    let id = if authenticated { request.id } else { -1 };
+
+                    responses.push(Packet::new(request.id, PacketType::Response, String::new()));
+                    responses.push(Packet::new(
+                        id,
+                        PacketType::AuthenticationResponse,
+                        String::new(),
+                    ));
+                }
+                // Type 2 is decoded as seen from a client.
+                PacketType::AuthenticationResponse | PacketType::Message if authenticated => {
+                    let output = self.handler.handle(payload).await;
+
+                    for fragment in split(&output, self.max_fragment) {
+                        responses.push(Packet::new(
+                            request.id,
+                            PacketType::Response,
+                            fragment.to_string(),
+                        ));
+                    }
+                }
+                PacketType::Response if authenticated => {
+                    responses.push(Packet::new(request.id, PacketType::Response, String::new()));
+                    responses.push(Packet::new(
+                        request.id,
+                        PacketType::Response,
+                        "\0\u{1}\0\0".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+
+            let mut write_buffer = BytesMut::new();
+
+            for response in &responses {
+                codec.encode_packet(response, &mut write_buffer)?;
+            }
+
+            io.write_all(&write_buffer).await?;
+            io.flush().await?;
+
+            if !authenticated {
+                return io.shutdown().await;
+            }
+        }
+    }
+}
+
+/// Splits `output` into pieces of at most `max` bytes, at character
+/// boundaries. Empty output is one empty piece.
+fn split(output: &str, max: usize) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut rest = output;
+
+    while rest.len() > max {
+        let mut end = max;
+
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        // A character wider than `max` is sent whole.
+        if end == 0 {
+            end = rest.chars().next().map_or(rest.len(), char::len_utf8);
+        }
+
+        let (piece, tail) = rest.split_at(end);
+        pieces.push(piece);
+        rest = tail;
+    }
+
+    pieces.push(rest);
+    pieces
+}
@@ -0,0 +1,70 @@
+//! A [`tower_service::Service`] over [`RconClient`], so tower middleware such as
+//! timeouts, retries and rate limits can wrap RCON commands.
+//!
+//! ```no_run
+//! # async fn run() -> specul::Result<()> {
+//! use specul::{service::RconService, Connection};
+//!
+//! let client = Connection::connect("127.0.0.1:27015", "password").await?.client()?;
+//! let service = RconService::new(client);
+//! // Wrap `service` in tower layers, then call it with commands.
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tower_service::Service;
+
+use crate::{Error, RconClient, Response, Result};
+
+/// A service that executes each command it is called with on an
+/// [`RconClient`], pipelined with any others in flight.
+///
+/// It is ready as long as the client's connection is open, and fails with
+/// [`Error::ConnectionClosed`] once it is not. Cloning it is cheap and
+/// shares the connection.
+#[derive(Debug, Clone)]
+pub struct RconService {
+    client: RconClient,
+}
+
+impl RconService {
+    pub fn new(client: RconClient) -> Self {
+        RconService { client }
+    }
+
+    /// Returns the client commands are executed on.
+    pub fn client(&self) -> &RconClient {
+        &self.client
+    }
+}
+
+impl From<RconClient> for RconService {
+    fn from(client: RconClient) -> Self {
+        RconService::new(client)
+    }
+}
+
+impl Service<String> for RconService {
+    type Response = Response;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Response>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        match self.client.is_closed() {
+            true => Poll::Ready(Err(Error::ConnectionClosed)),
+            false => Poll::Ready(Ok(())),
+        }
+    }
+
+    fn call(&mut self, command: String) -> Self::Future {
+        let client = self.client.clone();
+
+        Box::pin(async move { client.execute(&command).await })
+    }
+}
@@ -0,0 +1,53 @@
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::{Mutex, MutexGuard},
+};
+
+use crate::{Connection, ConnectionMonitor, Result};
+
+/// A [`Connection`] that can be used through a shared reference, for example
+/// from several tasks behind an [`Arc`](std::sync::Arc).
+///
+/// Commands are strictly serialized: each command is sent and its whole
+/// response received before the next one starts, so responses can never be
+/// interleaved between callers. There is no concurrency benefit, only safe
+/// sharing. A command whose future is dropped part way through may leave its
+/// response unread for the next caller.
+#[derive(Debug)]
+pub struct SharedConnection<T> {
+    connection: Mutex<Connection<T>>,
+    monitor: ConnectionMonitor,
+}
+
+impl<T> SharedConnection<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    pub(crate) fn new(connection: Connection<T>) -> Self {
+        SharedConnection {
+            monitor: connection.monitor(),
+            connection: Mutex::new(connection),
+        }
+    }
+
+    /// Executes a command on the server once every earlier caller is done.
+    pub async fn execute_command(&self, command: &str) -> Result<Vec<String>> {
+        self.connection.lock().await.execute_command(command).await
+    }
+
+    /// Waits for exclusive access to the connection, for anything other than
+    /// executing a single command.
+    pub async fn lock(&self) -> MutexGuard<'_, Connection<T>> {
+        self.connection.lock().await
+    }
+
+    /// Returns a read-only view of the connection's state and stats.
+    pub fn monitor(&self) -> ConnectionMonitor {
+        self.monitor.clone()
+    }
+
+    /// Returns the wrapped connection.
+    pub fn into_inner(self) -> Connection<T> {
+        self.connection.into_inner()
+    }
+}
@@ -0,0 +1,164 @@
+//! Parsing the output of the Source engine's `status` command.
+//!
+//! The layout differs between games: CS:GO, TF2 and Garry's Mod list players
+//! as `#`-prefixed lines with a quoted name and a SteamID, while CS2 lists
+//! them in a table with the name last and no SteamID. [`Status::parse`]
+//! accepts both.
+
+use crate::{
+    parse::{parse_pairs, parse_source_status},
+    Command, Error, Result,
+};
+
+pub use crate::parse::StatusPlayer;
+
+/// The server and players described by a `status` response.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Status {
+    pub hostname: String,
+    pub version: String,
+    pub map: String,
+    /// Human players connected.
+    pub players: u32,
+    pub bots: u32,
+    pub max_players: Option<u32>,
+    /// Connected players, without bots or connections still being set up.
+    pub player_list: Vec<StatusPlayer>,
+    /// Player lines that could not be parsed.
+    pub malformed: Vec<String>,
+}
+
+impl Status {
+    /// Parses a `status` response, failing with
+    /// [`Error::UnexpectedResponse`] if it has neither a hostname nor a
+    /// player table.
+    ///
+    /// ```
+    /// use specul::source::Status;
+    ///
+    /// let status = Status::parse(
+    ///     "hostname: My Server\n\
+    ///      version : 8622567/24 8622567 secure\n\
+    ///      map     : ctf_2fort at: 0 x, 0 y, 0 z\n\
+    ///      players : 1 humans, 1 bots (24 max)\n\
+    ///      ## userid name uniqueid connected ping loss state adr\n\
+    ///      ##      2 \"Gordon\" [U:1:22202] 00:35 50 0 active 10.0.0.7:27005\n\
+    ///      ##      3 \"Bot\" BOT active\n",
+    /// )?;
+    ///
+    /// assert_eq!(status.map, "ctf_2fort");
+    /// assert_eq!((status.players, status.bots, status.max_players), (1, 1, Some(24)));
+    /// assert_eq!(status.player_list[0].name, "Gordon");
+    /// assert_eq!(status.player_list[0].ping, 50);
+    /// # Ok::<(), specul::Error>(())
+    /// ```
+    pub fn parse(text: &str) -> Result<Status> {
+        // The player table starts at the first line that is not a field.
+        let table_start = text
+            .lines()
+            .position(|line| {
+                let line = line.trim_start();
+                line.starts_with('#') || line.starts_with("---")
+            })
+            .unwrap_or(usize::MAX);
+
+        let header: String = text
+            .lines()
+            .take(table_start)
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let fields = parse_pairs(&header, &[':']);
+
+        let mut status = Status {
+            hostname: fields.get("hostname").cloned().unwrap_or_default(),
+            version: fields.get("version").cloned().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        status.map = match fields.get("map") {
+            Some(map) => first_word(map),
+            None => cs2_map(&header).unwrap_or_default(),
+        };
+
+        if let Some(players) = fields.get("players") {
+            parse_counts(players, &mut status);
+        }
+
+        let players = parse_source_status(text);
+        status.player_list = players.players;
+        status.malformed = players.malformed;
+
+        if status.hostname.is_empty() && table_start == usize::MAX {
+            return Err(Error::UnexpectedResponse(text.to_string()));
+        }
+
+        Ok(status)
+    }
+}
+
+/// `status`, parsed into a [`Status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GetStatus;
+
+impl Command for GetStatus {
+    type Output = Status;
+
+    fn to_command_string(&self) -> String {
+        "status".to_string()
+    }
+
+    fn parse_response(response: &str) -> Result<Status> {
+        Status::parse(response)
+    }
+}
+
+fn first_word(text: &str) -> String {
+    text.split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Finds the map in CS2's `loaded spawngroup(  1)  : SV:  [1: de_dust2 |
+/// main lump | mapload]`.
+fn cs2_map(header: &str) -> Option<String> {
+    header
+        .lines()
+        .find(|line| line.contains("main lump"))
+        .and_then(|line| line.split_once('['))
+        .and_then(|(_, rest)| rest.split_once(':'))
+        .and_then(|(_, rest)| rest.split('|').next())
+        .map(|map| map.trim().to_string())
+}
+
+/// Parses `2 humans, 1 bots (20/0 max)`, or `1 (32 max)` as older games
+/// print it.
+fn parse_counts(text: &str, status: &mut Status) {
+    let (counts, max) = match text.split_once('(') {
+        Some((counts, rest)) => (counts, Some(rest)),
+        None => (text, None),
+    };
+
+    let mut first = None;
+    let mut previous = None;
+
+    for word in counts.split(|c: char| c.is_whitespace() || c == ',') {
+        match word {
+            "humans" => status.players = previous.unwrap_or_default(),
+            "bots" => status.bots = previous.unwrap_or_default(),
+            word => {
+                previous = word.parse().ok();
+
+                if first.is_none() {
+                    first = previous;
+                    status.players = first.unwrap_or_default();
+                }
+            }
+        }
+    }
+
+    status.max_players = max.and_then(|max| {
+        let digits: String = max.chars().take_while(char::is_ascii_digit).collect();
+        digits.parse().ok()
+    });
+}
@@ -0,0 +1,289 @@
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use bytes::BytesMut;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadHalf, WriteHalf};
+
+use crate::{
+    codec::RconCodec,
+    frame::{self, FrameHook},
+    interceptor::Interceptors,
+    monitor::Shared,
+    packet::WireBuffer,
+    quirks::check_control_characters,
+    rate_limit::Pacing,
+    Charset, Connection, ConnectionMonitor, Error, FrameDirection, IdStrategy, Packet, PacketType,
+    Result, State,
+};
+
+/// The packet-id strategy and counter, shared by both halves of a split connection.
+#[derive(Debug)]
+struct PacketIds {
+    strategy: IdStrategy,
+    current: Mutex<i32>,
+    default: i32,
+    keepalive: Option<i32>,
+}
+
+impl PacketIds {
+    fn next(&self) -> i32 {
+        let mut current = self.current.lock().unwrap();
+        self.strategy
+            .next(&mut current, self.default, self.keepalive)
+    }
+}
+
+/// The sending half of a [`Connection`], created with
+/// [`Connection::split`].
+#[derive(Debug)]
+pub struct ConnectionSender<T> {
+    io: WriteHalf<T>,
+    codec: RconCodec,
+    write_buffer: WireBuffer,
+    ids: Arc<PacketIds>,
+    shared: Arc<Shared>,
+    max_payload_size: usize,
+    command_prefix: Option<String>,
+    charset: Charset,
+    reject_control_characters: bool,
+    require_authentication: bool,
+    interceptors: Interceptors,
+    pacing: Pacing,
+    on_frame: Option<FrameHook>,
+}
+
+/// The receiving half of a [`Connection`], created with
+/// [`Connection::split`].
+#[derive(Debug)]
+pub struct ConnectionReceiver<T> {
+    io: ReadHalf<T>,
+    codec: RconCodec,
+    ids: Arc<PacketIds>,
+    shared: Arc<Shared>,
+    read_buffer: BytesMut,
+    on_frame: Option<FrameHook>,
+}
+
+pub(crate) fn split<T>(connection: Connection<T>) -> (ConnectionSender<T>, ConnectionReceiver<T>)
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    let max_command_len = connection.max_command_len();
+    let (reader, writer) = tokio::io::split(connection.io);
+    let codec = RconCodec::new(connection.framing)
+        .with_max_incoming_packet_size(connection.max_incoming_packet_size);
+    let ids = Arc::new(PacketIds {
+        strategy: connection.id_strategy,
+        current: Mutex::new(connection.current_packet_id),
+        default: connection.default_packet_id,
+        keepalive: connection.keepalive_id,
+    });
+
+    let sender = ConnectionSender {
+        io: writer,
+        codec,
+        write_buffer: connection.write_buffer,
+        ids: ids.clone(),
+        shared: connection.shared.clone(),
+        max_payload_size: max_command_len,
+        command_prefix: connection.command_prefix,
+        charset: connection.charset,
+        reject_control_characters: connection.reject_control_characters,
+        require_authentication: connection.require_authentication,
+        interceptors: connection.interceptors,
+        pacing: connection.pacing,
+        on_frame: connection.on_frame.clone(),
+    };
+
+    let receiver = ConnectionReceiver {
+        io: reader,
+        codec: codec.owing(connection.owed_nulls),
+        ids,
+        shared: connection.shared,
+        read_buffer: connection.read_buffer,
+        on_frame: connection.on_frame,
+    };
+
+    (sender, receiver)
+}
+
+impl<T: AsyncWrite> ConnectionSender<T> {
+    /// Sends a command, with the connection's `command_prefix` and
+    /// interceptors' `before_send`, without waiting for its response.
+    /// Waits first if the connection's `min_command_interval` or `rate_limit`
+    /// says so.
+    ///
+    /// Returns the packet id the response will carry, for matching it to
+    /// the packets read from the [`ConnectionReceiver`].
+    pub async fn send_command(&mut self, command: &str) -> Result<i32> {
+        let command = self.prepare(command)?;
+        self.pace().await;
+        let id = self.new_packet_id();
+        let packet = self.command_packet(id, &command)?;
+
+        self.send_packet(&packet).await?;
+        Ok(id)
+    }
+
+    /// Sends a packet as it is, such as the empty `SERVERDATA_RESPONSE_VALUE`
+    /// that marks the end of a multi-packet response.
+    pub async fn send_packet(&mut self, packet: &Packet) -> Result<()> {
+        self.send_packets(std::slice::from_ref(packet)).await
+    }
+
+    /// Sends packets in a single write and flush, such as a command and the
+    /// sentinel after it, or many commands being pipelined.
+    pub async fn send_packets(&mut self, packets: &[Packet]) -> Result<()> {
+        let buffer = self.write_buffer.start();
+        let written = packets
+            .iter()
+            .map(|packet| self.codec.encode_packet(packet, buffer))
+            .collect::<io::Result<Vec<_>>>()?;
+
+        let result = self.io.write_all(self.write_buffer.bytes()).await;
+
+        if result.is_ok() {
+            frame::emit_sent(
+                self.on_frame.as_ref(),
+                packets,
+                self.write_buffer.bytes(),
+                &written,
+                self.codec.framing(),
+            );
+        }
+
+        self.write_buffer.clear();
+        result?;
+        self.io.flush().await?;
+
+        for (_packet, written) in packets.iter().zip(written) {
+            #[cfg(feature = "tracing")]
+            _packet.trace_sent(written);
+
+            self.shared.record_sent(written);
+        }
+        Ok(())
+    }
+
+    /// Returns the next packet id from the `id_strategy`, never the
+    /// `keepalive_id`.
+    pub fn new_packet_id(&self) -> i32 {
+        self.ids.next()
+    }
+
+    /// Returns a monitor for the connection.
+    pub fn monitor(&self) -> ConnectionMonitor {
+        ConnectionMonitor::new(self.shared.clone())
+    }
+
+    /// Shuts down the write side, telling the server no more commands follow.
+    pub async fn shutdown(&mut self) -> Result<()> {
+        Ok(self.io.shutdown().await?)
+    }
+
+    /// Waits until the connection's pacing lets the next command through.
+    pub(crate) async fn pace(&mut self) {
+        self.pacing.wait().await;
+    }
+
+    /// Whether commands may have to wait for [`pace`](Self::pace).
+    #[cfg_attr(not(feature = "client"), allow(dead_code))]
+    pub(crate) fn is_paced(&self) -> bool {
+        self.pacing.is_active()
+    }
+
+    /// Returns `command` as it is sent, with the `command_prefix` added and
+    /// rewritten by the interceptors.
+    pub(crate) fn prepare(&self, command: &str) -> Result<String> {
+        if self.require_authentication && self.shared.state() != State::Authenticated {
+            return Err(Error::NotAuthenticated);
+        }
+
+        let mut command = match &self.command_prefix {
+            Some(prefix) if !command.starts_with(prefix.as_str()) => {
+                format!("{}{}", prefix, command)
+            }
+            _ => command.to_string(),
+        };
+
+        self.interceptors.before_send(&mut command)?;
+
+        if self.reject_control_characters {
+            check_control_characters(&command, "command")?;
+        }
+
+        Ok(command)
+    }
+
+    /// Builds the packet for a [prepared](Self::prepare) `command`, counting
+    /// it as a command sent.
+    pub(crate) fn command_packet(&self, id: i32, command: &str) -> Result<Packet> {
+        let payload = self.encode(command.to_string())?;
+
+        if payload.len() > self.max_payload_size {
+            return Err(Error::PayloadSize);
+        }
+
+        self.shared.record_command();
+        Ok(Packet::new(id, PacketType::Message, payload))
+    }
+
+    /// Encodes `text` with the connection's `charset`.
+    pub(crate) fn encode(&self, text: String) -> Result<Vec<u8>> {
+        Ok(self.charset.encode_string(text)?)
+    }
+}
+
+impl<T: AsyncRead> ConnectionReceiver<T> {
+    /// Receives the next packet, skipping empty packets with the
+    /// `keepalive_id`.
+    pub async fn receive_packet(&mut self) -> Result<Packet> {
+        loop {
+            let packet = self.next_packet().await?;
+
+            if !(packet.payload.is_empty() && Some(packet.id) == self.ids.keepalive) {
+                return Ok(packet);
+            }
+        }
+    }
+
+    /// Returns a monitor for the connection.
+    pub fn monitor(&self) -> ConnectionMonitor {
+        ConnectionMonitor::new(self.shared.clone())
+    }
+
+    async fn next_packet(&mut self) -> Result<Packet> {
+        loop {
+            let buffered = frame::wanted(self.on_frame.as_ref()).then(|| self.read_buffer.to_vec());
+
+            if let Some(packet) = self.codec.decode_packet(&mut self.read_buffer)? {
+                if let Some(buffered) = &buffered {
+                    let consumed = buffered.len() - self.read_buffer.len();
+                    frame::emit(
+                        self.on_frame.as_ref(),
+                        FrameDirection::Received,
+                        &packet,
+                        &buffered[..consumed],
+                        self.codec.framing(),
+                    );
+                }
+
+                #[cfg(feature = "tracing")]
+                packet.trace_received();
+
+                self.shared.record_packet_received();
+                return Ok(packet);
+            }
+
+            let read = self.io.read_buf(&mut self.read_buffer).await?;
+
+            if read == 0 {
+                return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+            }
+
+            self.shared.record_bytes_received(read);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use futures_core::Stream;
+use futures_sink::Sink;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::{frame, packet::Outgoing, Connection, Error, FrameDirection, Packet, Result, State};
+
+/// Packets queued past this many bytes are flushed before more are accepted.
+const BACKPRESSURE: usize = 8 * 1024;
+
+/// A [`Connection`] as a [`Stream`] of the packets it receives and a
+/// [`Sink`] of packets to send, created with
+/// [`Connection::into_stream`].
+///
+/// Packets are read and written like
+/// [`receive_packet`](Connection::receive_packet) and
+/// [`send_packet`](Connection::send_packet) do: nothing is skipped or
+/// matched to a command. The stream ends when the server closes the
+/// connection, and `read_timeout` and `timeout` do not apply.
+///
+/// ```no_run
+/// # async fn run() -> specul::Result<()> {
+/// use futures::{SinkExt, StreamExt};
+/// use specul::{Connection, Packet, PacketType};
+///
+/// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+/// let id = connection.new_packet_id();
+/// let mut stream = connection.into_stream();
+///
+/// stream.send(Packet::new(id, PacketType::Message, "status")).await?;
+///
+/// while let Some(packet) = stream.next().await {
+///     println!("{}", String::from_utf8_lossy(packet?.as_bytes()));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PacketStream<T> {
+    connection: Connection<T>,
+}
+
+impl<T> PacketStream<T> {
+    pub(crate) fn new(connection: Connection<T>) -> Self {
+        PacketStream { connection }
+    }
+
+    pub fn get_ref(&self) -> &Connection<T> {
+        &self.connection
+    }
+
+    pub fn get_mut(&mut self) -> &mut Connection<T> {
+        &mut self.connection
+    }
+
+    /// Returns the connection, with any packets received but not yet taken
+    /// from the stream still buffered. Packets sent but not flushed are
+    /// dropped.
+    pub fn into_inner(self) -> Connection<T> {
+        self.connection
+    }
+}
+
+impl<T> Stream for PacketStream<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    type Item = Result<Packet>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Packet>>> {
+        match ready!(self.get_mut().connection.poll_next_packet(cx)) {
+            Err(Error::Io(error)) if error.kind() == io::ErrorKind::UnexpectedEof => {
+                Poll::Ready(None)
+            }
+            Err(Error::ConnectionClosed) => Poll::Ready(None),
+            result => Poll::Ready(Some(result)),
+        }
+    }
+}
+
+impl<T> Sink<Packet> for PacketStream<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    type Error = Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.connection.write_buffer.bytes().len() >= BACKPRESSURE {
+            return self.poll_flush(cx);
+        }
+
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, packet: Packet) -> Result<()> {
+        let connection = &mut self.get_mut().connection;
+
+        if connection.state() == State::Closed {
+            return Err(Error::ConnectionClosed);
+        }
+
+        let packet = Outgoing(packet);
+        let written = connection
+            .codec()
+            .encode_packet(&packet, connection.write_buffer.append())?;
+
+        let bytes = connection.write_buffer.bytes();
+        frame::emit(
+            connection.on_frame.as_ref(),
+            FrameDirection::Sent,
+            &packet,
+            &bytes[bytes.len() - written..],
+            connection.framing,
+        );
+
+        #[cfg(feature = "tracing")]
+        packet.trace_sent(written);
+
+        connection.shared.record_sent(written);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        let connection = &mut self.get_mut().connection;
+
+        while !connection.write_buffer.bytes().is_empty() {
+            let written = ready!(
+                Pin::new(&mut connection.io).poll_write(cx, connection.write_buffer.bytes())
+            )?;
+
+            if written == 0 {
+                return Poll::Ready(Err(Error::Io(io::ErrorKind::WriteZero.into())));
+            }
+
+            connection.write_buffer.consume(written);
+        }
+
+        ready!(Pin::new(&mut connection.io).poll_flush(cx))?;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        ready!(self.as_mut().poll_flush(cx))?;
+        ready!(Pin::new(&mut self.get_mut().connection.io).poll_shutdown(cx))?;
+        Poll::Ready(Ok(()))
+    }
+}
@@ -0,0 +1,154 @@
+use std::{io, net::SocketAddr};
+
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::{lookup_host, TcpStream, ToSocketAddrs},
+};
+
+use crate::{Connection, ConnectionBuilder, ConnectionConfig, Connector, Result};
+
+impl Connection<TcpStream> {
+    /// Connects to `addr` and returns an authenticated connection that is
+    /// ready for commands.
+    ///
+    /// In order, this:
+    /// 1. resolves `addr` and connects to the first address that accepts,
+    /// 2. builds a connection with default settings and a [`Connector`] that
+    ///    dials the same addresses, so [`reconnect`](Connection::reconnect) works,
+    /// 3. [drains](Connection::drain) any banner packets sent before
+    ///    authentication,
+    /// 4. [authenticates](Connection::authenticate) with `password`, skipping
+    ///    the empty mirror packet some servers send first,
+    /// 5. drains any packets left over after authentication.
+    ///
+    /// To change any of these steps, connect the `TcpStream` yourself, build
+    /// the connection with [`ConnectionBuilder`] and call `drain` and
+    /// `authenticate` as needed.
+    pub async fn connect_and_ready(addr: impl ToSocketAddrs, password: &str) -> Result<Self> {
+        let mut connection = Self::dial(addr).await?;
+        connection.ready(password).await?;
+
+        Ok(connection)
+    }
+
+    /// Connects to `addr` and authenticates with `password`, returning a
+    /// connection with default settings and a [`Connector`] that dials the
+    /// same addresses again on [`reconnect`](Connection::reconnect).
+    ///
+    /// Unlike [`connect_and_ready`](Connection::connect_and_ready), nothing is
+    /// drained, so servers that send a banner before authenticating need the
+    /// latter.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::Connection;
+    ///
+    /// let mut connection = Connection::connect("127.0.0.1:27015", "password").await?;
+    /// let response = connection.execute_command("status").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect(addr: impl ToSocketAddrs, password: &str) -> Result<Self> {
+        let mut connection = Self::dial(addr).await?;
+        connection.authenticate(password).await?;
+
+        Ok(connection)
+    }
+
+    /// Connects and authenticates as described by an `rcon://` URL, with
+    /// the URL's options applied to the connection. See
+    /// [`ConnectionConfig::from_url`] for the format.
+    ///
+    /// ```no_run
+    /// # async fn run() -> specul::Result<()> {
+    /// use specul::Connection;
+    ///
+    /// let mut connection =
+    ///     Connection::connect_url("rcon://:password@127.0.0.1:27015?timeout=5s").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_url(url: &str) -> Result<Self> {
+        let config = ConnectionConfig::from_url(url)?;
+        let mut connection =
+            Self::dial_with(config.addr(), |builder| config.apply(builder)).await?;
+        connection.authenticate(&config.password).await?;
+
+        Ok(connection)
+    }
+
+    pub(crate) async fn dial(addr: impl ToSocketAddrs) -> Result<Self> {
+        Self::dial_with(addr, |builder| builder).await
+    }
+
+    /// Connects to `addr` and builds the connection with the settings
+    /// `configure` adds, failing with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) io error if they do not
+    /// build.
+    pub(crate) async fn dial_with(
+        addr: impl ToSocketAddrs,
+        configure: impl FnOnce(ConnectionBuilder<TcpStream>) -> ConnectionBuilder<TcpStream>,
+    ) -> Result<Self> {
+        let open = async {
+            let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+            let tcp = TcpStream::connect(&addrs[..]).await?;
+            Ok::<_, io::Error>((addrs, tcp))
+        };
+
+        #[cfg(feature = "tracing")]
+        let span = tracing::debug_span!("connect", peer = tracing::field::Empty);
+        #[cfg(feature = "tracing")]
+        let open = tracing::Instrument::instrument(open, span.clone());
+
+        let (addrs, tcp) = open.await?;
+        let peer_addr = tcp.peer_addr().ok();
+
+        #[cfg(feature = "tracing")]
+        if let Some(peer) = peer_addr {
+            span.record("peer", tracing::field::display(peer));
+        }
+
+        let connector = Connector::new(move || {
+            let addrs = addrs.clone();
+            async move { TcpStream::connect(&addrs[..]).await }
+        });
+
+        Connection::dialled(tcp, connector, peer_addr, configure)
+    }
+}
+
+impl<T> Connection<T>
+where
+    T: Unpin + AsyncRead + AsyncWrite,
+{
+    /// Builds a connection over `io`, which `connector` dialled to
+    /// `peer_addr`, with the settings `configure` adds, failing with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) io error if they do not
+    /// build.
+    pub(crate) fn dialled(
+        io: T,
+        connector: Connector<T>,
+        peer_addr: Option<SocketAddr>,
+        configure: impl FnOnce(ConnectionBuilder<T>) -> ConnectionBuilder<T>,
+    ) -> Result<Self> {
+        let builder = ConnectionBuilder::default().io(io).connector(connector);
+        let connection = configure(builder)
+            .build()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        connection.shared.set_peer_addr(peer_addr);
+
+        Ok(connection)
+    }
+
+    /// Drains any banner, authenticates with `password` and drains what is
+    /// left over, as [`connect_and_ready`](Connection::connect_and_ready)
+    /// describes.
+    pub(crate) async fn ready(&mut self, password: &str) -> Result<()> {
+        self.drain().await?;
+        self.authenticate(password).await?;
+        self.drain().await?;
+
+        Ok(())
+    }
+}
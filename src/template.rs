@@ -0,0 +1,201 @@
+use std::{collections::HashMap, fmt};
+
+use crate::{Error, Quirks, Result};
+
+/// A command with named placeholders, such as
+/// `banid {minutes} {steamid} "{reason}"`, filled in with
+/// [`render`](CommandTemplate::render).
+///
+/// Values are escaped by the same rules as [`Quirks::escape_argument`], so
+/// a value cannot end the command early or run another one, and no value may
+/// hold a control character other than a tab. Outside quotes, a value is
+/// escaped as one argument, quoted where the server would split it. Within
+/// quotes, Minecraft escapes `"` and `\` in values, while Source and
+/// Factorio, which have no escapes, reject values holding `"`.
+///
+/// `{{` and `}}` stand for literal braces.
+///
+/// ```
+/// use specul::{CommandTemplate, Quirks};
+///
+/// let ban = CommandTemplate::new(r#"banid {minutes} {steamid} "{reason}""#)?;
+/// let command = ban.render([
+///     ("steamid", "STEAM_1:0:11101"),
+///     ("minutes", "30"),
+///     ("reason", "team killing"),
+/// ])?;
+///
+/// assert_eq!(command, r#"banid 30 "STEAM_1:0:11101" "team killing""#);
+/// assert!(ban.render([("steamid", "STEAM_1:0:11101")]).is_err());
+///
+/// let kick = CommandTemplate::new(r#"kick {player} "{reason}""#)?.quirks(Quirks::Minecraft);
+/// assert_eq!(
+///     kick.render([("player", "Steve"), ("reason", r#"said "hi""#)])?,
+///     r#"kick Steve "said \"hi\"""#
+/// );
+/// # Ok::<(), specul::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CommandTemplate {
+    text: String,
+    parts: Vec<Part>,
+    quirks: Quirks,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Part {
+    Literal(String),
+    Placeholder { name: String, quoted: bool },
+}
+
+impl CommandTemplate {
+    /// Parses a template, failing with [`Error::Template`] on an unclosed
+    /// or unmatched brace, or a placeholder name other than letters, digits
+    /// and `_`. Values are checked by Source's rules unless
+    /// [`quirks`](CommandTemplate::quirks) says otherwise.
+    pub fn new(text: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut quoted = false;
+        let mut chars = text.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    let mut closed = false;
+
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        name.push(c);
+                    }
+
+                    if !closed {
+                        return Err(invalid(format!("unclosed placeholder {{{}", name)));
+                    }
+
+                    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                        return Err(invalid(format!("invalid placeholder name {:?}", name)));
+                    }
+
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Placeholder { name, quoted });
+                }
+                '}' => return Err(invalid("unmatched }")),
+                '\\' if quoted => {
+                    literal.push(c);
+                    literal.extend(chars.next());
+                }
+                '"' => {
+                    quoted = !quoted;
+                    literal.push(c);
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(CommandTemplate {
+            text: text.to_string(),
+            parts,
+            quirks: Quirks::Source,
+        })
+    }
+
+    /// Sets the server whose rules values are checked and escaped by.
+    pub fn quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Returns the names of the placeholders, in order, with repeats.
+    pub fn placeholders(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            Part::Placeholder { name, .. } => Some(name.as_str()),
+            Part::Literal(_) => None,
+        })
+    }
+
+    /// Fills in the placeholders from `(name, value)` pairs, ignoring
+    /// names the template does not use.
+    ///
+    /// Fails with [`Error::Template`] if a placeholder has no value, or a
+    /// value is not allowed where it goes.
+    pub fn render<I, K, V>(&self, values: I) -> Result<String>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        let values: HashMap<String, V> = values
+            .into_iter()
+            .map(|(name, value)| (name.as_ref().to_string(), value))
+            .collect();
+        let mut command = String::with_capacity(self.text.len());
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => command.push_str(text),
+                Part::Placeholder { name, quoted } => {
+                    let value = values
+                        .get(name)
+                        .ok_or_else(|| invalid(format!("missing parameter {}", name)))?;
+
+                    self.substitute(name, value.as_ref(), *quoted, &mut command)?;
+                }
+            }
+        }
+
+        Ok(command)
+    }
+
+    fn substitute(
+        &self,
+        name: &str,
+        value: &str,
+        quoted: bool,
+        command: &mut String,
+    ) -> Result<()> {
+        let what = format!("parameter {}", name);
+        let escaped = match quoted {
+            true => self.quirks.escape_quoted(value, &what),
+            false => self.quirks.escape(value, &what),
+        };
+
+        match escaped {
+            Ok(escaped) => {
+                command.push_str(&escaped);
+                Ok(())
+            }
+            Err(Error::InvalidCommand(message)) => Err(invalid(message)),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// Displays the template as it was written.
+impl fmt::Display for CommandTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+fn invalid(message: impl Into<String>) -> Error {
+    Error::Template(message.into())
+}
@@ -0,0 +1,205 @@
+//! A scripted RCON server for deterministic tests of code that uses this
+//! crate.
+//!
+//! ```
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use specul::testing::{MockServer, Reply};
+//! use specul::Connection;
+//!
+//! let mock = MockServer::builder("password")
+//!     .respond("status", Reply::text("hostname: test"))
+//!     .start()
+//!     .await?;
+//!
+//! let mut connection = Connection::connect(mock.addr(), "password").await?;
+//! assert_eq!(connection.execute_command("status").await?, vec!["hostname: test"]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, io, net::SocketAddr, sync::Arc};
+
+use bytes::BytesMut;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::{codec::RconCodec, Packet, PacketType};
+
+/// How the mock answers a command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Reply {
+    /// One response packet.
+    Text(String),
+    /// A response split over one packet per fragment.
+    Fragments(Vec<String>),
+    /// A packet with an id that answers nothing, followed by the reply.
+    Junk { payload: String, then: Box<Reply> },
+    /// Bytes written as they are, for malformed-packet scenarios.
+    Raw(Vec<u8>),
+    /// No response at all.
+    Silent,
+}
+
+impl Reply {
+    /// A reply of one packet holding `text`.
+    pub fn text(text: impl Into<String>) -> Self {
+        Reply::Text(text.into())
+    }
+
+    /// A reply split over one packet per fragment.
+    pub fn fragments<I, S>(fragments: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Reply::Fragments(fragments.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Configures a [`MockServer`].
+#[derive(Debug, Clone)]
+pub struct MockServerBuilder {
+    password: String,
+    replies: HashMap<String, Reply>,
+}
+
+impl MockServerBuilder {
+    /// Answers `command` with `reply`. Unregistered commands are answered
+    /// with `Unknown command "<command>"`.
+    pub fn respond(mut self, command: impl Into<String>, reply: Reply) -> Self {
+        self.replies.insert(command.into(), reply);
+        self
+    }
+
+    /// Binds an ephemeral port on localhost and starts serving.
+    pub async fn start(self) -> io::Result<MockServer> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let script = Arc::new(self);
+
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let script = script.clone();
+
+                tokio::spawn(async move {
+                    let _ = script.serve(stream).await;
+                });
+            }
+        });
+
+        Ok(MockServer { addr, task })
+    }
+
+    async fn serve(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut codec = RconCodec::default();
+        let mut read_buffer = BytesMut::new();
+        let mut authenticated = false;
+
+        loop {
+            let request = match codec.decode_packet(&mut read_buffer) {
+                Ok(Some(packet)) => packet,
+                Ok(None) => {
+                    if stream.read_buf(&mut read_buffer).await? == 0 {
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Err(_) => return Ok(()),
+            };
+
+            let Ok(payload) = request.to_str() else {
+                return Ok(());
+            };
+
+            let mut write_buffer = BytesMut::new();
+
+            match request.packet_type {
+                PacketType::Authentication => {
+                    authenticated = payload == self.password;
+                    let id = if authenticated { request.id } else { -1 };
+
+                    let mirror = Packet::new(request.id, PacketType::Response, String::new());
+                    let response =
+                        Packet::new(id, PacketType::AuthenticationResponse, String::new());
+                    codec.encode_packet(&mirror, &mut write_buffer)?;
+                    codec.encode_packet(&response, &mut write_buffer)?;
+                }
+                PacketType::AuthenticationResponse | PacketType::Message if authenticated => {
+                    let unknown = Reply::Text(format!("Unknown command \"{}\"", payload));
+                    let reply = self.replies.get(payload).unwrap_or(&unknown);
+
+                    encode_reply(&codec, request.id, reply, &mut write_buffer)?;
+                }
+                PacketType::Response if authenticated => {
+                    let mirror = Packet::new(request.id, PacketType::Response, String::new());
+                    let marker =
+                        Packet::new(request.id, PacketType::Response, "\0\u{1}\0\0".to_string());
+                    codec.encode_packet(&mirror, &mut write_buffer)?;
+                    codec.encode_packet(&marker, &mut write_buffer)?;
+                }
+                _ => {}
+            }
+
+            stream.write_all(&write_buffer).await?;
+
+            if !authenticated {
+                return stream.shutdown().await;
+            }
+        }
+    }
+}
+
+fn encode_reply(codec: &RconCodec, id: i32, reply: &Reply, dst: &mut BytesMut) -> io::Result<()> {
+    match reply {
+        Reply::Text(text) => {
+            codec.encode_packet(&Packet::new(id, PacketType::Response, text.clone()), dst)?;
+        }
+        Reply::Fragments(fragments) => {
+            for fragment in fragments {
+                let packet = Packet::new(id, PacketType::Response, fragment.clone());
+                codec.encode_packet(&packet, dst)?;
+            }
+        }
+        Reply::Junk { payload, then } => {
+            let junk = Packet::new(id.wrapping_add(1000), PacketType::Response, payload.clone());
+            codec.encode_packet(&junk, dst)?;
+            encode_reply(codec, id, then, dst)?;
+        }
+        Reply::Raw(bytes) => dst.extend_from_slice(bytes),
+        Reply::Silent => {}
+    }
+
+    Ok(())
+}
+
+/// A running scripted server, stopped when dropped.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Starts configuring a mock that accepts `password`.
+    pub fn builder(password: impl Into<String>) -> MockServerBuilder {
+        MockServerBuilder {
+            password: password.into(),
+            replies: HashMap::new(),
+        }
+    }
+
+    /// Returns the address the mock is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -0,0 +1,124 @@
+//! Removing the formatting codes games embed in their output, for showing
+//! responses in logs and UIs.
+//!
+//! Set `strip_formatting` on the builder to apply [`strip_formatting`] to
+//! every response, or call these functions on the responses that need it.
+//!
+//! ```
+//! use specul::text::strip_formatting;
+//!
+//! assert_eq!(strip_formatting("§aSteve§r joined"), "Steve joined");
+//! assert_eq!(strip_formatting("\x1b[1;31mError\x1b[0m"), "Error");
+//! assert_eq!(strip_formatting("\x04[SM]\x01 Reloaded"), "[SM] Reloaded");
+//! ```
+
+const SECTION_SIGN: char = '§';
+const ESCAPE: char = '\x1b';
+
+/// Removes Minecraft's `§` codes, such as `§a` for green and `§l` for bold.
+///
+/// The hex colors of newer servers, `§x§r§r§g§g§b§b`, are made of such codes
+/// too and are removed whole.
+pub fn strip_minecraft(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            SECTION_SIGN => {
+                chars.next();
+            }
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+/// Removes ANSI escape sequences: `ESC [ ... m` style CSI sequences such as
+/// colors, `ESC ] ...` OSC sequences such as window titles, and two-byte
+/// escapes.
+pub fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESCAPE {
+            stripped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // Parameters and intermediates, up to a final byte in @..~.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Terminated by BEL or by ESC \.
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+
+                    if c == ESCAPE && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    stripped
+}
+
+/// Removes the control bytes Source games color chat and console output
+/// with: `\x01` to `\x10`, and the `\x07RRGGBB` and `\x08RRGGBBAA` hex colors
+/// of TF2 and other Orange Box games. Tabs and line breaks are kept.
+pub fn strip_source_colors(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        rest = &rest[c.len_utf8()..];
+
+        match c {
+            '\t' | '\n' | '\r' => stripped.push(c),
+            '\x07' => rest = skip_hex(rest, 6),
+            '\x08' => rest = skip_hex(rest, 8),
+            '\x01'..='\x10' => {}
+            c => stripped.push(c),
+        }
+    }
+
+    stripped
+}
+
+/// Removes Minecraft codes, ANSI sequences and Source color bytes.
+pub fn strip_formatting(text: &str) -> String {
+    strip_source_colors(&strip_ansi(&strip_minecraft(text)))
+}
+
+/// Skips `digits` hex digits at the start of `text`, if it has that many.
+fn skip_hex(text: &str, digits: usize) -> &str {
+    match text.get(..digits) {
+        Some(hex) if hex.chars().all(|c| c.is_ascii_hexdigit()) => &text[digits..],
+        _ => text,
+    }
+}
+
+/// Returns `payloads`, stripped with [`strip_formatting`] if `strip`.
+pub(crate) fn strip_payloads(strip: bool, payloads: Vec<String>) -> Vec<String> {
+    match strip {
+        true => payloads
+            .iter()
+            .map(|payload| strip_formatting(payload))
+            .collect(),
+        false => payloads,
+    }
+}
@@ -0,0 +1,341 @@
+//! TLS transport, for RCON ports that are only reachable through a TLS
+//! terminator such as stunnel.
+
+use std::{fmt, io, net::SocketAddr, sync::Arc};
+
+use derive_builder::Builder;
+use tokio::net::{lookup_host, TcpStream, ToSocketAddrs};
+use tokio_rustls::{
+    client::TlsStream,
+    rustls::{
+        client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+        crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider},
+        pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime},
+        ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme,
+    },
+    TlsConnector,
+};
+
+pub use tokio_rustls::rustls;
+
+use crate::{Connection, Connector, Result};
+
+/// A certificate the server is allowed to present, for [`pinned_config`].
+///
+/// [`Certificate`](CertificatePin::Certificate) and
+/// [`Sha256`](CertificatePin::Sha256) pin the whole certificate, so they stop
+/// matching when it is reissued, even for the same key.
+/// [`Spki`](CertificatePin::Spki) pins only the public key, as HPKP did, and
+/// keeps matching across renewals that keep it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum CertificatePin {
+    /// The exact DER-encoded certificate.
+    Certificate(CertificateDer<'static>),
+    /// The SHA-256 digest of the DER-encoded certificate, as printed by
+    /// `openssl x509 -in cert.pem -outform der | sha256sum`.
+    Sha256([u8; 32]),
+    /// The SHA-256 digest of the certificate's DER-encoded
+    /// SubjectPublicKeyInfo, as printed by
+    /// `openssl x509 -in cert.pem -pubkey -noout | openssl pkey -pubin -outform der | sha256sum`.
+    Spki([u8; 32]),
+}
+
+impl CertificatePin {
+    fn matches(&self, certificate: &CertificateDer<'_>) -> bool {
+        match self {
+            CertificatePin::Certificate(pinned) => pinned.as_ref() == certificate.as_ref(),
+            CertificatePin::Sha256(digest) => sha256(certificate) == *digest,
+            CertificatePin::Spki(digest) => webpki::EndEntityCert::try_from(certificate)
+                .is_ok_and(|parsed| sha256(&parsed.subject_public_key_info()) == *digest),
+        }
+    }
+}
+
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    ::ring::digest::digest(&::ring::digest::SHA256, bytes)
+        .as_ref()
+        .try_into()
+        .expect("SHA-256 digests are 32 bytes")
+}
+
+/// Accepts a server only if its certificate matches one of the pins.
+///
+/// The certificate chain, expiry and server name are not checked, which is
+/// what makes self-signed game-server certificates usable. The handshake
+/// signature is still verified, so the server must hold the pinned key.
+struct PinnedVerifier {
+    pins: Vec<CertificatePin>,
+    provider: Arc<CryptoProvider>,
+}
+
+impl fmt::Debug for PinnedVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PinnedVerifier")
+            .field("pins", &self.pins)
+            .finish_non_exhaustive()
+    }
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        if self.pins.iter().any(|pin| pin.matches(end_entity)) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(
+                "server certificate does not match any pin".to_string(),
+            ))
+        }
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.provider.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.provider
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
+
+/// Builds a client config that trusts only servers presenting one of the
+/// pinned certificates, for [`connect_tls`](Connection::connect_tls).
+///
+/// This is the usual way to talk to a server with a self-signed
+/// certificate, which the WebPKI roots would reject:
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::sync::Arc;
+///
+/// use specul::tls::{pinned_config, CertificatePin};
+/// use specul::Connection;
+/// use specul::tls::rustls::pki_types::CertificateDer;
+///
+/// let der = std::fs::read("server.der")?;
+/// let config = pinned_config([CertificatePin::Certificate(CertificateDer::from(der))]);
+///
+/// let connection =
+///     Connection::connect_tls("127.0.0.1:27015", "localhost", "password", Arc::new(config))
+///         .await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// For a custom verifier, build a [`ClientConfig`] with
+/// `dangerous().with_custom_certificate_verifier` and pass that to
+/// `connect_tls` instead.
+pub fn pinned_config(pins: impl IntoIterator<Item = CertificatePin>) -> ClientConfig {
+    let provider = Arc::new(ring::default_provider());
+    let verifier = PinnedVerifier {
+        pins: pins.into_iter().collect(),
+        provider: provider.clone(),
+    };
+
+    ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .expect("ring supports the default protocol versions")
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth()
+}
+
+/// A client certificate chain and its private key, for servers that require
+/// mutual TLS.
+#[derive(Debug)]
+pub struct ClientAuth {
+    pub chain: Vec<CertificateDer<'static>>,
+    pub key: PrivateKeyDer<'static>,
+}
+
+impl Clone for ClientAuth {
+    fn clone(&self) -> Self {
+        ClientAuth {
+            chain: self.chain.clone(),
+            key: self.key.clone_key(),
+        }
+    }
+}
+
+/// Which servers to trust and how to identify to them, for building the
+/// [`ClientConfig`] that [`connect_tls`](Connection::connect_tls) takes.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// use std::sync::Arc;
+///
+/// use specul::tls::{rustls::pki_types::CertificateDer, TlsOptionsBuilder};
+/// use specul::Connection;
+///
+/// let ca = CertificateDer::from(std::fs::read("ca.der")?);
+/// let options = TlsOptionsBuilder::default()
+///     .webpki_roots(false)
+///     .root(ca)
+///     .build()?;
+///
+/// let config = Arc::new(options.client_config()?);
+/// let connection =
+///     Connection::connect_tls("rcon.example.com:27015", "rcon.example.com", "password", config)
+///         .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Builder)]
+#[builder(pattern = "owned", build_fn(validate = "Self::validate"))]
+pub struct TlsOptions {
+    /// Trust the Mozilla root certificates bundled with `webpki-roots`.
+    #[builder(default = "true")]
+    webpki_roots: bool,
+    /// Additional trusted roots, such as a private CA.
+    #[builder(default, setter(each(name = "root")))]
+    roots: Vec<CertificateDer<'static>>,
+    /// Certificates the server may present instead of one that chains to a
+    /// root, as with [`pinned_config`]. Pinning replaces root verification,
+    /// so it cannot be combined with `roots`.
+    #[builder(default, setter(each(name = "pin")))]
+    pins: Vec<CertificatePin>,
+    /// A certificate to present to the server.
+    #[builder(default, setter(strip_option))]
+    client_auth: Option<ClientAuth>,
+    /// Send the domain as Server Name Indication. Some terminators serve a
+    /// different certificate, or none, when it is missing or present.
+    #[builder(default = "true")]
+    sni: bool,
+}
+
+impl TlsOptionsBuilder {
+    fn validate(&self) -> std::result::Result<(), String> {
+        let pinned = self.pins.as_ref().is_some_and(|pins| !pins.is_empty());
+        let roots = self.roots.as_ref().is_some_and(|roots| !roots.is_empty());
+
+        if pinned && roots {
+            return Err("pins cannot be combined with roots".to_string());
+        }
+
+        if !pinned && !roots && self.webpki_roots == Some(false) {
+            return Err("no roots or pins to trust".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+impl TlsOptions {
+    /// Builds the client config, failing with an
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput) io error if a root
+    /// certificate or the client key cannot be used.
+    pub fn client_config(&self) -> Result<ClientConfig> {
+        let provider = Arc::new(ring::default_provider());
+        let builder = ClientConfig::builder_with_provider(provider.clone())
+            .with_safe_default_protocol_versions()
+            .expect("ring supports the default protocol versions");
+
+        let builder = if self.pins.is_empty() {
+            let mut store = RootCertStore::empty();
+
+            if self.webpki_roots {
+                store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+            }
+
+            for root in &self.roots {
+                store.add(root.clone()).map_err(invalid_input)?;
+            }
+
+            builder.with_root_certificates(store)
+        } else {
+            builder
+                .dangerous()
+                .with_custom_certificate_verifier(Arc::new(PinnedVerifier {
+                    pins: self.pins.clone(),
+                    provider,
+                }))
+        };
+
+        let mut config = match &self.client_auth {
+            Some(auth) => builder
+                .with_client_auth_cert(auth.chain.clone(), auth.key.clone_key())
+                .map_err(invalid_input)?,
+            None => builder.with_no_client_auth(),
+        };
+
+        config.enable_sni = self.sni;
+        Ok(config)
+    }
+}
+
+fn invalid_input(error: rustls::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error)
+}
+
+impl Connection<TlsStream<TcpStream>> {
+    /// Connects to `addr` over TLS and returns an authenticated connection
+    /// that is ready for commands, like
+    /// [`connect_and_ready`](Connection::connect_and_ready).
+    ///
+    /// `domain` is the name sent as SNI and, depending on `config`, checked
+    /// against the certificate. `config` decides which certificates are
+    /// trusted; build one with [`TlsOptions`], or see [`pinned_config`] for
+    /// self-signed servers.
+    pub async fn connect_tls(
+        addr: impl ToSocketAddrs,
+        domain: &str,
+        password: &str,
+        config: Arc<ClientConfig>,
+    ) -> Result<Self> {
+        let addrs: Vec<SocketAddr> = lookup_host(addr).await?.collect();
+        let domain = ServerName::try_from(domain.to_string())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        let tls = TlsConnector::from(config);
+
+        let dial = move || {
+            let addrs = addrs.clone();
+            let domain = domain.clone();
+            let tls = tls.clone();
+
+            async move {
+                let tcp = TcpStream::connect(&addrs[..]).await?;
+                tls.connect(domain, tcp).await
+            }
+        };
+
+        let stream = dial().await?;
+        let peer_addr = stream.get_ref().0.peer_addr().ok();
+
+        let mut connection =
+            Connection::dialled(stream, Connector::new(dial), peer_addr, |builder| builder)?;
+        connection.ready(password).await?;
+
+        Ok(connection)
+    }
+}
@@ -0,0 +1,77 @@
+use std::{fmt, io, sync::Arc};
+
+use bytes::Bytes;
+
+use crate::Charset;
+
+type TransformFn = dyn Fn(Vec<u8>) -> io::Result<Vec<u8>> + Send + Sync;
+
+/// Rewrites the bytes of a command's response before they are decoded with
+/// the connection's [`Charset`](crate::Charset), for servers that encode
+/// their output.
+///
+/// The transform receives the payloads of the whole response joined
+/// together, and its output is returned as a single payload.
+#[derive(Clone)]
+pub struct ResponseTransform {
+    transform: Arc<TransformFn>,
+}
+
+impl ResponseTransform {
+    /// Creates a transform from a function over the response bytes.
+    pub fn new<F>(transform: F) -> Self
+    where
+        F: Fn(Vec<u8>) -> io::Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        ResponseTransform {
+            transform: Arc::new(transform),
+        }
+    }
+
+    /// Decodes standard base64, ignoring any whitespace such as line breaks.
+    ///
+    /// This is not part of the RCON protocol; it is only useful for the few
+    /// modded servers that base64-encode their output.
+    #[cfg(feature = "base64")]
+    pub fn base64() -> Self {
+        use base64::Engine;
+
+        ResponseTransform::new(|mut bytes| {
+            bytes.retain(|byte| !byte.is_ascii_whitespace());
+
+            base64::engine::general_purpose::STANDARD
+                .decode(bytes)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+        })
+    }
+
+    pub(crate) fn apply(&self, bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+        (self.transform)(bytes)
+    }
+}
+
+/// Decodes `payloads` with `charset`, or their concatenation passed through
+/// `transform` if there is one.
+pub(crate) fn decode_payloads(
+    transform: Option<&ResponseTransform>,
+    charset: Charset,
+    payloads: Vec<Bytes>,
+) -> crate::Result<Vec<String>> {
+    match transform {
+        Some(transform) => {
+            let bytes = transform.apply(payloads.concat())?;
+
+            Ok(vec![charset.decode(&bytes)?])
+        }
+        None => payloads
+            .iter()
+            .map(|payload| Ok(charset.decode(payload)?))
+            .collect(),
+    }
+}
+
+impl fmt::Debug for ResponseTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseTransform").finish_non_exhaustive()
+    }
+}
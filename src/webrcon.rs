@@ -0,0 +1,284 @@
+//! WebRcon, the WebSocket transport Facepunch's Rust servers use instead of
+//! the Source protocol.
+//!
+//! Commands and their responses are JSON objects matched by an
+//! `Identifier`; everything else the server sends is console output,
+//! published to [`WebRconConnection::console`].
+//!
+//! ```no_run
+//! # async fn run() -> specul::Result<()> {
+//! use specul::webrcon::WebRconConnection;
+//!
+//! let connection = WebRconConnection::connect("127.0.0.1:28016", "password").await?;
+//! let mut console = connection.console();
+//!
+//! println!("{:?}", connection.execute_command("status").await?);
+//!
+//! while let Ok(output) = console.recv().await {
+//!     println!("{}", output.message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    net::TcpStream,
+    sync::{broadcast, oneshot},
+    task::JoinHandle,
+};
+use tokio_tungstenite::{
+    tungstenite::{self, Message},
+    MaybeTlsStream, WebSocketStream,
+};
+
+use crate::{url, Error, Result};
+
+/// The WebSocket a [`WebRconConnection`] runs over.
+pub type WebSocket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// The kind of a console message, as the server labels it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MessageType {
+    #[default]
+    Generic,
+    Warning,
+    Error,
+    Chat,
+    Report,
+    /// A type this crate does not know about.
+    #[serde(other)]
+    Unknown,
+}
+
+/// A message from the server that does not answer a command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConsoleMessage {
+    pub message: String,
+    pub message_type: MessageType,
+    /// The identifier the server sent, usually 0 or -1.
+    pub identifier: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Request<'a> {
+    identifier: i32,
+    message: &'a str,
+    name: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Response {
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    identifier: i32,
+    #[serde(rename = "Type", default)]
+    message_type: MessageType,
+}
+
+/// State shared with the reading task.
+#[derive(Debug, Default)]
+struct Commands {
+    next_identifier: i32,
+    pending: HashMap<i32, oneshot::Sender<Result<Vec<String>>>>,
+    closed: bool,
+}
+
+impl Commands {
+    /// Returns a positive identifier, so responses cannot be confused with
+    /// console output.
+    fn next_identifier(&mut self) -> i32 {
+        self.next_identifier = self.next_identifier.checked_add(1).unwrap_or(1);
+        self.next_identifier
+    }
+}
+
+/// A connection to a WebRcon server.
+///
+/// Incoming messages are read on a background task, so commands may be
+/// executed from several tasks at once. The task stops when the connection
+/// is dropped.
+#[derive(Debug)]
+pub struct WebRconConnection {
+    sink: tokio::sync::Mutex<SplitSink<WebSocket, Message>>,
+    commands: Arc<Mutex<Commands>>,
+    console: broadcast::Sender<ConsoleMessage>,
+    timeout: Duration,
+    task: JoinHandle<()>,
+}
+
+impl WebRconConnection {
+    /// Connects to `ws://<addr>/<password>`, which is how WebRcon
+    /// authenticates. The password is percent-encoded, so characters such as
+    /// `/` and `?` stay part of it.
+    ///
+    /// Servers refuse the WebSocket handshake for a wrong password, which
+    /// fails with [`Error::Authentication`].
+    pub async fn connect(addr: &str, password: &str) -> Result<Self> {
+        let url = format!("ws://{}/{}", addr, url::percent_encode(password));
+
+        match tokio_tungstenite::connect_async(url).await {
+            Ok((stream, _)) => Ok(Self::from_stream(stream)),
+            Err(tungstenite::Error::Http(_)) => Err(Error::Authentication),
+            Err(error) => Err(ws_error(error)),
+        }
+    }
+
+    /// Wraps an established WebSocket, such as one connected over TLS.
+    pub fn from_stream(stream: WebSocket) -> Self {
+        let (sink, stream) = stream.split();
+        let commands = Arc::new(Mutex::new(Commands::default()));
+        let console = broadcast::channel(64).0;
+
+        let task = tokio::spawn(receive(stream, commands.clone(), console.clone()));
+
+        WebRconConnection {
+            sink: tokio::sync::Mutex::new(sink),
+            commands,
+            console,
+            timeout: Duration::from_secs(10),
+            task,
+        }
+    }
+
+    /// Sets how long a command may wait for its response before failing
+    /// with [`Error::Timeout`]. Defaults to 10 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Executes a command on the server.
+    ///
+    /// WebRcon answers in one message, so the response has one element.
+    /// Fails with [`Error::ConnectionClosed`] once the server has closed the
+    /// connection.
+    pub async fn execute_command(&self, command: &str) -> Result<Vec<String>> {
+        let (reply, response) = oneshot::channel();
+
+        let identifier = {
+            let mut commands = self.commands.lock().unwrap();
+
+            if commands.closed {
+                return Err(Error::ConnectionClosed);
+            }
+
+            let identifier = commands.next_identifier();
+            commands.pending.insert(identifier, reply);
+            identifier
+        };
+
+        let request = serde_json::to_string(&Request {
+            identifier,
+            message: command,
+            name: "WebRcon",
+        })
+        .map_err(io::Error::from)?;
+
+        let result = async {
+            self.sink
+                .lock()
+                .await
+                .send(Message::text(request))
+                .await
+                .map_err(ws_error)?;
+
+            response.await.unwrap_or(Err(Error::ConnectionClosed))
+        };
+
+        let result = tokio::time::timeout(self.timeout, result)
+            .await
+            .unwrap_or(Err(Error::Timeout));
+
+        if result.is_err() {
+            self.commands.lock().unwrap().pending.remove(&identifier);
+        }
+
+        result
+    }
+
+    /// Subscribes to console output, chat and other messages that do not
+    /// answer a command.
+    pub fn console(&self) -> broadcast::Receiver<ConsoleMessage> {
+        self.console.subscribe()
+    }
+
+    /// Closes the WebSocket.
+    pub async fn close(&self) -> Result<()> {
+        self.sink.lock().await.close().await.map_err(ws_error)
+    }
+}
+
+impl Drop for WebRconConnection {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Routes every message from the server until the connection closes.
+async fn receive(
+    mut stream: SplitStream<WebSocket>,
+    commands: Arc<Mutex<Commands>>,
+    console: broadcast::Sender<ConsoleMessage>,
+) {
+    while let Some(Ok(message)) = stream.next().await {
+        let response: Response = match message {
+            Message::Text(text) => match serde_json::from_str(&text) {
+                Ok(response) => response,
+                Err(_) => continue,
+            },
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let reply = commands
+            .lock()
+            .unwrap()
+            .pending
+            .remove(&response.identifier);
+
+        match reply {
+            Some(reply) => {
+                let _ = reply.send(Ok(vec![response.message]));
+            }
+            None => {
+                let _ = console.send(ConsoleMessage {
+                    message: response.message,
+                    message_type: response.message_type,
+                    identifier: response.identifier,
+                });
+            }
+        }
+    }
+
+    let mut commands = commands.lock().unwrap();
+    commands.closed = true;
+
+    for (_, reply) in commands.pending.drain() {
+        let _ = reply.send(Err(Error::ConnectionClosed));
+    }
+}
+
+fn ws_error(error: tungstenite::Error) -> Error {
+    match error {
+        tungstenite::Error::Io(error) => Error::Io(error),
+        tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed => {
+            Error::ConnectionClosed
+        }
+        error => Error::Io(io::Error::other(error)),
+    }
+}
@@ -0,0 +1,262 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{read_typed, write_typed};
+use specul::{ConnectionBuilder, Error, ErrorKind, State};
+use tokio::io::duplex;
+
+const RESPONSE_VALUE: i32 = 0;
+const AUTH_RESPONSE: i32 = 2;
+
+#[tokio::test]
+async fn skips_mirror_packets_before_auth_response() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, _, _) = read_typed(&mut server).await;
+        for _ in 0..3 {
+            write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+        }
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_auth_packets(4)
+        .build()
+        .unwrap();
+
+    connection.authenticate("password").await.unwrap();
+    assert_eq!(connection.state(), State::Authenticated);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_auth_packets() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, _, _) = read_typed(&mut server).await;
+        loop {
+            write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+        }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_auth_packets(4)
+        .build()
+        .unwrap();
+
+    let result = connection.authenticate("password").await;
+    assert!(matches!(result, Err(Error::Protocol(_))));
+    assert_eq!(connection.state(), State::Connected);
+}
+
+#[tokio::test]
+async fn strict_auth_rejects_unknown_packet_types() {
+    for strict in [false, true] {
+        let (client, mut server) = duplex(4096);
+
+        tokio::spawn(async move {
+            let (id, _, _) = read_typed(&mut server).await;
+            // A first packet of an unknown type is not taken for RCON at all.
+            write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+            write_typed(&mut server, id, 7, "").await;
+            write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+            server
+        });
+
+        let mut connection = ConnectionBuilder::default()
+            .io(client)
+            .strict_auth(strict)
+            .build()
+            .unwrap();
+
+        match connection.authenticate("password").await {
+            Err(Error::UnexpectedPacketType(7)) if strict => {}
+            Ok(()) if !strict => assert_eq!(connection.state(), State::Authenticated),
+            result => panic!("strict_auth {}: {:?}", strict, result),
+        }
+    }
+}
+
+#[tokio::test]
+async fn returns_io_errors_while_waiting_for_the_auth_response() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, _, _) = read_typed(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let result = connection.authenticate("password").await;
+    assert!(matches!(result, Err(Error::Io(_))));
+}
+
+#[tokio::test]
+async fn handshake_detects_mirrored_probe() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (auth_id, _, _) = read_typed(&mut server).await;
+        let (probe_id, _, _) = read_typed(&mut server).await;
+        write_typed(&mut server, auth_id, RESPONSE_VALUE, "").await;
+        write_typed(&mut server, auth_id, AUTH_RESPONSE, "").await;
+        write_typed(&mut server, probe_id, RESPONSE_VALUE, "").await;
+        write_typed(
+            &mut server,
+            probe_id,
+            RESPONSE_VALUE,
+            "\u{0}\u{0}\u{0}\u{1}",
+        )
+        .await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let capabilities = connection.handshake("password").await.unwrap();
+    assert!(capabilities.multi_response);
+    assert_eq!(capabilities.max_fragment, 0);
+    assert_eq!(connection.state(), State::Authenticated);
+
+    drop(server);
+}
+
+#[tokio::test]
+async fn authenticating_twice_fails_without_sending() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, _, _) = read_typed(&mut server).await;
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+
+        // The next packet is the command, not a second authentication.
+        let (id, packet_type, command) = read_typed(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "ok").await;
+        (packet_type, command)
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    connection.authenticate("password").await.unwrap();
+    assert!(connection.is_authenticated());
+    assert!(matches!(
+        connection.authenticate("password").await,
+        Err(Error::AlreadyAuthenticated)
+    ));
+    assert!(matches!(
+        connection.handshake("password").await,
+        Err(Error::AlreadyAuthenticated)
+    ));
+
+    assert_eq!(connection.execute_command("status").await.unwrap(), ["ok"]);
+    assert_eq!(server.await.unwrap(), (2, "status".to_string()));
+}
+
+#[tokio::test]
+async fn commands_fail_before_authenticating_when_required() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, packet_type, _) = read_typed(&mut server).await;
+        assert_eq!(packet_type, 3);
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+
+        let (id, _, command) = read_typed(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "ok").await;
+        command
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .require_authentication(true)
+        .build()
+        .unwrap();
+
+    assert!(!connection.is_authenticated());
+    let error = connection.execute_command("status").await.unwrap_err();
+    assert!(matches!(error, Error::NotAuthenticated));
+    assert_eq!(error.kind(), ErrorKind::Auth);
+
+    connection.authenticate("password").await.unwrap();
+    assert_eq!(connection.execute_command("status").await.unwrap(), ["ok"]);
+    assert_eq!(server.await.unwrap(), "status");
+}
+
+#[tokio::test]
+async fn split_senders_require_authentication_too() {
+    let (client, _server) = duplex(4096);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .require_authentication(true)
+        .build()
+        .unwrap();
+
+    let (mut sender, _receiver) = connection.split();
+    assert!(matches!(
+        sender.send_command("status").await,
+        Err(Error::NotAuthenticated)
+    ));
+}
+
+#[tokio::test]
+async fn probes_a_server_that_needs_a_password() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        read_typed(&mut server).await;
+        write_typed(&mut server, -1, RESPONSE_VALUE, "").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert!(connection.probe_auth_required().await.unwrap());
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn probes_a_server_that_drops_or_ignores_unauthenticated_commands() {
+    let (client, mut server) = duplex(4096);
+    tokio::spawn(async move {
+        read_typed(&mut server).await;
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    assert!(connection.probe_auth_required().await.unwrap());
+
+    let (client, mut server) = duplex(4096);
+    let server = tokio::spawn(async move {
+        read_typed(&mut server).await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .drain_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+    assert!(connection.probe_auth_required().await.unwrap());
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn probes_a_server_without_a_password() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, _, _) = read_typed(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert!(!connection.probe_auth_required().await.unwrap());
+    let _server = server.await.unwrap();
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, Error};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn writes_every_command_before_reading_and_matches_by_id() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for _ in 0..3 {
+            requests.push(read_packet(&mut server).await);
+        }
+
+        for (id, command) in requests.into_iter().rev() {
+            write_packet(&mut server, id, &format!("ok {}", command)).await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_prefix("sm_")
+        .build()
+        .unwrap();
+
+    let responses = connection
+        .execute_commands(["whitelist a", "whitelist b", "whitelist c"])
+        .await
+        .unwrap();
+
+    assert_eq!(
+        responses,
+        vec![
+            vec!["ok sm_whitelist a".to_string()],
+            vec!["ok sm_whitelist b".to_string()],
+            vec!["ok sm_whitelist c".to_string()],
+        ]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn collects_multi_packet_responses_in_a_batch() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for _ in 0..4 {
+            requests.push(read_packet(&mut server).await.0);
+        }
+
+        for pair in requests.chunks(2) {
+            let (id, sentinel) = (pair[0], pair[1]);
+            write_packet(&mut server, id, &format!("{} one", id)).await;
+            write_packet(&mut server, id, &format!("{} two", id)).await;
+            write_packet(&mut server, sentinel, "").await;
+            write_packet(&mut server, sentinel, "\0\u{1}\0\0").await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+
+    let responses = connection.execute_commands(["a", "b"]).await.unwrap();
+
+    assert_eq!(
+        responses,
+        vec![
+            vec!["0 one".to_string(), "0 two".to_string()],
+            vec!["2 one".to_string(), "2 two".to_string()],
+        ]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn sends_nothing_if_a_command_is_too_long() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(4)
+        .build()
+        .unwrap();
+
+    let result = connection.execute_commands(["ok", "too long"]).await;
+
+    assert!(matches!(result, Err(Error::PayloadSize)));
+    assert_eq!(connection.stats().packets_sent, 0);
+}
+
+#[tokio::test]
+async fn filtered_commands_keep_the_passing_responses_in_order() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..4 {
+            let (id, command) = read_packet(&mut server).await;
+            // Settings are acknowledged with an empty response.
+            let response = match command.starts_with("sv_") {
+                true => String::new(),
+                false => format!("{} done", command),
+            };
+            write_packet(&mut server, id, &response).await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let kept = connection
+        .execute_commands_filtered(
+            ["status", "sv_cheats 0", "users", "sv_gravity 800"],
+            |response| !response.trim().is_empty(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        kept,
+        [
+            ("status".to_string(), "status done".to_string()),
+            ("users".to_string(), "users done".to_string()),
+        ]
+    );
+    let _server = server.await.unwrap();
+}
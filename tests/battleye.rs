@@ -0,0 +1,244 @@
+#![cfg(feature = "battleye")]
+
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use specul::{
+    battleye::{BeConnection, BeConnectionBuilder},
+    Error,
+};
+use tokio::net::UdpSocket;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn packet(body: &[u8]) -> Vec<u8> {
+    let mut checked = vec![0xFF];
+    checked.extend_from_slice(body);
+
+    let mut packet = b"BE".to_vec();
+    packet.extend_from_slice(&crc32(&checked).to_le_bytes());
+    packet.extend_from_slice(&checked);
+    packet
+}
+
+/// Receives a packet, checks its checksum and returns the bytes after 0xFF.
+async fn receive(server: &UdpSocket) -> (Vec<u8>, SocketAddr) {
+    let mut buffer = vec![0; 2048];
+    let (read, from) = server.recv_from(&mut buffer).await.unwrap();
+    let datagram = &buffer[..read];
+
+    assert_eq!(&datagram[..2], b"BE");
+    assert_eq!(
+        u32::from_le_bytes(datagram[2..6].try_into().unwrap()),
+        crc32(&datagram[6..])
+    );
+    assert_eq!(datagram[6], 0xFF);
+    (datagram[7..].to_vec(), from)
+}
+
+async fn client(server: &UdpSocket) -> BeConnectionBuilder {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket.connect(server.local_addr().unwrap()).await.unwrap();
+    BeConnectionBuilder::default().socket(socket)
+}
+
+#[tokio::test]
+async fn logs_in_and_reassembles_split_responses() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = server.local_addr().unwrap();
+
+    let task = tokio::spawn(async move {
+        let (login, from) = receive(&server).await;
+        assert_eq!(login, b"\x00password");
+        server.send_to(&packet(b"\x00\x01"), from).await.unwrap();
+
+        let (command, _) = receive(&server).await;
+        assert_eq!(&command[2..], b"players");
+        let sequence = command[1];
+
+        // Parts may arrive out of order.
+        for (index, part) in [(1u8, "two"), (0, "one ")] {
+            let mut body = vec![0x01, sequence, 0x00, 2, index];
+            body.extend_from_slice(part.as_bytes());
+            server.send_to(&packet(&body), from).await.unwrap();
+        }
+    });
+
+    let connection = BeConnection::connect(addr, "password").await.unwrap();
+    let response = connection.execute_command("players").await.unwrap();
+
+    assert_eq!(response, "one two");
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_a_wrong_password() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut connection = client(&server).await.build().unwrap();
+
+    let task = tokio::spawn(async move {
+        let (_, from) = receive(&server).await;
+        server.send_to(&packet(b"\x00\x00"), from).await.unwrap();
+    });
+
+    let result = connection.authenticate("wrong").await;
+
+    assert!(matches!(result, Err(Error::Authentication)));
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn acknowledges_and_publishes_server_messages_once() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut connection = client(&server).await.build().unwrap();
+
+    let task = tokio::spawn(async move {
+        let (_, from) = receive(&server).await;
+        server.send_to(&packet(b"\x00\x01"), from).await.unwrap();
+
+        // Sent twice, as if the first acknowledgement was lost.
+        for _ in 0..2 {
+            server
+                .send_to(&packet(b"\x02\x00(Global) alice: hi"), from)
+                .await
+                .unwrap();
+            let (ack, _) = receive(&server).await;
+            assert_eq!(ack, b"\x02\x00");
+        }
+        server
+            .send_to(&packet(b"\x02\x01(Global) bob: hey"), from)
+            .await
+            .unwrap();
+        let (ack, _) = receive(&server).await;
+        assert_eq!(ack, b"\x02\x01");
+    });
+
+    connection.authenticate("password").await.unwrap();
+    let mut messages = connection.messages();
+
+    assert_eq!(messages.recv().await.unwrap(), "(Global) alice: hi");
+    assert_eq!(messages.recv().await.unwrap(), "(Global) bob: hey");
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn sends_keepalives_while_idle() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut connection = client(&server)
+        .await
+        .keepalive_interval(Duration::from_millis(20))
+        .build()
+        .unwrap();
+
+    let task = tokio::spawn(async move {
+        let (_, from) = receive(&server).await;
+        server.send_to(&packet(b"\x00\x01"), from).await.unwrap();
+
+        for expected in 0..2u8 {
+            let (keepalive, _) = receive(&server).await;
+            assert_eq!(keepalive, [0x01, expected]);
+        }
+    });
+
+    connection.authenticate("password").await.unwrap();
+    task.await.unwrap();
+}
+
+#[tokio::test]
+async fn sequence_numbers_in_use_are_skipped_when_they_wrap() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut connection = client(&server).await.build().unwrap();
+
+    let task = tokio::spawn(async move {
+        let (_, from) = receive(&server).await;
+        server.send_to(&packet(b"\x00\x01"), from).await.unwrap();
+
+        // `slow` is answered after the others have used every other number.
+        let (slow, _) = receive(&server).await;
+        let mut sequences = Vec::new();
+        for _ in 0..256 {
+            let (command, _) = receive(&server).await;
+            sequences.push(command[1]);
+            server
+                .send_to(&packet(&[0x01, command[1], b'o', b'k']), from)
+                .await
+                .unwrap();
+        }
+        server
+            .send_to(&packet(&[0x01, slow[1], b'l', b'a', b't', b'e']), from)
+            .await
+            .unwrap();
+
+        (slow[1], sequences)
+    });
+
+    connection.authenticate("password").await.unwrap();
+    let connection = Arc::new(connection);
+    let slow = tokio::spawn({
+        let connection = connection.clone();
+        async move { connection.execute_command("slow").await }
+    });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    for _ in 0..256 {
+        assert_eq!(connection.execute_command("fast").await.unwrap(), "ok");
+    }
+
+    assert_eq!(slow.await.unwrap().unwrap(), "late");
+    let (slow, sequences) = task.await.unwrap();
+    assert!(!sequences.contains(&slow));
+}
+
+#[tokio::test]
+async fn fails_when_every_sequence_number_is_in_use() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let mut connection = client(&server).await.build().unwrap();
+
+    let task = tokio::spawn(async move {
+        let (_, from) = receive(&server).await;
+        server.send_to(&packet(b"\x00\x01"), from).await.unwrap();
+
+        for _ in 0..256 {
+            receive(&server).await;
+        }
+        server
+    });
+
+    connection.authenticate("password").await.unwrap();
+    let connection = Arc::new(connection);
+    let waiting: Vec<_> = (0..256)
+        .map(|_| {
+            let connection = connection.clone();
+            tokio::spawn(async move { connection.execute_command("wait").await })
+        })
+        .collect();
+
+    let _server = task.await.unwrap();
+    let result = connection.execute_command("one too many").await;
+
+    assert!(matches!(result, Err(Error::Protocol(_))), "{:?}", result);
+    for command in waiting {
+        command.abort();
+    }
+}
+
+#[test]
+fn rejects_keepalive_intervals_the_server_would_time_out() {
+    let result = BeConnectionBuilder::default()
+        .keepalive_interval(Duration::from_secs(45))
+        .build();
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,62 @@
+#![cfg(all(feature = "blocking", feature = "testing"))]
+
+use specul::{
+    blocking::Connection,
+    testing::{MockServer, Reply},
+    Error, State,
+};
+use tokio::runtime::Runtime;
+
+/// Starts a mock server on a multi-threaded runtime, which keeps serving
+/// while the test thread blocks.
+fn mock(runtime: &Runtime) -> MockServer {
+    runtime
+        .block_on(
+            MockServer::builder("password")
+                .respond("status", Reply::text("hostname: blocking"))
+                .respond("list", Reply::text("There are 0 players online"))
+                .start(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn executes_commands_without_a_runtime() {
+    let runtime = Runtime::new().unwrap();
+    let server = mock(&runtime);
+
+    let mut connection = Connection::connect(server.addr(), "password").unwrap();
+
+    assert_eq!(connection.state(), State::Authenticated);
+    assert_eq!(connection.peer_addr(), Some(server.addr()));
+    assert_eq!(
+        connection.execute_command("status").unwrap(),
+        ["hostname: blocking"]
+    );
+    assert_eq!(
+        connection.execute("list").unwrap(),
+        "There are 0 players online"
+    );
+
+    connection.close().unwrap();
+    assert_eq!(connection.state(), State::Closed);
+}
+
+#[test]
+fn authenticates_separately() {
+    let runtime = Runtime::new().unwrap();
+    let server = mock(&runtime);
+
+    let mut connection = Connection::open(server.addr()).unwrap();
+    assert!(matches!(
+        connection.authenticate("wrong"),
+        Err(Error::Authentication)
+    ));
+
+    let url = format!("rcon://:password@{}", server.addr());
+    let mut connection = Connection::connect_url(&url).unwrap();
+    assert_eq!(
+        connection.execute_command("status").unwrap(),
+        ["hostname: blocking"]
+    );
+}
@@ -0,0 +1,168 @@
+mod common;
+
+use common::{read_bytes, write_typed};
+use specul::{Charset, ConnectionBuilder, Error};
+use tokio::io::{duplex, DuplexStream};
+
+const RESPONSE_VALUE: i32 = 0;
+
+/// Answers one command with `response`, returning the command's payload.
+fn serve(mut server: DuplexStream, response: &'static [u8]) -> tokio::task::JoinHandle<Vec<u8>> {
+    tokio::spawn(async move {
+        let (id, _, command) = read_bytes(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, response).await;
+        command
+    })
+}
+
+#[tokio::test]
+async fn fails_invalid_utf8_by_default() {
+    let (client, server) = duplex(4096);
+    serve(server, b"caf\xe9");
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    match connection.execute_command("status").await {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+        other => panic!("expected an InvalidData error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn replaces_invalid_utf8_when_lossy() {
+    let (client, server) = duplex(4096);
+    serve(server, b"caf\xe9");
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .charset(Charset::Utf8Lossy)
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("status").await.unwrap();
+    assert_eq!(response, vec!["caf\u{fffd}"]);
+}
+
+#[tokio::test]
+async fn transcodes_latin1_both_ways() {
+    let (client, server) = duplex(4096);
+    let command = serve(server, b"caf\xe9");
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .charset(Charset::Latin1)
+        .build()
+        .unwrap();
+
+    let response = connection
+        .execute_command("say d\u{e9}j\u{e0}")
+        .await
+        .unwrap();
+    assert_eq!(response, vec!["caf\u{e9}"]);
+    assert_eq!(command.await.unwrap(), b"say d\xe9j\xe0");
+}
+
+#[tokio::test]
+async fn rejects_characters_outside_latin1() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .charset(Charset::Latin1)
+        .build()
+        .unwrap();
+
+    match connection.execute_command("say \u{20ac}").await {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput),
+        other => panic!("expected an InvalidInput error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn executes_raw_bytes() {
+    let (client, server) = duplex(4096);
+    let command = serve(server, b"\x00\x01\xff binary");
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let response = connection.execute_command_raw(b"dump \xfe").await.unwrap();
+    assert_eq!(response, b"\x00\x01\xff binary");
+    assert_eq!(command.await.unwrap(), b"dump \xfe");
+}
+
+#[tokio::test]
+async fn decodes_text_again_after_a_raw_command() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        for response in [&b"\xe9"[..], "\u{e9}".as_bytes()] {
+            let (id, _, _) = read_bytes(&mut server).await;
+            write_typed(&mut server, id, RESPONSE_VALUE, response).await;
+        }
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(connection.execute_command_raw(b"a").await.unwrap(), b"\xe9");
+    assert_eq!(
+        connection.execute_command("b").await.unwrap(),
+        vec!["\u{e9}"]
+    );
+}
+
+#[cfg(feature = "encoding")]
+#[tokio::test]
+async fn transcodes_with_encoding_rs() {
+    let (client, server) = duplex(4096);
+    let command = serve(server, b"\x80 5");
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .charset(Charset::Encoding(encoding_rs::WINDOWS_1252))
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("price \u{20ac}").await.unwrap();
+    assert_eq!(response, vec!["\u{20ac} 5"]);
+    assert_eq!(command.await.unwrap(), b"price \x80");
+}
+
+#[tokio::test]
+async fn limits_raw_commands_by_their_bytes() {
+    let (client, server) = duplex(4096);
+    let command = serve(server, b"ok");
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(4usize)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection
+            .execute_command_raw(b"\xff\xff\xff\xff\xff")
+            .await,
+        Err(Error::PayloadSize)
+    ));
+    assert_eq!(
+        connection
+            .execute_command_raw(b"\xff\xff\xff\xff")
+            .await
+            .unwrap(),
+        b"ok"
+    );
+    assert_eq!(command.await.unwrap(), b"\xff\xff\xff\xff");
+}
+
+#[test]
+fn reports_the_charset_in_the_wire_config() {
+    let (client, _server) = duplex(4096);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .charset(Charset::Latin1)
+        .build()
+        .unwrap();
+
+    assert_eq!(connection.wire_config().encoding, "iso-8859-1");
+}
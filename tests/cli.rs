@@ -0,0 +1,570 @@
+#![cfg(all(feature = "cli", feature = "testing"))]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+use specul::testing::{MockServer, Reply};
+
+async fn rcon(args: &[&str], password: Option<&str>) -> Output {
+    rcon_with_input(args, password, &[], "").await
+}
+
+/// Runs `rcon` with `input` on stdin, and without a history file unless
+/// `envs` sets one.
+async fn rcon_with_input(
+    args: &[&str],
+    password: Option<&str>,
+    envs: &[(&str, &str)],
+    input: &str,
+) -> Output {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_rcon"));
+    command
+        .args(args)
+        .env_remove("RCON_HOST")
+        .env_remove("RCON_HISTORY")
+        .env_remove("HOME")
+        .envs(envs.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+
+    match password {
+        Some(password) => command.env("RCON_PASSWORD", password),
+        None => command.env_remove("RCON_PASSWORD"),
+    };
+
+    let input = input.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut child = command.spawn().unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(input.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    })
+    .await
+    .unwrap()
+}
+
+async fn mock() -> MockServer {
+    MockServer::builder("hunter2")
+        .respond("status", Reply::text("hostname: test"))
+        .respond("say hi", Reply::text("hi\n"))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn prints_responses() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let output = rcon(&["--host", &addr, "say hi", "status"], Some("hunter2")).await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hi\nhostname: test\n"
+    );
+}
+
+#[tokio::test]
+async fn takes_the_password_as_an_option_or_url() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+    let url = format!("rcon://:hunter2@{}", addr);
+
+    let with_option = rcon(&["-H", &addr, "-p", "hunter2", "status"], None).await;
+    let with_url = rcon(&["--url", &url, "status"], None).await;
+
+    assert!(with_option.status.success());
+    assert!(with_url.status.success());
+}
+
+#[tokio::test]
+async fn fails_on_a_wrong_password() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let output = rcon(&["--host", &addr, "status"], Some("wrong")).await;
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("authentication failed"));
+}
+
+#[tokio::test]
+async fn fails_on_invalid_arguments() {
+    let missing_command = rcon(&["--host", "127.0.0.1:1"], Some("x")).await;
+    let unknown_option = rcon(&["--frobnicate", "status"], Some("x")).await;
+
+    assert_eq!(missing_command.status.code(), Some(2));
+    assert_eq!(unknown_option.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn repl_reads_commands_from_stdin() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+    let history = std::env::temp_dir().join(format!("rcon-history-{}", std::process::id()));
+
+    let output = rcon_with_input(
+        &["--host", &addr, "--game", "source", "repl"],
+        Some("hunter2"),
+        &[("RCON_HISTORY", history.to_str().unwrap())],
+        "status\n:complete sa\n\nsay hi\n:quit\nstatus\n",
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hostname: test\nsay\nhi\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(&history).unwrap(),
+        "#V2\nstatus\nsay hi\n"
+    );
+
+    std::fs::remove_file(history).unwrap();
+}
+
+#[tokio::test]
+async fn repl_recalls_earlier_sessions() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+    let history = std::env::temp_dir().join(format!("rcon-recall-{}", std::process::id()));
+    std::fs::write(&history, "#V2\nstatus\n").unwrap();
+
+    let output = rcon_with_input(
+        &["--host", &addr, "repl"],
+        Some("hunter2"),
+        &[("RCON_HISTORY", history.to_str().unwrap())],
+        "say hi\n:history\n",
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hi\nstatus\nsay hi\n"
+    );
+
+    std::fs::remove_file(history).unwrap();
+}
+
+#[tokio::test]
+async fn prompts_for_the_password() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let output = rcon_with_input(&["--host", &addr, "repl"], None, &[], "hunter2\nstatus\n").await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hostname: test\n"
+    );
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .contains("Password: "));
+}
+
+#[tokio::test]
+async fn connects_with_a_profile() {
+    let mock = mock().await;
+    let config = std::env::temp_dir().join(format!("rcon-servers-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        format!(
+            "# Test servers\n\
+             [local]\n\
+             host = \"{}\"\n\
+             port = {} # the mock's\n\
+             password_env = 'LOCAL_PASSWORD'\n\
+             timeout = 2.5\n\
+             \n\
+             [scripted]\n\
+             host = \"{}\"\n\
+             password_command = \"echo hunter2\"\n\
+             quirks = \"source\"\n",
+            mock.addr().ip(),
+            mock.addr().port(),
+            mock.addr(),
+        ),
+    )
+    .unwrap();
+    let config_path = config.to_str().unwrap();
+
+    let from_env = rcon_with_input(
+        &["--config", config_path, "--profile", "local", "status"],
+        None,
+        &[("LOCAL_PASSWORD", "hunter2")],
+        "",
+    )
+    .await;
+    let from_command = rcon(&["-c", config_path, "-P", "scripted", "status"], None).await;
+    let missing = rcon(&["-c", config_path, "-P", "nope", "status"], None).await;
+
+    assert!(from_env.status.success());
+    assert_eq!(
+        String::from_utf8(from_env.stdout).unwrap(),
+        "hostname: test\n"
+    );
+    assert!(from_command.status.success());
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(String::from_utf8(missing.stderr)
+        .unwrap()
+        .contains("no profile nope"));
+
+    std::fs::remove_file(config).unwrap();
+}
+
+#[tokio::test]
+async fn rejects_invalid_profiles() {
+    let config = std::env::temp_dir().join(format!("rcon-invalid-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        "[typo]\n\
+         host = \"127.0.0.1\"\n\
+         prot = 27015\n\
+         \n\
+         [twice]\n\
+         host = \"127.0.0.1\"\n\
+         password = \"hunter2\"\n\
+         password_env = \"RCON_PASSWORD\"\n\
+         \n\
+         [locked]\n\
+         host = \"127.0.0.1\"\n\
+         password_keyring = \"specul-test-missing-entry\"\n",
+    )
+    .unwrap();
+    let config_path = config.to_str().unwrap();
+
+    for (profile, message) in [
+        ("typo", "unknown field `prot`"),
+        ("twice", "more than one password"),
+        (
+            "locked",
+            "cannot read keyring entry specul-test-missing-entry",
+        ),
+    ] {
+        let output = rcon(&["-c", config_path, "-P", profile, "status"], None).await;
+        let stderr = String::from_utf8(output.stderr).unwrap();
+
+        assert_eq!(output.status.code(), Some(1));
+        assert!(stderr.contains(message), "{}", stderr);
+    }
+
+    std::fs::remove_file(config).unwrap();
+}
+
+#[tokio::test]
+async fn prints_json() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let output = rcon(
+        &["--host", &addr, "--output", "json", "status", "say hi"],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<serde_json::Value> = stdout
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["command"], "status");
+    assert_eq!(lines[0]["response"], "hostname: test");
+    assert_eq!(lines[0]["packets"], 1);
+    assert_eq!(lines[0]["server"], addr.as_str());
+    assert!(lines[0]["latency_ms"].as_f64().unwrap() >= 0.0);
+    assert_eq!(lines[1]["response"], "hi\n");
+}
+
+#[tokio::test]
+async fn prints_player_tables() {
+    let mock = MockServer::builder("hunter2")
+        .respond(
+            "status",
+            Reply::text(
+                "hostname: Test\n\
+                 map     : ctf_2fort at: 0 x, 0 y, 0 z\n\
+                 players : 2 humans, 0 bots (24 max)\n\
+                 # userid name uniqueid connected ping loss state adr\n\
+                 #      2 \"Gordon\" [U:1:22202] 00:35 50 0 active 10.0.0.7:27005\n\
+                 #     13 \"Alyx Vance\" [U:1:4242] 1:02:11 120 3 active 10.0.0.8:27005\n",
+            ),
+        )
+        .respond("echo hi", Reply::text("hi"))
+        .start()
+        .await
+        .unwrap();
+    let addr = mock.addr().to_string();
+
+    let output = rcon(
+        &["--host", &addr, "-o", "table", "status", "echo hi"],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hostname: Test\n\
+         map: ctf_2fort\n\
+         players: 2/24 (0 bots)\n\
+         \n\
+         USERID  NAME        STEAMID            CONNECTED  PING  LOSS  ADDRESS\n\
+         2       Gordon      76561197960287930  00:35      50    0     10.0.0.7:27005\n\
+         13      Alyx Vance  76561197960269970  1:02:11    120   3     10.0.0.8:27005\n\
+         hi\n"
+    );
+}
+
+#[tokio::test]
+async fn watches_a_command() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let output = rcon(
+        &["--host", &addr, "watch", "-i", "10ms", "-n", "3", "status"],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hostname: test\nhostname: test\nhostname: test\n"
+    );
+
+    let output = rcon(
+        &[
+            "--host", &addr, "watch", "--diff", "-i", "10ms", "-n", "3", "status",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "hostname: test\n"
+    );
+
+    let output = rcon(&["--host", &addr, "--diff", "status"], Some("hunter2")).await;
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn watch_reconnects_after_a_broken_response() {
+    let mock = MockServer::builder("hunter2")
+        .respond("broken", Reply::Raw(vec![0xff; 16]))
+        .start()
+        .await
+        .unwrap();
+    let addr = mock.addr().to_string();
+
+    let output = rcon(
+        &[
+            "--host", &addr, "-t", "1", "watch", "-i", "10ms", "-n", "2", "broken",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert_eq!(stderr.matches("rcon: broken: ").count(), 2, "{}", stderr);
+    assert!(!stderr.contains("reconnecting"), "{}", stderr);
+}
+
+#[tokio::test]
+async fn broadcasts_to_profiles() {
+    let eu1 = mock().await;
+    let eu2 = MockServer::builder("hunter2")
+        .respond("say hi", Reply::text("hi from eu2"))
+        .start()
+        .await
+        .unwrap();
+    // A port nothing listens on.
+    let down = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let config = std::env::temp_dir().join(format!("rcon-fleet-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        format!(
+            "[eu1]\nhost = \"{}\"\n\n[eu2]\nhost = \"{}\"\n\n[down]\nhost = \"{}\"\n",
+            eu1.addr(),
+            eu2.addr(),
+            down,
+        ),
+    )
+    .unwrap();
+    let config_path = config.to_str().unwrap();
+
+    let up = rcon(
+        &[
+            "-c",
+            config_path,
+            "broadcast",
+            "--profiles",
+            "eu2,eu1",
+            "say hi",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+    let partial = rcon(
+        &[
+            "-c",
+            config_path,
+            "-o",
+            "json",
+            "broadcast",
+            "--profiles",
+            "eu1,down",
+            "say hi",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+    let fail_fast = rcon(
+        &[
+            "-c",
+            config_path,
+            "broadcast",
+            "--fail-fast",
+            "--profiles",
+            "down,eu1",
+            "say hi",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+    let with_host = rcon(
+        &[
+            "--host",
+            "localhost",
+            "broadcast",
+            "--profiles",
+            "eu1",
+            "say hi",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+    std::fs::remove_file(&config).unwrap();
+
+    assert!(up.status.success());
+    assert_eq!(
+        String::from_utf8(up.stdout).unwrap(),
+        "eu2: hi from eu2\neu1: hi\n"
+    );
+
+    assert_eq!(partial.status.code(), Some(1));
+    let lines: Vec<serde_json::Value> = String::from_utf8(partial.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["profile"], "eu1");
+    assert_eq!(lines[0]["response"], "hi\n");
+    assert_eq!(lines[1]["profile"], "down");
+    assert_eq!(lines[1]["ok"], false);
+    assert!(String::from_utf8(partial.stderr)
+        .unwrap()
+        .contains("1 of 2 servers failed"));
+
+    assert_eq!(fail_fast.status.code(), Some(1));
+    assert!(String::from_utf8(fail_fast.stderr)
+        .unwrap()
+        .contains("rcon: down: "));
+
+    assert_eq!(with_host.status.code(), Some(2));
+}
+
+#[tokio::test]
+async fn broadcasts_with_each_profiles_settings() {
+    let plugin = MockServer::builder("hunter2")
+        .respond("sm_say hi", Reply::text("hi from the plugin"))
+        .start()
+        .await
+        .unwrap();
+
+    let config =
+        std::env::temp_dir().join(format!("rcon-fleet-prefix-{}.toml", std::process::id()));
+    std::fs::write(
+        &config,
+        format!(
+            "[plugin]\nhost = \"{}\"\ncommand_prefix = \"sm_\"\n",
+            plugin.addr()
+        ),
+    )
+    .unwrap();
+
+    let output = rcon(
+        &[
+            "-c",
+            config.to_str().unwrap(),
+            "broadcast",
+            "--profiles",
+            "plugin",
+            "say hi",
+        ],
+        Some("hunter2"),
+    )
+    .await;
+    std::fs::remove_file(&config).unwrap();
+
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "plugin: hi from the plugin\n"
+    );
+}
+
+#[tokio::test]
+async fn executes_scripts() {
+    let mock = mock().await;
+    let addr = mock.addr().to_string();
+
+    let from_stdin = rcon_with_input(
+        &["--host", &addr, "exec", "--delay", "10ms", "-"],
+        Some("hunter2"),
+        &[],
+        "# Greet everyone\nsay hi\n\n// and check\nstatus\n",
+    )
+    .await;
+    let missing = rcon(
+        &["--host", &addr, "exec", "/nonexistent/script.cfg"],
+        Some("hunter2"),
+    )
+    .await;
+
+    assert!(from_stdin.status.success());
+    assert_eq!(
+        String::from_utf8(from_stdin.stdout).unwrap(),
+        "hi\nhostname: test\n"
+    );
+    assert_eq!(missing.status.code(), Some(1));
+    assert!(String::from_utf8(missing.stderr)
+        .unwrap()
+        .contains("/nonexistent/script.cfg: "));
+}
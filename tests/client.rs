@@ -0,0 +1,212 @@
+#![cfg(feature = "client")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::{read_packet, write_typed};
+use specul::{ConnectionBuilder, Error, Event, IdStrategy};
+use tokio::io::{duplex, AsyncReadExt};
+
+const RESPONSE_VALUE: i32 = 0;
+
+#[tokio::test]
+async fn matches_out_of_order_responses_to_their_commands() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let first = read_packet(&mut server).await;
+        let second = read_packet(&mut server).await;
+
+        for (id, command) in [second, first] {
+            write_typed(&mut server, id, RESPONSE_VALUE, &format!("ran {}", command)).await;
+        }
+        server
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    let other = client.clone();
+    let (status, users) = tokio::join!(
+        client.execute_command("status"),
+        other.execute_command("users")
+    );
+
+    assert_eq!(status.unwrap(), vec!["ran status".to_string()]);
+    assert_eq!(users.unwrap(), vec!["ran users".to_string()]);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn collects_multi_packet_responses_up_to_the_sentinel() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        let (sentinel, _) = read_packet(&mut server).await;
+
+        write_typed(&mut server, id, RESPONSE_VALUE, "a").await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "b").await;
+        write_typed(&mut server, sentinel, RESPONSE_VALUE, "").await;
+        write_typed(&mut server, sentinel, RESPONSE_VALUE, "\0\u{1}\0\0").await;
+        server
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    let response = client.execute_command("cvarlist").await.unwrap();
+
+    assert_eq!(response, vec!["a".to_string(), "b".to_string()]);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn fails_pending_commands_when_the_connection_closes() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        read_packet(&mut server).await;
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    let result = client.execute_command("status").await;
+
+    assert!(matches!(result, Err(Error::ConnectionClosed)));
+}
+
+#[tokio::test]
+async fn sends_an_empty_keepalive_when_idle() {
+    let (client, mut server) = duplex(4096);
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .keepalive_interval(Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+    let mut events = client.monitor().events();
+
+    let (length, packet_type) = {
+        let length = server.read_i32_le().await.unwrap();
+        let _id = server.read_i32_le().await.unwrap();
+        let packet_type = server.read_i32_le().await.unwrap();
+        let mut rest = vec![0; length as usize - 8];
+        server.read_exact(&mut rest).await.unwrap();
+        (length, packet_type)
+    };
+
+    assert_eq!(length, 10);
+    assert_eq!(packet_type, RESPONSE_VALUE);
+
+    drop(server);
+
+    assert!(matches!(events.recv().await.unwrap(), Event::Disconnected));
+    assert!(matches!(
+        client.execute_command("status").await,
+        Err(Error::ConnectionClosed)
+    ));
+}
+
+#[tokio::test]
+async fn sends_the_keepalive_command_and_drops_its_response() {
+    let (client, mut server) = duplex(4096);
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .keepalive_interval(Duration::from_millis(50))
+        .keepalive_command("echo")
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+    let mut events = client.monitor().events();
+
+    let (id, command) = read_packet(&mut server).await;
+    assert_eq!(command, "echo");
+    write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+
+    // Further keep-alives may arrive alongside the command.
+    tokio::spawn(async move {
+        loop {
+            let (id, command) = read_packet(&mut server).await;
+            write_typed(&mut server, id, RESPONSE_VALUE, &format!("ran {}", command)).await;
+        }
+    });
+
+    assert_eq!(
+        client.execute_command("status").await.unwrap(),
+        vec!["ran status".to_string()]
+    );
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn refuses_a_constant_packet_id() {
+    let (client, _server) = duplex(64);
+
+    let result = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Constant(42))
+        .build()
+        .unwrap()
+        .client();
+
+    assert!(matches!(result, Err(Error::Unsupported(_))));
+}
+
+#[tokio::test]
+async fn drops_the_late_response_to_a_command_that_timed_out() {
+    let (client, mut server) = duplex(4096);
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .command_deadline(Duration::from_millis(50))
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+    let mut events = client.monitor().events();
+
+    let (slow, _) = tokio::join!(client.execute_command("slow"), async {
+        read_packet(&mut server).await
+    });
+    assert!(matches!(slow, Err(Error::Timeout)));
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "status");
+
+        // The late response comes first, as it would from the server.
+        write_typed(&mut server, id - 1, RESPONSE_VALUE, "ran slow").await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "ran status").await;
+        server
+    });
+
+    assert_eq!(
+        client.execute_command("status").await.unwrap(),
+        vec!["ran status".to_string()]
+    );
+    assert!(events.try_recv().is_err());
+
+    let _server = server.await.unwrap();
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, Error, State};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn execute_after_close_fails_cleanly() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    connection.close().await.unwrap();
+    assert_eq!(connection.state(), State::Closed);
+
+    let result = connection.execute_command("status").await;
+    assert!(matches!(result, Err(Error::ConnectionClosed)));
+
+    // Nothing was written after the shutdown.
+    let mut received = Vec::new();
+    server.read_to_end(&mut received).await.unwrap();
+    assert!(received.is_empty());
+}
+
+#[tokio::test]
+async fn close_waits_for_the_server_to_close_its_side() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .close_wait(Duration::from_secs(5))
+        .build()
+        .unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut received = Vec::new();
+        server.read_to_end(&mut received).await.unwrap();
+        server.write_all(b"goodbye").await.unwrap();
+    });
+
+    let started = Instant::now();
+    connection.close().await.unwrap();
+
+    assert!(started.elapsed() < Duration::from_secs(5));
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn close_gives_up_waiting_after_close_wait() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .close_wait(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    connection.close().await.unwrap();
+    assert_eq!(connection.state(), State::Closed);
+}
+
+#[tokio::test]
+async fn close_sends_the_disconnect_command_before_shutting_down() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .disconnect_command("quit")
+        .build()
+        .unwrap();
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        write_packet(&mut server, id, "bye").await;
+
+        // The shutdown follows, with nothing written after the command.
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        (command, rest)
+    });
+
+    connection.close().await.unwrap();
+    assert_eq!(connection.state(), State::Closed);
+
+    let (command, rest) = server.await.unwrap();
+    assert_eq!(command, "quit");
+    assert!(rest.is_empty());
+}
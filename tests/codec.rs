@@ -0,0 +1,88 @@
+#![cfg(feature = "codec")]
+
+use bytes::BytesMut;
+use futures::{SinkExt, StreamExt};
+use specul::{codec::RconCodec, Packet, PacketType};
+use tokio_util::codec::{Decoder, Encoder, Framed};
+
+#[test]
+fn decodes_one_packet_fed_a_byte_at_a_time() {
+    let mut codec = RconCodec::default();
+
+    let mut wire = BytesMut::new();
+    codec
+        .encode(
+            Packet::new(7, PacketType::Response, "hello".to_string()),
+            &mut wire,
+        )
+        .unwrap();
+
+    let mut buffer = BytesMut::new();
+    let mut packets = Vec::new();
+
+    for (i, byte) in wire.iter().enumerate() {
+        buffer.extend_from_slice(&[*byte]);
+
+        if let Some(packet) = codec.decode(&mut buffer).unwrap() {
+            // The terminator is not waited for, but skipped as it arrives.
+            assert_eq!(i, wire.len() - 3, "packet emitted before its payload");
+            packets.push(packet);
+        }
+    }
+
+    assert_eq!(packets.len(), 1);
+    assert_eq!(packets[0].id, 7);
+    assert_eq!(packets[0].payload, "hello");
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn skips_a_terminator_that_arrives_late_but_not_the_next_packet() {
+    let mut codec = RconCodec::default();
+    let mut wire = BytesMut::new();
+    for id in [1, 2] {
+        codec
+            .encode(Packet::new(id, PacketType::Response, "hi"), &mut wire)
+            .unwrap();
+    }
+
+    // The first packet's terminator comes with the second packet.
+    let mut buffer = wire.split_to(14);
+    assert_eq!(codec.decode(&mut buffer).unwrap().unwrap().id, 1);
+    buffer.extend_from_slice(&wire);
+    assert_eq!(codec.decode(&mut buffer).unwrap().unwrap().id, 2);
+    assert!(buffer.is_empty());
+}
+
+#[test]
+fn encodes_into_the_provided_buffer() {
+    let mut codec = RconCodec::default();
+    let mut wire = BytesMut::from(&b"prefix"[..]);
+
+    codec
+        .encode(
+            &Packet::new(1, PacketType::Message, "status".to_string()),
+            &mut wire,
+        )
+        .unwrap();
+
+    assert_eq!(&wire[..6], b"prefix");
+    assert_eq!(&wire[6..10], &16i32.to_le_bytes());
+    assert_eq!(&wire[wire.len() - 2..], &[0, 0]);
+}
+
+#[tokio::test]
+async fn streams_packets_through_framed() {
+    let (client, server) = tokio::io::duplex(4096);
+    let mut client = Framed::new(client, RconCodec::default());
+    let mut server = Framed::new(server, RconCodec::default());
+
+    client
+        .send(Packet::new(3, PacketType::Message, "status".to_string()))
+        .await
+        .unwrap();
+
+    let packet = server.next().await.unwrap().unwrap();
+    assert_eq!(packet.id, 3);
+    assert_eq!(packet.payload, "status");
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::{echo, read_packet, write_packet};
+use specul::{Command, ConnectionBuilder, Error, Result};
+use tokio::io::duplex;
+
+/// `maxplayers`, answered with a bare number.
+struct MaxPlayers {
+    map: Option<&'static str>,
+}
+
+impl Command for MaxPlayers {
+    type Output = u32;
+
+    fn to_command_string(&self) -> String {
+        match self.map {
+            Some(map) => format!("maxplayers {}", map),
+            None => "maxplayers".to_string(),
+        }
+    }
+
+    fn parse_response(response: &str) -> Result<u32> {
+        response
+            .trim()
+            .parse()
+            .map_err(|_| Error::UnexpectedResponse(response.to_string()))
+    }
+}
+
+#[tokio::test]
+async fn runs_typed_commands() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for reply in ["24\n", "lots"] {
+            let (id, command) = read_packet(&mut server).await;
+            assert_eq!(command, "maxplayers de_dust2");
+            write_packet(&mut server, id, reply).await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let command = MaxPlayers {
+        map: Some("de_dust2"),
+    };
+
+    assert_eq!(connection.run(&command).await.unwrap(), 24);
+    assert!(matches!(
+        connection.run(&command).await,
+        Err(Error::UnexpectedResponse(response)) if response == "lots"
+    ));
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn only_prefixed_commands_get_the_command_prefix() {
+    let (client, server) = duplex(4096);
+    let server = echo(server, 2);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_prefix("sm_")
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.execute_command("kick Gordon").await.unwrap(),
+        ["sm_kick Gordon"]
+    );
+    assert_eq!(
+        connection.execute_unprefixed("status").await.unwrap(),
+        ["status"]
+    );
+    assert_eq!(server.await.unwrap(), ["sm_kick Gordon", "status"]);
+}
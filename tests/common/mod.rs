@@ -0,0 +1,68 @@
+//! Helpers for tests that play the server's side of a duplex stream.
+
+#![allow(dead_code)]
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+/// Writes a `SERVERDATA_RESPONSE_VALUE` packet.
+pub async fn write_packet(io: &mut DuplexStream, id: i32, payload: &str) {
+    write_typed(io, id, 0, payload).await;
+}
+
+pub async fn write_typed(
+    io: &mut DuplexStream,
+    id: i32,
+    packet_type: i32,
+    payload: impl AsRef<[u8]>,
+) {
+    let payload = payload.as_ref();
+
+    io.write_i32_le(10 + payload.len() as i32).await.unwrap();
+    io.write_i32_le(id).await.unwrap();
+    io.write_i32_le(packet_type).await.unwrap();
+    io.write_all(payload).await.unwrap();
+    io.write_all(&[0, 0]).await.unwrap();
+}
+
+/// Reads a packet, returning its id, type and payload.
+pub async fn read_bytes(io: &mut DuplexStream) -> (i32, i32, Vec<u8>) {
+    let length = io.read_i32_le().await.unwrap();
+    let id = io.read_i32_le().await.unwrap();
+    let packet_type = io.read_i32_le().await.unwrap();
+    let mut payload = vec![0; length as usize - 8];
+    io.read_exact(&mut payload).await.unwrap();
+    payload.truncate(payload.len() - 2);
+    (id, packet_type, payload)
+}
+
+/// Reads a packet with a UTF-8 payload, returning its id, type and payload.
+pub async fn read_typed(io: &mut DuplexStream) -> (i32, i32, String) {
+    let (id, packet_type, payload) = read_bytes(io).await;
+    (id, packet_type, String::from_utf8(payload).unwrap())
+}
+
+/// Reads a packet with a UTF-8 payload, returning its id and payload.
+pub async fn read_packet(io: &mut DuplexStream) -> (i32, String) {
+    let (id, _, payload) = read_typed(io).await;
+    (id, payload)
+}
+
+pub async fn read_id(io: &mut DuplexStream) -> i32 {
+    read_bytes(io).await.0
+}
+
+/// Answers each of `count` commands with the command itself, returning
+/// them.
+pub fn echo(mut server: DuplexStream, count: usize) -> tokio::task::JoinHandle<Vec<String>> {
+    tokio::spawn(async move {
+        let mut commands = Vec::new();
+
+        for _ in 0..count {
+            let (id, command) = read_packet(&mut server).await;
+            write_packet(&mut server, id, &command).await;
+            commands.push(command);
+        }
+
+        commands
+    })
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "tcp")]
+
+use specul::{Connection, State};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+const RESPONSE_VALUE: i32 = 0;
+const AUTH_RESPONSE: i32 = 2;
+
+async fn read_packet(stream: &mut TcpStream) -> (i32, String) {
+    let length = stream.read_i32_le().await.unwrap();
+    let id = stream.read_i32_le().await.unwrap();
+    let mut rest = vec![0; length as usize - 4];
+    stream.read_exact(&mut rest).await.unwrap();
+
+    (
+        id,
+        String::from_utf8_lossy(&rest[4..rest.len() - 2]).into_owned(),
+    )
+}
+
+async fn write_typed(stream: &mut TcpStream, id: i32, packet_type: i32, payload: &str) {
+    stream
+        .write_i32_le(10 + payload.len() as i32)
+        .await
+        .unwrap();
+    stream.write_i32_le(id).await.unwrap();
+    stream.write_i32_le(packet_type).await.unwrap();
+    stream.write_all(payload.as_bytes()).await.unwrap();
+    stream.write_all(&[0, 0]).await.unwrap();
+}
+
+#[tokio::test]
+async fn connect_and_ready_drains_around_authentication() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        write_typed(&mut stream, 0, RESPONSE_VALUE, "welcome").await;
+
+        let (id, password) = read_packet(&mut stream).await;
+        assert_eq!(password, "password");
+        write_typed(&mut stream, id, RESPONSE_VALUE, "").await;
+        write_typed(&mut stream, id, AUTH_RESPONSE, "").await;
+        write_typed(&mut stream, 0, RESPONSE_VALUE, "message of the day").await;
+
+        let (id, command) = read_packet(&mut stream).await;
+        write_typed(&mut stream, id, RESPONSE_VALUE, &format!("ran {}", command)).await;
+        stream
+    });
+
+    let mut connection = Connection::connect_and_ready(addr, "password")
+        .await
+        .unwrap();
+
+    assert_eq!(connection.state(), State::Authenticated);
+    assert_eq!(connection.peer_addr(), Some(addr));
+    assert!(connection.try_read_buffered_packets().unwrap().is_empty());
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        ["ran status"]
+    );
+    let _stream = server.await.unwrap();
+}
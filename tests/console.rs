@@ -0,0 +1,179 @@
+mod common;
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{read_id, read_packet, write_packet, write_typed};
+use specul::{Backoff, ConnectionBuilder, Connector, ConsoleItem, Packet};
+use tokio::io::{duplex, DuplexStream};
+
+const AUTH_RESPONSE: i32 = 2;
+
+fn packet(item: Option<ConsoleItem>) -> Packet {
+    match item {
+        Some(ConsoleItem::Packet(packet)) => packet,
+        other => panic!("expected a packet, got {:?}", other),
+    }
+}
+
+/// Pushes a chat line before answering each of `count` commands.
+fn chatty(mut server: DuplexStream, count: usize) -> tokio::task::JoinHandle<DuplexStream> {
+    tokio::spawn(async move {
+        for i in 0..count {
+            let id = read_id(&mut server).await;
+            write_packet(&mut server, 1000, &format!("chat {}", i)).await;
+            write_packet(&mut server, id, "ok").await;
+        }
+        server
+    })
+}
+
+#[tokio::test]
+async fn pushed_packets_go_to_the_console_stream() {
+    let (client, server) = duplex(4096);
+    let server = chatty(server, 2);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let mut console = connection.console_messages();
+
+    assert_eq!(connection.execute_command("a").await.unwrap(), ["ok"]);
+    assert_eq!(connection.execute_command("b").await.unwrap(), ["ok"]);
+
+    let first = packet(console.recv().await);
+    let second = packet(console.recv().await);
+
+    assert_eq!((first.id, first.to_str().unwrap()), (1000, "chat 0"));
+    assert_eq!(second.to_str().unwrap(), "chat 1");
+    assert!(console.try_recv().is_err());
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn dropping_the_stream_does_not_affect_commands() {
+    let (client, server) = duplex(4096);
+    let server = chatty(server, 2);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    drop(connection.console_messages());
+
+    assert_eq!(connection.execute_command("a").await.unwrap(), ["ok"]);
+    assert_eq!(connection.execute_command("b").await.unwrap(), ["ok"]);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn a_new_stream_replaces_the_previous_one() {
+    let (client, server) = duplex(4096);
+    let server = chatty(server, 1);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let mut old = connection.console_messages();
+    let mut new = connection.console_messages();
+
+    connection.execute_command("a").await.unwrap();
+
+    assert_eq!(packet(new.recv().await).to_str().unwrap(), "chat 0");
+    assert!(old.recv().await.is_none());
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn the_stream_survives_the_server_going_away() {
+    let (first, mut killed) = duplex(4096);
+    let (second, mut restarted) = duplex(4096);
+
+    let killed = tokio::spawn(async move {
+        let id = read_id(&mut killed).await;
+        write_typed(&mut killed, id, AUTH_RESPONSE, "").await;
+        let id = read_id(&mut killed).await;
+        write_packet(&mut killed, 1000, "line 0").await;
+        write_packet(&mut killed, id, "ok").await;
+    });
+
+    let restarted = tokio::spawn(async move {
+        let id = read_id(&mut restarted).await;
+        write_typed(&mut restarted, id, AUTH_RESPONSE, "").await;
+        let (id, command) = read_packet(&mut restarted).await;
+        write_packet(&mut restarted, id, "").await;
+        let id = read_id(&mut restarted).await;
+        write_packet(&mut restarted, 1000, "line 1").await;
+        write_packet(&mut restarted, id, "ok").await;
+        (command, restarted)
+    });
+
+    let streams = Arc::new(Mutex::new(vec![second]));
+    let connector = Connector::new(move || {
+        let stream = streams.lock().unwrap().pop();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .auto_reconnect(Backoff {
+            initial: Duration::from_millis(10),
+            ..Backoff::default()
+        })
+        .console_command("log on")
+        .build()
+        .unwrap();
+    let mut console = connection.console_messages();
+
+    connection.authenticate("password").await.unwrap();
+    assert_eq!(connection.execute_command("a").await.unwrap(), ["ok"]);
+    killed.await.unwrap();
+    assert_eq!(connection.execute_command("b").await.unwrap(), ["ok"]);
+
+    assert_eq!(packet(console.recv().await).to_str().unwrap(), "line 0");
+    assert_eq!(console.recv().await, Some(ConsoleItem::Reconnected));
+    assert_eq!(packet(console.recv().await).to_str().unwrap(), "line 1");
+
+    let (command, _restarted) = restarted.await.unwrap();
+    assert_eq!(command, "log on");
+}
+
+#[tokio::test]
+async fn the_console_command_waits_for_a_stream() {
+    let (first, mut killed) = duplex(4096);
+    let (second, mut restarted) = duplex(4096);
+
+    tokio::spawn(async move {
+        let id = read_id(&mut killed).await;
+        write_typed(&mut killed, id, AUTH_RESPONSE, "").await;
+    });
+
+    let restarted = tokio::spawn(async move {
+        let id = read_id(&mut restarted).await;
+        write_typed(&mut restarted, id, AUTH_RESPONSE, "").await;
+        let (id, command) = read_packet(&mut restarted).await;
+        write_packet(&mut restarted, id, "ok").await;
+        (command, restarted)
+    });
+
+    let streams = Arc::new(Mutex::new(vec![second]));
+    let connector = Connector::new(move || {
+        let stream = streams.lock().unwrap().pop();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .auto_reconnect(Backoff::default())
+        .console_command("log on")
+        .build()
+        .unwrap();
+
+    connection.authenticate("password").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(connection.execute_command("status").await.unwrap(), ["ok"]);
+
+    let (command, _restarted) = restarted.await.unwrap();
+    assert_eq!(command, "status");
+}
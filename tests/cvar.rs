@@ -0,0 +1,189 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, CvarValue, Error};
+use tokio::io::duplex;
+
+#[test]
+fn types_values() {
+    assert_eq!(CvarValue::parse("false"), CvarValue::Bool(false));
+    assert_eq!(CvarValue::parse("-12"), CvarValue::Int(-12));
+    assert_eq!(CvarValue::parse("0.25"), CvarValue::Float(0.25));
+    assert_eq!(
+        CvarValue::parse("My Server"),
+        CvarValue::String("My Server".to_string())
+    );
+
+    assert_eq!(CvarValue::Int(1).as_bool(), Some(true));
+    assert_eq!(CvarValue::Int(2).as_bool(), None);
+    assert_eq!(CvarValue::Int(3).as_f64(), Some(3.0));
+    assert_eq!(CvarValue::Bool(true).to_string(), "1");
+}
+
+#[tokio::test]
+async fn gets_and_sets_cvars() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let exchanges = [
+            (
+                "sv_cheats",
+                "\"sv_cheats\" = \"0\" ( def. \"0\" )\n notify replicated\n - Allow cheats on server\n",
+            ),
+            ("sv_gravity", "sv_gravity = 800.5\n"),
+            ("hostname", "\"hostname\" = \"My Server\"\n"),
+            ("sv_cheats \"1\"", ""),
+        ];
+
+        for (expected, reply) in exchanges {
+            let (id, command) = read_packet(&mut server).await;
+            assert_eq!(command, expected);
+            write_packet(&mut server, id, reply).await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let cheats = connection.get_cvar("sv_cheats").await.unwrap();
+    assert_eq!(cheats, CvarValue::Int(0));
+    assert_eq!(cheats.as_bool(), Some(false));
+    assert_eq!(
+        connection.get_cvar("sv_gravity").await.unwrap(),
+        CvarValue::Float(800.5)
+    );
+    assert_eq!(
+        connection.get_cvar("hostname").await.unwrap().as_str(),
+        Some("My Server")
+    );
+    connection.set_cvar("sv_cheats", true).await.unwrap();
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn caches_until_invalidated() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for reply in ["\"mp_timelimit\" = \"30\"", "\"mp_timelimit\" = \"45\""] {
+            let (id, command) = read_packet(&mut server).await;
+            assert_eq!(command, "mp_timelimit");
+            write_packet(&mut server, id, reply).await;
+        }
+
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "mp_timelimit \"60\"");
+        write_packet(&mut server, id, "").await;
+
+        // Setting forgets the cached value, so it is read again.
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "mp_timelimit");
+        write_packet(&mut server, id, "\"mp_timelimit\" = \"60\"").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .cvar_cache_ttl(Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.get_cvar("mp_timelimit").await.unwrap(),
+        30.into()
+    );
+    assert_eq!(
+        connection.get_cvar("mp_timelimit").await.unwrap(),
+        30.into()
+    );
+
+    connection.invalidate_cvar("mp_timelimit");
+    assert_eq!(
+        connection.get_cvar("mp_timelimit").await.unwrap(),
+        45.into()
+    );
+
+    connection.set_cvar("mp_timelimit", 60).await.unwrap();
+    assert_eq!(
+        connection.get_cvar("mp_timelimit").await.unwrap(),
+        60.into()
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn rejects_unknown_and_invalid_cvars() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let (id, _) = read_packet(&mut server).await;
+            write_packet(&mut server, id, "Unknown command \"sv_nope\"\n").await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert!(matches!(
+        connection.get_cvar("sv_nope").await,
+        Err(Error::UnexpectedResponse(_))
+    ));
+    assert!(matches!(
+        connection.set_cvar("sv_nope", 1).await,
+        Err(Error::UnexpectedResponse(_))
+    ));
+    assert!(matches!(
+        connection.get_cvar("sv_cheats; quit").await,
+        Err(Error::InvalidCommand(_))
+    ));
+    assert!(matches!(
+        connection.set_cvar("hostname", "a\"; quit").await,
+        Err(Error::InvalidCommand(_))
+    ));
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn ignores_the_command_prefix() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "sv_cheats");
+        write_packet(&mut server, id, "sv_cheats = false").await;
+
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "sv_cheats \"1\"");
+        write_packet(&mut server, id, "").await;
+
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "sv_cheats");
+        write_packet(&mut server, id, "sv_cheats = true").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_prefix("sm_")
+        .cvar_cache_ttl(Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.get_cvar("sv_cheats").await.unwrap(),
+        CvarValue::Bool(false)
+    );
+    connection.set_cvar("sv_cheats", true).await.unwrap();
+    assert_eq!(
+        connection.get_cvar("sv_cheats").await.unwrap(),
+        CvarValue::Bool(true)
+    );
+
+    let _server = server.await.unwrap();
+}
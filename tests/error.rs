@@ -0,0 +1,124 @@
+mod common;
+
+use std::{error::Error as _, io};
+
+use common::{read_id, write_packet};
+use specul::{ConnectionBuilder, Error, ErrorKind, PacketType};
+use tokio::io::{duplex, AsyncWriteExt};
+
+#[test]
+fn errors_have_kinds() {
+    let eof = Error::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+    let invalid = Error::from(io::Error::from(io::ErrorKind::InvalidData));
+
+    assert_eq!(eof.kind(), ErrorKind::Disconnected);
+    assert_eq!(invalid.kind(), ErrorKind::Encoding);
+    assert_eq!(Error::Timeout.kind(), ErrorKind::Timeout);
+    assert_eq!(Error::Authentication.kind(), ErrorKind::Auth);
+    assert_eq!(Error::MalformedPacket("x").kind(), ErrorKind::Protocol);
+    assert_eq!(Error::ConnectionClosed.kind(), ErrorKind::Disconnected);
+    assert_eq!(Error::NoConnector.kind(), ErrorKind::Other);
+    assert_eq!(Error::Timeout.command(), None);
+}
+
+#[tokio::test]
+async fn errors_carry_the_command_and_packet() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_packet(&mut server, id + 7, "stray").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .strict_ids(true)
+        .error_context(true)
+        .build()
+        .unwrap();
+
+    let error = connection.execute_command("status").await.unwrap_err();
+
+    assert_eq!(error.command(), Some("status"));
+    assert_eq!(error.kind(), ErrorKind::Protocol);
+    assert!(matches!(error.root(), Error::IdMismatch { .. }));
+
+    let header = error.header().unwrap();
+    assert_eq!((header.id, header.packet_type), (7, PacketType::Response));
+    assert_eq!(header.length, 15);
+
+    assert!(error.to_string().ends_with("executing \"status\""));
+    assert_eq!(
+        error.source().unwrap().to_string(),
+        error.root().to_string()
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn malformed_packets_are_reported_with_their_header() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        read_id(&mut server).await;
+        server.write_i32_le(2).await.unwrap();
+        server.write_i32_le(3).await.unwrap();
+        server.write_i32_le(0).await.unwrap();
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .validate_first_packet(false)
+        .error_context(true)
+        .build()
+        .unwrap();
+
+    let error = connection.execute_command("status").await.unwrap_err();
+
+    assert!(matches!(error.root(), Error::MalformedPacket(_)));
+    assert_eq!(error.header().map(|header| header.id), Some(3));
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn errors_are_bare_without_error_context() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(4)
+        .build()
+        .unwrap();
+
+    let error = connection.execute_command("status").await.unwrap_err();
+
+    assert!(matches!(error, Error::PayloadSize));
+    assert_eq!(error.kind(), ErrorKind::TooLarge);
+}
+
+#[tokio::test]
+async fn a_first_packet_that_is_not_rcon_is_reported() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        read_id(&mut server).await;
+        server
+            .write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n")
+            .await
+            .unwrap();
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    match connection.authenticate("password").await {
+        Err(Error::NotRconServer(raw)) => assert!(raw.starts_with(b"HTTP")),
+        result => panic!("{:?}", result),
+    }
+
+    let _server = server.await.unwrap();
+}
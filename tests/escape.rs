@@ -0,0 +1,88 @@
+mod common;
+
+use common::echo;
+use specul::{ConnectionBuilder, Error, Quirks};
+use tokio::io::duplex;
+
+#[test]
+fn source_arguments_are_quoted_when_they_would_split() {
+    let escape = |argument| Quirks::Source.escape_argument(argument);
+
+    assert_eq!(escape("Gordon").unwrap(), "Gordon");
+    assert_eq!(escape("").unwrap(), "\"\"");
+    assert_eq!(escape("Alyx Vance").unwrap(), "\"Alyx Vance\"");
+    assert_eq!(escape("a;quit").unwrap(), "\"a;quit\"");
+    assert_eq!(escape("STEAM_1:0:11101").unwrap(), "\"STEAM_1:0:11101\"");
+    assert_eq!(escape("x//y").unwrap(), "\"x//y\"");
+
+    for argument in ["\"; quit; \"", "one\ntwo", "nul\0"] {
+        assert!(
+            matches!(escape(argument), Err(Error::InvalidCommand(_))),
+            "{:?}",
+            argument
+        );
+    }
+}
+
+#[test]
+fn minecraft_arguments_are_quoted_unless_plain_words() {
+    let escape = |argument| Quirks::Minecraft.escape_argument(argument);
+
+    assert_eq!(escape("Steve_01").unwrap(), "Steve_01");
+    assert_eq!(escape("1.5").unwrap(), "1.5");
+    assert_eq!(escape("two words").unwrap(), "\"two words\"");
+    assert_eq!(escape(r#"a"b\c"#).unwrap(), r#""a\"b\\c""#);
+    assert!(escape("line\rbreak").is_err());
+}
+
+#[test]
+fn factorio_arguments_are_only_checked() {
+    assert_eq!(
+        Quirks::Factorio.escape_argument("hello \"world\"").unwrap(),
+        "hello \"world\""
+    );
+    assert!(Quirks::Factorio.escape_argument("a\nb").is_err());
+}
+
+#[tokio::test]
+async fn control_characters_are_rejected_when_enabled() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 2);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .reject_control_characters(true)
+        .build()
+        .unwrap();
+
+    let error = connection
+        .execute_command("say hi\nquit")
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::InvalidCommand(_)), "{:?}", error);
+    assert!(error.to_string().contains("'\\n'"), "{}", error);
+
+    assert_eq!(
+        connection.execute_command("say\thi").await.unwrap(),
+        ["say\thi"]
+    );
+    assert_eq!(
+        connection.execute_command("say bye").await.unwrap(),
+        ["say bye"]
+    );
+    assert_eq!(server.await.unwrap(), ["say\thi", "say bye"]);
+}
+
+#[tokio::test]
+async fn control_characters_are_sent_by_default() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 1);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(
+        connection.execute_command("say a\nsay b").await.unwrap(),
+        ["say a\nsay b"]
+    );
+    assert_eq!(server.await.unwrap(), ["say a\nsay b"]);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, Error, ExecOptions};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn commands_without_a_response_return_without_reading() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (fired, command) = read_packet(&mut server).await;
+        assert_eq!(command, "quit");
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "status");
+
+        // The late answer to `quit` comes first.
+        write_packet(&mut server, fired, "bye").await;
+        write_packet(&mut server, id, "ok").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let options = ExecOptions {
+        expect_response: false,
+        ..connection.exec_options()
+    };
+
+    let response = connection
+        .execute_command_with("quit", options)
+        .await
+        .unwrap();
+    assert!(response.is_empty());
+
+    assert_eq!(connection.execute_command("status").await.unwrap(), ["ok"]);
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn single_packet_responses_send_no_sentinel() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        write_packet(&mut server, id, "one packet").await;
+
+        // Nothing else is sent once the first packet arrives.
+        let next = tokio::time::timeout(Duration::from_millis(100), read_packet(&mut server));
+        assert!(next.await.is_err());
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+    let options = ExecOptions {
+        multi: false,
+        ..connection.exec_options()
+    };
+
+    let response = connection
+        .execute_command_with("status", options)
+        .await
+        .unwrap();
+
+    assert_eq!(response, ["one packet"]);
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn the_timeout_replaces_the_connections() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        read_packet(&mut server).await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_deadline(Duration::from_secs(30))
+        .build()
+        .unwrap();
+    let options = ExecOptions {
+        timeout: Some(Duration::from_millis(50)),
+        ..connection.exec_options()
+    };
+
+    let result = connection.execute_command_with("status", options).await;
+
+    assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+    let _server = server.await.unwrap();
+}
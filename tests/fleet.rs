@@ -0,0 +1,121 @@
+#![cfg(all(feature = "fleet", feature = "testing"))]
+
+use std::time::Duration;
+
+use specul::{
+    fleet::{Fleet, ServerConfig},
+    testing::{MockServer, Reply},
+    ConnectionConfig, Error,
+};
+use tokio::net::TcpListener;
+
+async fn mock(hostname: &str) -> MockServer {
+    MockServer::builder("password")
+        .respond("status", Reply::text(format!("hostname: {}", hostname)))
+        .start()
+        .await
+        .unwrap()
+}
+
+/// An address nothing is listening on.
+async fn unreachable() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn reports_every_servers_result_including_failures() {
+    let (eu1, eu2) = (mock("eu1").await, mock("eu2").await);
+
+    let mut fleet = Fleet::new();
+    fleet.insert("eu1", ServerConfig::new(eu1.addr().to_string(), "password"));
+    fleet.insert("eu2", ServerConfig::new(eu2.addr().to_string(), "password"));
+    fleet.insert("us1", ServerConfig::new(unreachable().await, "password"));
+    fleet.insert("us2", ServerConfig::new(eu1.addr().to_string(), "wrong"));
+
+    let results = fleet.execute_all("status").await;
+
+    assert_eq!(
+        results.keys().collect::<Vec<_>>(),
+        ["eu1", "eu2", "us1", "us2"]
+    );
+    assert_eq!(results["eu1"].as_ref().unwrap(), &["hostname: eu1"]);
+    assert_eq!(results["eu2"].as_ref().unwrap(), &["hostname: eu2"]);
+    assert!(matches!(results["us1"], Err(Error::Io(_))));
+    assert!(matches!(results["us2"], Err(Error::Authentication)));
+}
+
+#[tokio::test]
+async fn executes_on_the_named_servers_only() {
+    let (eu1, eu2) = (mock("eu1").await, mock("eu2").await);
+
+    let mut fleet = Fleet::new();
+    fleet.insert("eu1", ServerConfig::new(eu1.addr().to_string(), "password"));
+    fleet.insert("eu2", ServerConfig::new(eu2.addr().to_string(), "password"));
+
+    let results = fleet.execute_on(&["eu2", "ap1"], "status").await;
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results["eu2"].as_ref().unwrap(), &["hostname: eu2"]);
+    assert!(matches!(&results["ap1"], Err(Error::UnknownServer(name)) if name == "ap1"));
+}
+
+#[tokio::test]
+async fn reconnects_after_a_connection_fails() {
+    let eu1 = MockServer::builder("password")
+        .respond("status", Reply::text("hostname: eu1"))
+        .respond("hang", Reply::Silent)
+        .start()
+        .await
+        .unwrap();
+
+    let mut fleet = Fleet::new().timeout(Duration::from_millis(200));
+    fleet.insert("eu1", ServerConfig::new(eu1.addr().to_string(), "password"));
+
+    assert!(matches!(
+        fleet.execute_all("hang").await["eu1"],
+        Err(Error::Timeout)
+    ));
+    assert_eq!(
+        fleet.execute_all("status").await["eu1"].as_ref().unwrap(),
+        &["hostname: eu1"]
+    );
+}
+
+#[tokio::test]
+async fn connects_with_each_servers_settings() {
+    let server = MockServer::builder("password")
+        .respond("sm_status", Reply::text("hostname: prefixed"))
+        .start()
+        .await
+        .unwrap();
+    let url = format!("rcon://:password@{}?prefix=sm_", server.addr());
+
+    let mut fleet = Fleet::new();
+    fleet.insert(
+        "config",
+        ServerConfig::from_config(ConnectionConfig::from_url(&url).unwrap()),
+    );
+    fleet.insert(
+        "configured",
+        ServerConfig::new(server.addr().to_string(), "password")
+            .configure(|builder| builder.command_prefix("sm_")),
+    );
+    fleet.insert(
+        "invalid",
+        ServerConfig::new(server.addr().to_string(), "password")
+            .configure(|builder| builder.rate_limit(0.0, 1)),
+    );
+
+    let results = fleet.execute_all("status").await;
+
+    assert_eq!(results["config"].as_ref().unwrap(), &["hostname: prefixed"]);
+    assert_eq!(
+        results["configured"].as_ref().unwrap(),
+        &["hostname: prefixed"]
+    );
+    assert!(matches!(
+        &results["invalid"],
+        Err(Error::Io(error)) if error.kind() == std::io::ErrorKind::InvalidInput
+    ));
+}
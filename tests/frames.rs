@@ -0,0 +1,148 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{read_packet, write_typed};
+use specul::{ConnectionBuilder, Frame, FrameDirection, PacketType};
+use tokio::io::{duplex, DuplexStream};
+
+/// Accepts the password and answers each of `count` commands with the
+/// command itself.
+fn server(mut server: DuplexStream, count: usize) -> tokio::task::JoinHandle<String> {
+    tokio::spawn(async move {
+        let (id, password) = read_packet(&mut server).await;
+        write_typed(&mut server, id, 2, "").await;
+
+        for _ in 0..count {
+            let (id, command) = read_packet(&mut server).await;
+            write_typed(&mut server, id, 0, &command).await;
+        }
+
+        password
+    })
+}
+
+fn collect() -> (Arc<Mutex<Vec<Frame>>>, impl Fn(&Frame) + Send + Sync) {
+    let frames = Arc::new(Mutex::new(Vec::new()));
+    let sink = frames.clone();
+    (frames, move |frame: &Frame| {
+        sink.lock().unwrap().push(frame.clone())
+    })
+}
+
+#[tokio::test]
+async fn frames_are_passed_to_the_hook() {
+    let (client, io) = duplex(16 * 1024);
+    let server = server(io, 1);
+    let (frames, hook) = collect();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .on_frame(hook)
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+    connection.execute_command("status").await.unwrap();
+    assert_eq!(server.await.unwrap(), "hunter2");
+
+    let frames = frames.lock().unwrap();
+    let directions: Vec<_> = frames.iter().map(|frame| frame.direction).collect();
+    assert_eq!(
+        directions,
+        [
+            FrameDirection::Sent,
+            FrameDirection::Received,
+            FrameDirection::Sent,
+            FrameDirection::Received,
+        ]
+    );
+
+    let command = &frames[2];
+    assert_eq!(command.header.packet_type, PacketType::Message);
+    assert_eq!(command.bytes.len(), 4 + 10 + "status".len());
+    assert_eq!(&command.bytes[12..18], b"status");
+    assert_eq!(frames[3].header.id, command.header.id);
+    assert_eq!(&frames[3].bytes[12..18], b"status");
+}
+
+#[tokio::test]
+async fn passwords_are_masked() {
+    let (client, io) = duplex(16 * 1024);
+    let server = server(io, 0);
+    let (frames, hook) = collect();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .on_frame(hook)
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+    server.await.unwrap();
+
+    let frames = frames.lock().unwrap();
+    let auth = &frames[0];
+    assert_eq!(auth.header.packet_type, PacketType::Authentication);
+    assert_eq!(&auth.bytes[12..], b"*******\0\0");
+
+    let dump = auth.to_string();
+    assert!(!dump.contains("hunter2"));
+    assert_eq!(
+        dump,
+        "> sent id 0, type Authentication, length 17, 21 bytes\n\
+         0000  11 00 00 00 00 00 00 00  03 00 00 00 2a 2a 2a 2a  |............****|\n\
+         0010  2a 2a 2a 00 00                                    |***..|"
+    );
+}
+
+#[tokio::test]
+async fn dumps_are_truncated() {
+    let (client, io) = duplex(16 * 1024);
+    let long = "x".repeat(100);
+    let server = server(io, 1);
+    let (frames, hook) = collect();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .on_frame(hook)
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+    connection.execute_command(&long).await.unwrap();
+    server.await.unwrap();
+
+    let frames = frames.lock().unwrap();
+    let dump = frames[2].dump(16);
+    assert_eq!(dump.lines().count(), 3);
+    assert!(dump.ends_with("... 98 more bytes\n"));
+    assert_eq!(frames[2].to_string().lines().count(), 9);
+}
+
+#[tokio::test]
+async fn split_halves_pass_frames_to_the_hook() {
+    let (client, io) = duplex(16 * 1024);
+    let server = server(io, 1);
+    let (frames, hook) = collect();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .on_frame(hook)
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+    let (mut sender, mut receiver) = connection.split();
+    let id = sender.send_command("status").await.unwrap();
+
+    let packet = receiver.receive_packet().await.unwrap();
+    assert_eq!(packet.id, id);
+    server.await.unwrap();
+
+    let frames = frames.lock().unwrap();
+    assert_eq!(frames.len(), 4);
+    assert_eq!(frames[2].direction, FrameDirection::Sent);
+    assert_eq!(frames[3].direction, FrameDirection::Received);
+    assert_eq!(frames[3].header.id, id);
+}
@@ -0,0 +1,266 @@
+use specul::{ConnectionBuilder, Error, Framing, PacketType, PrefixWidth};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn round_trips_with_two_byte_prefix() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let length = server.read_u16_le().await.unwrap();
+        let id = server.read_i32_le().await.unwrap();
+        let packet_type = server.read_i32_le().await.unwrap();
+        let mut body = vec![0; length as usize - 8];
+        server.read_exact(&mut body).await.unwrap();
+
+        assert_eq!(length, 15);
+        assert_eq!(packet_type, 2);
+        assert_eq!(body, b"hello\0\0");
+
+        server.write_u16_le(15).await.unwrap();
+        server.write_i32_le(id).await.unwrap();
+        server.write_i32_le(0).await.unwrap();
+        server.write_all(b"world\0\0").await.unwrap();
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .framing(Framing {
+            prefix_width: PrefixWidth::Two,
+            ..Framing::default()
+        })
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("hello").await.unwrap();
+    assert_eq!(response, vec!["world".to_string()]);
+
+    server.await.unwrap();
+}
+
+fn packet(id: i32, payload: &str, terminator: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(10 + payload.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(&id.to_le_bytes());
+    bytes.extend_from_slice(&0i32.to_le_bytes());
+    bytes.extend_from_slice(payload.as_bytes());
+    bytes.extend_from_slice(terminator);
+    bytes
+}
+
+#[tokio::test]
+async fn decodes_empty_payload_with_trailing_nulls() {
+    let (client, mut server) = duplex(4096);
+
+    server.write_all(&packet(1, "", &[0, 0])).await.unwrap();
+    server.write_all(&packet(2, "next", &[0, 0])).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "");
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "next");
+}
+
+#[tokio::test]
+async fn decodes_empty_payload_without_trailing_nulls() {
+    let (client, mut server) = duplex(4096);
+
+    server.write_all(&packet(1, "", &[])).await.unwrap();
+    server.write_all(&packet(2, "next", &[0, 0])).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "");
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "next");
+}
+
+#[tokio::test]
+async fn decodes_a_last_packet_without_trailing_nulls() {
+    let (client, mut server) = duplex(4096);
+
+    server
+        .write_all(&packet(1, "first", &[0, 0]))
+        .await
+        .unwrap();
+    server.write_all(&packet(2, "last", &[])).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "first");
+    let last = tokio::time::timeout(
+        std::time::Duration::from_secs(1),
+        connection.recieve_single_response(),
+    )
+    .await
+    .expect("the last packet waits for a terminator");
+    assert_eq!(last.unwrap(), "last");
+}
+
+#[tokio::test]
+async fn strict_terminator_rejects_missing_nulls() {
+    let (client, mut server) = duplex(4096);
+
+    server.write_all(&packet(1, "", &[])).await.unwrap();
+    server.write_all(&packet(2, "next", &[0, 0])).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .framing(Framing {
+            strict_terminator: true,
+            ..Framing::default()
+        })
+        .build()
+        .unwrap();
+
+    assert!(connection.recieve_single_response().await.is_err());
+}
+
+#[tokio::test]
+async fn rejects_packets_longer_than_the_cap() {
+    let (client, mut server) = duplex(4096);
+
+    // Only the header is sent, so accepting it would mean waiting for the
+    // rest of a 16-byte payload.
+    server.write_i32_le(26).await.unwrap();
+    server.write_i32_le(1).await.unwrap();
+    server.write_i32_le(0).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_incoming_packet_size(20)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.recieve_single_response().await,
+        Err(Error::MalformedPacket(_))
+    ));
+}
+
+#[tokio::test]
+async fn rejects_lengths_shorter_than_the_header() {
+    let (client, mut server) = duplex(4096);
+
+    server.write_i32_le(4).await.unwrap();
+    server.write_i32_le(1).await.unwrap();
+    server.write_i32_le(0).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .validate_first_packet(false)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.recieve_single_response().await,
+        Err(Error::MalformedPacket(_))
+    ));
+}
+
+async fn round_trip_with_trailing_nulls(trailing_nulls: u8) {
+    let (client, mut server) = duplex(4096);
+    let nulls = vec![0; trailing_nulls as usize];
+    let length = 8 + 5 + trailing_nulls as i32;
+
+    let server = tokio::spawn(async move {
+        assert_eq!(server.read_i32_le().await.unwrap(), length);
+        let id = server.read_i32_le().await.unwrap();
+        let _packet_type = server.read_i32_le().await.unwrap();
+        let mut body = vec![0; length as usize - 8];
+        server.read_exact(&mut body).await.unwrap();
+        assert_eq!(&body[..5], b"hello");
+        assert_eq!(&body[5..], &nulls[..]);
+
+        server.write_i32_le(length).await.unwrap();
+        server.write_i32_le(id).await.unwrap();
+        server.write_i32_le(0).await.unwrap();
+        server.write_all(b"world").await.unwrap();
+        server.write_all(&nulls).await.unwrap();
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .framing(Framing {
+            trailing_nulls,
+            ..Framing::default()
+        })
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("hello").await.unwrap();
+    assert_eq!(response, vec!["world".to_string()]);
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn round_trips_with_one_trailing_null() {
+    round_trip_with_trailing_nulls(1).await;
+}
+
+#[tokio::test]
+async fn round_trips_with_two_trailing_nulls() {
+    round_trip_with_trailing_nulls(2).await;
+}
+
+#[test]
+fn rejects_unsupported_trailing_nulls() {
+    let (client, _server) = duplex(4096);
+
+    let result = ConnectionBuilder::default()
+        .io(client)
+        .framing(Framing {
+            trailing_nulls: 3,
+            ..Framing::default()
+        })
+        .build();
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn keeps_packets_that_arrive_in_one_read() {
+    let (client, mut server) = duplex(4096);
+
+    let mut bytes = packet(1, "first", &[0, 0]);
+    bytes.extend(packet(2, "second", &[0, 0]));
+    bytes.extend(&packet(3, "third", &[0, 0])[..6]);
+    server.write_all(&bytes).await.unwrap();
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "first");
+    assert_eq!(
+        connection.recieve_single_response().await.unwrap(),
+        "second"
+    );
+
+    server
+        .write_all(&packet(3, "third", &[0, 0])[6..])
+        .await
+        .unwrap();
+    assert_eq!(connection.recieve_single_response().await.unwrap(), "third");
+}
+
+#[tokio::test]
+async fn writes_each_packet_without_leftovers_of_the_last() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    connection
+        .send(PacketType::Message, "a longer command".to_string())
+        .await
+        .unwrap();
+    connection
+        .send(PacketType::Message, "short".to_string())
+        .await
+        .unwrap();
+    drop(connection);
+
+    let mut written = Vec::new();
+    server.read_to_end(&mut written).await.unwrap();
+
+    // Each packet is its length prefix, id, type, payload and two NULs.
+    assert_eq!(written.len(), (12 + 16 + 2) + (12 + 5 + 2));
+    assert!(written.ends_with(b"short\0\0"));
+}
@@ -0,0 +1,152 @@
+mod common;
+
+use std::time::Duration;
+
+use common::{read_id, write_packet};
+use specul::{ConnectionBuilder, Error, IdStrategy};
+use tokio::io::duplex;
+
+#[test]
+fn sequential_ids_count_up_and_wrap() {
+    let (client, _server) = duplex(64);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .current_packet_id(i32::MAX - 1)
+        .default_packet_id(10)
+        .keepalive_id(11)
+        .build()
+        .unwrap();
+
+    let ids: Vec<i32> = (0..4).map(|_| connection.new_packet_id()).collect();
+
+    assert_eq!(ids, [i32::MAX - 1, i32::MAX, 10, 12]);
+}
+
+#[test]
+fn random_ids_are_never_negative_or_the_keepalive_id() {
+    let (client, _server) = duplex(64);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Random)
+        .keepalive_id(0)
+        .build()
+        .unwrap();
+
+    let ids: Vec<i32> = (0..100).map(|_| connection.new_packet_id()).collect();
+
+    assert!(ids.iter().all(|&id| id > 0));
+    assert!(ids.windows(2).any(|pair| pair[0] != pair[1]));
+}
+
+#[test]
+fn constant_ids_repeat() {
+    let (client, _server) = duplex(64);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Constant(42))
+        .build()
+        .unwrap();
+
+    assert_eq!(connection.new_packet_id(), 42);
+    assert_eq!(connection.new_packet_id(), 42);
+}
+
+#[test]
+fn invalid_constant_ids_are_rejected() {
+    let build = |strategy, multiple_responses| {
+        let (client, _server) = duplex(64);
+
+        ConnectionBuilder::default()
+            .io(client)
+            .id_strategy(strategy)
+            .keepalive_id(7)
+            .multiple_responses(multiple_responses)
+            .build()
+    };
+
+    assert!(build(IdStrategy::Constant(-1), false).is_err());
+    assert!(build(IdStrategy::Constant(7), false).is_err());
+    assert!(build(IdStrategy::Constant(1), true).is_err());
+    assert!(build(IdStrategy::Constant(1), false).is_ok());
+}
+
+#[tokio::test]
+async fn commands_use_random_ids() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let mut ids = Vec::new();
+
+        for _ in 0..2 {
+            let id = read_id(&mut server).await;
+            write_packet(&mut server, id, "ok").await;
+            ids.push(id);
+        }
+
+        ids
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Random)
+        .strict_ids(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(connection.execute_command("a").await.unwrap(), ["ok"]);
+    assert_eq!(connection.execute_command("b").await.unwrap(), ["ok"]);
+
+    let ids = server.await.unwrap();
+    assert!(ids.iter().all(|&id| id >= 0));
+}
+
+#[tokio::test]
+async fn split_halves_share_the_strategy() {
+    let (client, _server) = duplex(64);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Constant(3))
+        .build()
+        .unwrap();
+    let (sender, _receiver) = connection.split();
+
+    assert_eq!(sender.new_packet_id(), 3);
+    assert_eq!(sender.new_packet_id(), 3);
+}
+
+#[tokio::test]
+async fn constant_ids_are_never_pipelined() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_packet(&mut server, id, "ok").await;
+        id
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .id_strategy(IdStrategy::Constant(42))
+        .build()
+        .unwrap();
+
+    let batch = tokio::time::timeout(
+        Duration::from_secs(1),
+        connection.execute_commands(["a", "b"]),
+    )
+    .await
+    .expect("a batch with a constant id hangs");
+    assert!(matches!(batch, Err(Error::Unsupported(_))));
+    assert!(matches!(
+        connection.fire("a").await,
+        Err(Error::Unsupported(_))
+    ));
+
+    // A single command still runs.
+    assert_eq!(connection.execute_commands(["c"]).await.unwrap(), [["ok"]]);
+    assert_eq!(server.await.unwrap(), 42);
+}
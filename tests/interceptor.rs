@@ -0,0 +1,225 @@
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, Error, Interceptor, Result};
+use tokio::io::duplex;
+
+/// Records the order hooks run in, under `name`.
+struct Trace {
+    name: &'static str,
+    log: Arc<Mutex<Vec<String>>>,
+}
+
+impl Interceptor for Trace {
+    fn before_send(&self, command: &mut String) -> Result<()> {
+        self.log
+            .lock()
+            .unwrap()
+            .push(format!("{} sends {}", self.name, command));
+        Ok(())
+    }
+
+    fn after_receive(&self, command: &str, response: &mut Vec<String>) -> Result<()> {
+        self.log.lock().unwrap().push(format!(
+            "{} receives {} for {}",
+            self.name,
+            response.concat(),
+            command
+        ));
+        Ok(())
+    }
+}
+
+/// Masks a word in commands and responses, and refuses `quit`.
+struct Filter;
+
+impl Interceptor for Filter {
+    fn before_send(&self, command: &mut String) -> Result<()> {
+        if command == "quit" {
+            return Err(Error::Rejected(command.clone()));
+        }
+
+        *command = command.replace("darn", "****");
+        Ok(())
+    }
+
+    fn after_receive(&self, _command: &str, response: &mut Vec<String>) -> Result<()> {
+        for payload in response {
+            *payload = payload.replace("darn", "****");
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn runs_interceptors_as_a_stack() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "say ****");
+        write_packet(&mut server, id, "darn it").await;
+        server
+    });
+
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .interceptor(Trace {
+            name: "outer",
+            log: log.clone(),
+        })
+        .interceptor(Filter)
+        .interceptor(Trace {
+            name: "inner",
+            log: log.clone(),
+        })
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.execute_command("say darn").await.unwrap(),
+        ["**** it"]
+    );
+    assert_eq!(
+        *log.lock().unwrap(),
+        [
+            "outer sends say darn",
+            "inner sends say ****",
+            "inner receives darn it for say ****",
+            "outer receives **** it for say ****",
+        ]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn rejected_commands_are_not_sent() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "status");
+        write_packet(&mut server, id, "hostname: darn").await;
+
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "echo ****");
+        write_packet(&mut server, id, "****").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .interceptor(Filter)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.execute_command("quit").await,
+        Err(Error::Rejected(command)) if command == "quit"
+    ));
+    assert!(matches!(
+        connection.execute_commands(["status", "quit"]).await,
+        Err(Error::Rejected(_))
+    ));
+    assert_eq!(
+        connection.execute("status").await.unwrap(),
+        "hostname: ****"
+    );
+    assert_eq!(
+        connection.execute_commands(["echo darn"]).await.unwrap(),
+        [["****"]]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn the_split_sender_runs_before_send() {
+    let (client, mut server) = duplex(4096);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .interceptor(Filter)
+        .build()
+        .unwrap();
+    let (mut sender, _receiver) = connection.split();
+
+    assert!(matches!(
+        sender.send_command("quit").await,
+        Err(Error::Rejected(_))
+    ));
+    sender.send_command("say darn").await.unwrap();
+
+    let (_, command) = read_packet(&mut server).await;
+    assert_eq!(command, "say ****");
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn the_client_runs_interceptors() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "say ****");
+        write_packet(&mut server, id, "darn it").await;
+        server
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .interceptor(Filter)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    assert!(matches!(
+        client.execute_command("quit").await,
+        Err(Error::Rejected(_))
+    ));
+    assert_eq!(
+        client.execute_command("say darn").await.unwrap(),
+        ["**** it"]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[cfg(feature = "tower")]
+#[tokio::test]
+async fn the_service_runs_interceptors() {
+    use futures::future::poll_fn;
+    use specul::service::RconService;
+    use tower_service::Service;
+
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "say ****");
+        write_packet(&mut server, id, "darn it").await;
+        server
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .interceptor(Filter)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+    let mut service = RconService::new(client);
+
+    poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+    assert_eq!(
+        service.call("say darn".to_string()).await.unwrap(),
+        "**** it"
+    );
+
+    let _server = server.await.unwrap();
+}
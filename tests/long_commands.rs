@@ -0,0 +1,110 @@
+mod common;
+
+use common::{echo, read_bytes, write_packet};
+use specul::{Charset, ConnectionBuilder, Error};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn long_commands_are_sent_line_by_line() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 3);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(16)
+        .split_long_commands(true)
+        .command_prefix("/")
+        .build()
+        .unwrap();
+
+    let response = connection
+        .execute_command("say one\nsay two\n\nsay three")
+        .await
+        .unwrap();
+
+    assert_eq!(response, ["/say one", "/say two", "/say three"]);
+    assert_eq!(
+        server.await.unwrap(),
+        ["/say one", "/say two", "/say three"]
+    );
+}
+
+#[tokio::test]
+async fn commands_that_fit_are_sent_whole() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 1);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .split_long_commands(true)
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("say a\nsay b").await.unwrap();
+
+    assert_eq!(response, ["say a\nsay b"]);
+    assert_eq!(server.await.unwrap(), ["say a\nsay b"]);
+}
+
+#[tokio::test]
+async fn commands_are_measured_in_the_bytes_sent() {
+    let (client, mut server) = duplex(16 * 1024);
+    let server = tokio::spawn(async move {
+        let (id, _, command) = read_bytes(&mut server).await;
+        write_packet(&mut server, id, "ok").await;
+        command
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(16)
+        .split_long_commands(true)
+        .charset(Charset::Latin1)
+        .build()
+        .unwrap();
+
+    // 20 bytes of UTF-8, but 15 of Latin-1.
+    let response = connection
+        .execute_command("say \u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\nsay a")
+        .await
+        .unwrap();
+
+    assert_eq!(response, ["ok"]);
+    assert_eq!(server.await.unwrap(), b"say \xe9\xe9\xe9\xe9\xe9\nsay a");
+}
+
+#[tokio::test]
+async fn lines_over_the_limit_still_fail() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 1);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(16)
+        .split_long_commands(true)
+        .build()
+        .unwrap();
+
+    let command = format!("say short\nsay {}", "a".repeat(16));
+    let result = connection.execute_command(&command).await;
+
+    assert!(matches!(result, Err(Error::PayloadSize)));
+    assert_eq!(server.await.unwrap(), ["say short"]);
+}
+
+#[tokio::test]
+async fn long_commands_fail_without_the_option() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(16)
+        .build()
+        .unwrap();
+
+    let result = connection
+        .execute_command("say one\nsay two\nsay three")
+        .await;
+
+    assert!(matches!(result, Err(Error::PayloadSize)));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{read_packet, write_typed};
+use specul::{ConnectionBuilder, Connector, Error, Metrics};
+use tokio::io::duplex;
+
+#[derive(Clone, Default)]
+struct Recorder {
+    calls: Arc<Mutex<Vec<String>>>,
+}
+
+impl Recorder {
+    fn push(&self, call: String) {
+        self.calls.lock().unwrap().push(call);
+    }
+
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+}
+
+impl Metrics for Recorder {
+    fn command_sent(&self) {
+        self.push("command_sent".to_string());
+    }
+
+    fn command_completed(&self, name: &str, latency: Duration) {
+        assert!(latency < Duration::from_secs(5));
+        self.push(format!("command_completed {}", name));
+    }
+
+    fn command_failed(&self, name: &str, error: &Error) {
+        self.push(format!("command_failed {} {}", name, error));
+    }
+
+    fn packet_sent(&self, bytes: usize) {
+        self.push(format!("packet_sent {}", bytes));
+    }
+
+    fn packet_received(&self) {
+        self.push("packet_received".to_string());
+    }
+
+    fn auth_failed(&self) {
+        self.push("auth_failed".to_string());
+    }
+
+    fn reconnected(&self) {
+        self.push("reconnected".to_string());
+    }
+}
+
+#[tokio::test]
+async fn reports_commands_and_packets() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        read_packet(&mut server).await;
+        write_typed(&mut server, -1, 2, "").await;
+
+        let (id, _) = read_packet(&mut server).await;
+        write_typed(&mut server, id, 0, "hostname: metered").await;
+        server
+    });
+
+    let recorder = Recorder::default();
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .metrics(recorder.clone())
+        .timeout(Duration::from_millis(200))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.authenticate("wrong").await,
+        Err(Error::Authentication)
+    ));
+    connection.execute_command("status players").await.unwrap();
+    let _server = server.await.unwrap();
+
+    assert_eq!(
+        recorder.calls(),
+        [
+            "packet_sent 19",
+            "packet_received",
+            "auth_failed",
+            "command_sent",
+            "packet_sent 28",
+            "packet_received",
+            "command_completed status",
+        ]
+    );
+    assert_eq!(connection.stats().commands, 1);
+}
+
+#[tokio::test]
+async fn reports_failures_and_reconnects() {
+    let (client, _server) = duplex(4096);
+
+    let recorder = Recorder::default();
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .metrics(recorder.clone())
+        .command_deadline(Duration::from_millis(20))
+        .connector(Connector::new(|| async { Ok(duplex(4096).0) }))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.execute_command("status").await,
+        Err(Error::Timeout)
+    ));
+    connection.reconnect().await.unwrap();
+
+    let calls = recorder.calls();
+    assert!(calls.contains(&"command_failed status operation timed out".to_string()));
+    assert_eq!(calls.last().unwrap(), "reconnected");
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "minecraft")]
+
+use specul::{
+    minecraft::{
+        Color, Component, Deop, GetGameRule, ListPlayers, Op, SaveAll, SetGameRule, Tellraw,
+        WhitelistAdd, WhitelistRemove,
+    },
+    Command, Error,
+};
+
+#[test]
+fn parses_the_player_list() {
+    let list =
+        ListPlayers::parse_response("There are 2 of a max of 20 players online: Steve, Alex")
+            .unwrap();
+
+    assert_eq!((list.online, list.max), (2, 20));
+    assert_eq!(list.players[1].name, "Alex");
+
+    let empty = ListPlayers::parse_response("There are 0/10 players online:").unwrap();
+
+    assert_eq!((empty.online, empty.max), (0, 10));
+    assert!(empty.players.is_empty());
+
+    assert!(matches!(
+        ListPlayers::parse_response("Unknown or incomplete command"),
+        Err(Error::UnexpectedResponse(_))
+    ));
+}
+
+#[test]
+fn reports_whether_player_commands_changed_anything() {
+    assert_eq!(
+        WhitelistAdd::new("Steve").to_command_string(),
+        "whitelist add Steve"
+    );
+    assert!(WhitelistAdd::parse_response("Added Steve to the whitelist").unwrap());
+    assert!(!WhitelistAdd::parse_response("Player is already whitelisted").unwrap());
+    assert!(WhitelistAdd::parse_response("That player does not exist").is_err());
+
+    assert!(WhitelistRemove::parse_response("Removed Steve from the whitelist").unwrap());
+    assert!(!WhitelistRemove::parse_response("Player is not whitelisted").unwrap());
+
+    assert_eq!(Op::new("Steve").to_command_string(), "op Steve");
+    assert!(Op::parse_response("Made Steve a server operator").unwrap());
+    assert!(!Op::parse_response("Nothing changed. The player already is an operator").unwrap());
+    assert!(Deop::parse_response("Made Steve no longer a server operator").unwrap());
+}
+
+#[test]
+fn reads_and_writes_typed_game_rules() {
+    let get = GetGameRule::<bool>::new("keepInventory");
+
+    assert_eq!(get.to_command_string(), "gamerule keepInventory");
+    assert!(!GetGameRule::<bool>::parse_response(
+        "Gamerule keepInventory is currently set to: false"
+    )
+    .unwrap());
+
+    let set = SetGameRule::new("randomTickSpeed", 10);
+
+    assert_eq!(set.to_command_string(), "gamerule randomTickSpeed 10");
+    assert_eq!(
+        SetGameRule::<i32>::parse_response("Gamerule randomTickSpeed is now set to: 10").unwrap(),
+        10
+    );
+    assert!(GetGameRule::<i32>::parse_response("Incorrect argument for command").is_err());
+}
+
+#[test]
+fn saves_the_world() {
+    assert_eq!(
+        SaveAll { flush: true }.to_command_string(),
+        "save-all flush"
+    );
+    assert!(
+        SaveAll::parse_response("Saving the game (this may take a moment!)Saved the game").is_ok()
+    );
+    assert!(SaveAll::parse_response("Saving failed").is_err());
+}
+
+#[test]
+fn builds_tellraw_from_components() {
+    let message = Component::text("Say \"hi\"\n")
+        .color(Color::DarkRed)
+        .bold(true)
+        .extra(Component::text("!").underlined(false));
+
+    assert_eq!(
+        Tellraw::new("@a", message).to_command_string(),
+        r#"tellraw @a {"text":"Say \"hi\"\n","color":"dark_red","bold":true,"extra":[{"text":"!","underlined":false}]}"#
+    );
+    assert!(Tellraw::parse_response("").is_ok());
+    assert!(Tellraw::parse_response("No player was found").is_err());
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "testing")]
+
+use specul::{
+    testing::{MockServer, Reply},
+    Connection, ConnectionBuilder, Error, Event,
+};
+use tokio::net::TcpStream;
+
+#[tokio::test]
+async fn answers_unknown_commands() {
+    let mock = MockServer::builder("password").start().await.unwrap();
+
+    let mut connection = Connection::connect(mock.addr(), "password").await.unwrap();
+    let response = connection.execute_command("nope").await.unwrap();
+
+    assert_eq!(response, vec!["Unknown command \"nope\"".to_string()]);
+}
+
+#[tokio::test]
+async fn replays_multi_packet_and_junk_replies() {
+    let mock = MockServer::builder("password")
+        .respond("cvarlist", Reply::fragments(["a", "b", "c"]))
+        .respond(
+            "status",
+            Reply::Junk {
+                payload: "noise".to_string(),
+                then: Box::new(Reply::text("ok")),
+            },
+        )
+        .start()
+        .await
+        .unwrap();
+
+    let mut connection = ConnectionBuilder::default()
+        .io(TcpStream::connect(mock.addr()).await.unwrap())
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+    let mut events = connection.monitor().events();
+    connection.authenticate("password").await.unwrap();
+
+    let cvars = connection.execute_command("cvarlist").await.unwrap();
+    let status = connection.execute_command("status").await.unwrap();
+
+    assert_eq!(cvars, vec!["a", "b", "c"]);
+    assert_eq!(status, vec!["ok"]);
+    assert!(
+        matches!(events.try_recv(), Ok(Event::Unsolicited(packet)) if packet.payload == "noise")
+    );
+}
+
+#[tokio::test]
+async fn replays_malformed_bytes() {
+    let mock = MockServer::builder("password")
+        .respond("junk", Reply::Raw(vec![2, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]))
+        .start()
+        .await
+        .unwrap();
+
+    let mut connection = Connection::connect(mock.addr(), "password").await.unwrap();
+    let result = connection.execute_command("junk").await;
+
+    assert!(matches!(result, Err(Error::MalformedPacket(_))));
+}
@@ -0,0 +1,161 @@
+mod common;
+
+use common::{read_id, write_packet};
+use specul::{ConnectionBuilder, Error, Event};
+use tokio::io::{duplex, DuplexStream};
+
+/// Answers a command and its sentinel like a Source server: the output, the
+/// mirrored sentinel and the `00 01 00 00` marker.
+async fn respond_multi(io: &mut DuplexStream, parts: &[&str]) -> i32 {
+    let id = read_id(io).await;
+    let sentinel = read_id(io).await;
+
+    for part in parts {
+        write_packet(io, id, part).await;
+    }
+    write_packet(io, sentinel, "").await;
+    write_packet(io, sentinel, "\0\u{1}\0\0").await;
+    id
+}
+
+#[tokio::test]
+async fn keeps_empty_lines_before_the_sentinel() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        respond_multi(&mut server, &["first", "", "second"]).await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("status").await.unwrap();
+
+    assert_eq!(
+        response,
+        vec!["first".to_string(), String::new(), "second".to_string()]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn keeps_packets_queued_after_the_terminator() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = respond_multi(&mut server, &["first"]).await;
+        write_packet(&mut server, id, "late output").await;
+
+        respond_multi(&mut server, &["second"]).await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+    let mut events = connection.monitor().events();
+
+    let first = connection.execute_command("one").await.unwrap();
+    let second = connection.execute_command("two").await.unwrap();
+
+    assert_eq!(first, vec!["first".to_string()]);
+    assert_eq!(second, vec!["second".to_string()]);
+
+    match events.try_recv().unwrap() {
+        Event::Unsolicited(packet) => assert_eq!(packet.payload, "late output"),
+        event => panic!("unexpected event {:?}", event),
+    }
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn discards_responses_to_fired_commands() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let fired = read_id(&mut server).await;
+        let id = read_id(&mut server).await;
+        write_packet(&mut server, fired, "ack").await;
+        write_packet(&mut server, id, "status").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let mut events = connection.monitor().events();
+
+    connection.fire("say hi").await.unwrap();
+    let response = connection.execute_command("status").await.unwrap();
+
+    assert_eq!(response, vec!["status".to_string()]);
+    assert!(events.try_recv().is_err());
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn ignores_keepalives_inside_a_multi_packet_response() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        let sentinel = read_id(&mut server).await;
+        write_packet(&mut server, id, "first").await;
+        write_packet(&mut server, -2, "").await;
+        write_packet(&mut server, id + 100, "").await;
+        write_packet(&mut server, id, "second").await;
+        write_packet(&mut server, sentinel, "").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .keepalive_id(-2)
+        .build()
+        .unwrap();
+    let mut events = connection.monitor().events();
+
+    let response = connection.execute_command("status").await.unwrap();
+
+    assert_eq!(response, vec!["first".to_string(), "second".to_string()]);
+    assert!(events.try_recv().is_err());
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn strict_ids_reject_mismatched_responses() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_packet(&mut server, id + 7, "stale").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .strict_ids(true)
+        .build()
+        .unwrap();
+
+    let error = connection.execute_command("status").await.unwrap_err();
+
+    assert!(matches!(
+        error,
+        Error::IdMismatch {
+            expected: 0,
+            received: 7
+        }
+    ));
+
+    let _server = server.await.unwrap();
+}
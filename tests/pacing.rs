@@ -0,0 +1,187 @@
+use std::time::{Duration, Instant};
+
+use specul::ConnectionBuilder;
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn waits_for_min_command_interval() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let length = server.read_i32_le().await.unwrap();
+            let id = server.read_i32_le().await.unwrap();
+            let mut rest = vec![0; length as usize - 4];
+            server.read_exact(&mut rest).await.unwrap();
+
+            server.write_i32_le(10).await.unwrap();
+            server.write_i32_le(id).await.unwrap();
+            server.write_i32_le(0).await.unwrap();
+            server.write_all(&[0, 0]).await.unwrap();
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .min_command_interval(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    connection.execute_command("one").await.unwrap();
+    connection.execute_command("two").await.unwrap();
+
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    let _server = server.await.unwrap();
+}
+
+/// Answers `count` commands with empty responses.
+fn answer(mut server: tokio::io::DuplexStream, count: usize) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        for _ in 0..count {
+            let length = server.read_i32_le().await.unwrap();
+            let id = server.read_i32_le().await.unwrap();
+            let mut rest = vec![0; length as usize - 4];
+            server.read_exact(&mut rest).await.unwrap();
+
+            server.write_i32_le(10).await.unwrap();
+            server.write_i32_le(id).await.unwrap();
+            server.write_i32_le(0).await.unwrap();
+            server.write_all(&[0, 0]).await.unwrap();
+        }
+    })
+}
+
+#[tokio::test]
+async fn rate_limit_lets_a_burst_through_then_paces() {
+    let (client, server) = duplex(4096);
+    let server = answer(server, 5);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .rate_limit(20.0, 3)
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+    for command in ["one", "two", "three"] {
+        connection.execute_command(command).await.unwrap();
+    }
+    assert!(start.elapsed() < Duration::from_millis(90));
+
+    // Two more need two tokens, refilled every 50ms.
+    connection.execute_commands(["four", "five"]).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    server.await.unwrap();
+}
+
+#[test]
+fn rate_limit_must_be_positive() {
+    let (client, _server) = duplex(4096);
+    assert!(ConnectionBuilder::default()
+        .io(client)
+        .rate_limit(0.0, 1)
+        .build()
+        .is_err());
+
+    let (client, _server) = duplex(4096);
+    assert!(ConnectionBuilder::default()
+        .io(client)
+        .rate_limit(5.0, 0)
+        .build()
+        .is_err());
+}
+
+#[tokio::test]
+async fn rate_limit_paces_the_split_sender() {
+    let (client, server) = duplex(4096);
+    let server = answer(server, 3);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .rate_limit(20.0, 1)
+        .build()
+        .unwrap();
+    let (mut sender, _receiver) = connection.split();
+
+    let start = Instant::now();
+    for command in ["one", "two", "three"] {
+        sender.send_command(command).await.unwrap();
+    }
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    server.await.unwrap();
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn rate_limit_paces_the_client() {
+    let (client, server) = duplex(4096);
+    let server = answer(server, 3);
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .rate_limit(20.0, 1)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    let start = Instant::now();
+    let (one, two, three) = tokio::join!(
+        client.execute_command("one"),
+        client.execute_command("two"),
+        client.execute_command("three")
+    );
+    assert!(one.is_ok() && two.is_ok() && three.is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    drop(client);
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn min_command_interval_paces_the_split_sender() {
+    let (client, server) = duplex(4096);
+    let server = answer(server, 2);
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .min_command_interval(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let (mut sender, _receiver) = connection.split();
+
+    let start = Instant::now();
+    sender.send_command("one").await.unwrap();
+    sender.send_command("two").await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    server.await.unwrap();
+}
+
+#[cfg(feature = "client")]
+#[tokio::test]
+async fn min_command_interval_paces_the_client() {
+    let (client, server) = duplex(4096);
+    let server = answer(server, 2);
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .min_command_interval(Duration::from_millis(100))
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+
+    let start = Instant::now();
+    let (one, two) = tokio::join!(client.execute_command("one"), client.execute_command("two"));
+    assert!(one.is_ok() && two.is_ok());
+    assert!(start.elapsed() >= Duration::from_millis(100));
+
+    drop(client);
+    server.await.unwrap();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use common::{read_typed, write_typed};
+use specul::{ConnectionBuilder, Packet, PacketType};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn sends_and_receives_custom_packet_types() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, packet_type, payload) = read_typed(&mut server).await;
+        assert_eq!((packet_type, payload.as_str()), (100, "subscribe chat"));
+        write_typed(&mut server, id, 101, "subscribed").await;
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .validate_first_packet(false)
+        .build()
+        .unwrap();
+
+    let id = connection.new_packet_id();
+    let packet = Packet::new(id, PacketType::Unknown(100), "subscribe chat".to_string());
+    connection.send_packet(packet).await.unwrap();
+
+    let answer = connection.receive_packet().await.unwrap();
+    assert_eq!(answer.id, id);
+    assert_eq!(answer.packet_type, PacketType::Unknown(101));
+    assert_eq!(answer.payload, "subscribed");
+}
+
+#[tokio::test]
+async fn receives_packets_commands_would_skip() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        write_typed(&mut server, -5, 0, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .keepalive_id(-5)
+        .build()
+        .unwrap();
+
+    let packet = connection.receive_packet().await.unwrap();
+    assert_eq!((packet.id, packet.packet_type), (-5, PacketType::Response));
+}
+
+#[test]
+fn new_packet_ids_skip_the_keepalive_id() {
+    let (client, _server) = duplex(64);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .keepalive_id(1)
+        .build()
+        .unwrap();
+
+    let ids = [connection.new_packet_id(), connection.new_packet_id()];
+    assert!(!ids.contains(&1));
+    assert_ne!(ids[0], ids[1]);
+}
@@ -0,0 +1,133 @@
+use specul::parse::{
+    parse_minecraft_list, parse_pairs, parse_source_status, SteamId, Uuid, DEFAULT_SEPARATORS,
+};
+
+#[test]
+fn parses_every_steam_id_form() {
+    let legacy: SteamId = "STEAM_1:0:11101".parse().unwrap();
+    let modern: SteamId = "[U:1:22202]".parse().unwrap();
+    let id64: SteamId = "76561197960287930".parse().unwrap();
+
+    assert_eq!(legacy, modern);
+    assert_eq!(legacy, id64);
+    assert_eq!(legacy.account_id(), 22202);
+}
+
+#[test]
+fn parses_the_largest_steam_id() {
+    let id: SteamId = "STEAM_0:1:2147483647".parse().unwrap();
+
+    assert_eq!(id.account_id(), u32::MAX);
+}
+
+#[test]
+fn rejects_malformed_steam_ids() {
+    for input in [
+        "",
+        "STEAM_0",
+        "STEAM_0:2:11101",
+        "STEAM_0:1:",
+        "STEAM_0:1:x",
+        "STEAM_0:1:11101:4",
+        "[U:1:nope]",
+        "[U:1:22202",
+        "12345",
+        "not an id",
+    ] {
+        assert!(input.parse::<SteamId>().is_err(), "{:?}", input);
+    }
+}
+
+#[test]
+fn rejects_steam_ids_that_overflow() {
+    for input in [
+        "STEAM_0:1:18446744073709551000",
+        "STEAM_0:1:2147483648",
+        "[U:1:4294967296]",
+        "18446744073709551616",
+    ] {
+        let error = input.parse::<SteamId>().unwrap_err();
+
+        assert!(error.to_string().contains(input));
+    }
+}
+
+#[test]
+fn rejects_malformed_uuids() {
+    for input in [
+        "",
+        "069a79f4-44e9-4726-a5be",
+        "069a79f444e94726a5befca90e38aaf",
+        "069a79f4-44e9-4726-a5be-fca90e38aafz",
+        "069a79f444e9-4726-a5be-fca90e38aaf5",
+        "+69a79f444e94726a5befca90e38aaf5",
+    ] {
+        assert!(input.parse::<Uuid>().is_err(), "{:?}", input);
+    }
+}
+
+#[test]
+fn parses_pairs_with_either_separator() {
+    let pairs = parse_pairs(
+        "hostname: test\nsv_cheats = 0\nno separator\n",
+        DEFAULT_SEPARATORS,
+    );
+
+    assert_eq!(pairs["hostname"], "test");
+    assert_eq!(pairs["sv_cheats"], "0");
+    assert_eq!(pairs.len(), 2);
+}
+
+#[test]
+fn collects_malformed_source_players() {
+    let players = parse_source_status(
+        "hostname: test\n\
+         # userid name uniqueid connected ping loss state adr\n\
+         #      2 \"Gordon\" STEAM_1:0:11101 00:35 50 0 active 10.0.0.7:27005\n\
+         #      3 \"Alyx\" STEAM_0:1:18446744073709551000 01:10 40 0 active\n\
+         #      4 \"Barney\" [U:1:3] 01:10 slow 0 active\n\
+         #      5 \"Unclosed\n\
+         #      6 \"Bot\" BOT active\n\
+         #end\n\
+         # 7 \"After\" [U:1:4] 00:01 10 0 active\n",
+    );
+
+    assert_eq!(players.players.len(), 1);
+    assert_eq!(players.players[0].userid, 2);
+    assert_eq!(
+        players.players[0].address,
+        Some("10.0.0.7:27005".parse().unwrap())
+    );
+    assert_eq!(players.malformed.len(), 3);
+    assert!(players.malformed[0].contains("Alyx"));
+}
+
+#[test]
+fn parses_cs2_players_without_a_steam_id() {
+    let players = parse_source_status(
+        "---------players--------\n\
+         \x20 id     time ping loss      state   rate adr name\n\
+         \x20  2    01:23   50    0     active 786432 10.0.0.7:27005 'Gordon'\n\
+         \x20 65      BOT    0    0     active      0 'Bot'\n\
+         \x20  x    01:23   50    0     active 786432 10.0.0.8:27005 'Broken'\n\
+         #end\n",
+    );
+
+    assert_eq!(players.players.len(), 1);
+    assert_eq!(players.players[0].name, "Gordon");
+    assert_eq!(players.players[0].steam_id, None);
+    assert_eq!(players.malformed.len(), 1);
+}
+
+#[test]
+fn parses_minecraft_lists() {
+    let players = parse_minecraft_list("There are 0 of a max of 20 players online:");
+    assert!(players.players.is_empty());
+
+    let players = parse_minecraft_list("There are 2 of a max of 20 players online: Steve, Alex");
+    let names: Vec<_> = players.players.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Steve", "Alex"]);
+    assert!(players.malformed.is_empty());
+
+    assert!(parse_minecraft_list("Unknown command").players.is_empty());
+}
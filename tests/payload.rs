@@ -0,0 +1,75 @@
+use bytes::BytesMut;
+use specul::{codec::RconCodec, ConnectionBuilder, Packet, PacketType};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+fn frame(id: i32, payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(10 + payload.len() as i32).to_le_bytes());
+    bytes.extend_from_slice(&id.to_le_bytes());
+    bytes.extend_from_slice(&0i32.to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&[0, 0]);
+    bytes
+}
+
+#[test]
+fn decodes_payloads_without_copying() {
+    let mut buffer = BytesMut::from(&frame(1, b"console output")[..]);
+    let start = buffer.as_ptr() as usize;
+    let end = start + buffer.len();
+
+    let packet = RconCodec::default()
+        .decode_packet(&mut buffer)
+        .unwrap()
+        .unwrap();
+
+    let payload = packet.payload.as_ptr() as usize;
+    assert!((start..end).contains(&payload), "payload was copied");
+    assert_eq!(packet.to_str().unwrap(), "console output");
+}
+
+#[test]
+fn keeps_packets_that_are_not_utf8() {
+    let mut buffer = BytesMut::new();
+    buffer.extend_from_slice(&frame(1, b"caf\xe9"));
+    buffer.extend_from_slice(&frame(2, b"next"));
+
+    let mut codec = RconCodec::default();
+    let first = codec.decode_packet(&mut buffer).unwrap().unwrap();
+    let second = codec.decode_packet(&mut buffer).unwrap().unwrap();
+
+    assert_eq!(first.as_bytes(), b"caf\xe9");
+    assert!(first.to_str().is_err());
+    assert_eq!(second.to_str().unwrap(), "next");
+}
+
+#[test]
+fn builds_packets_from_text_or_bytes() {
+    let text = Packet::new(1, PacketType::Message, "status".to_string());
+    let bytes = Packet::new(1, PacketType::Message, &b"status"[..]);
+
+    assert_eq!(text, bytes);
+    assert_eq!(text.length, 16);
+}
+
+#[tokio::test]
+async fn exposes_the_response_as_bytes_and_text() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let _length = server.read_i32_le().await.unwrap();
+        let id = server.read_i32_le().await.unwrap();
+        let mut rest = vec![0; 4 + "status".len() + 2];
+        server.read_exact(&mut rest).await.unwrap();
+        server
+            .write_all(&frame(id, "map: de_dust2".as_bytes()))
+            .await
+            .unwrap();
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let response = connection.execute("status").await.unwrap();
+
+    assert_eq!(response.to_str(), "map: de_dust2");
+    assert_eq!(response.as_bytes(), b"map: de_dust2");
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{read_typed, write_typed};
+use specul::{ConnectionBuilder, Error};
+use tokio::io::duplex;
+
+const RESPONSE_VALUE: i32 = 0;
+const EXECCOMMAND: i32 = 2;
+
+#[tokio::test]
+async fn pings_with_an_empty_response_value() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, packet_type, payload) = read_typed(&mut server).await;
+        assert_eq!((packet_type, payload.as_str()), (RESPONSE_VALUE, ""));
+
+        // The mirror, then the marker Source servers send after it.
+        write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "\0\u{1}\0\0").await;
+
+        let (id, _, command) = read_typed(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, &format!("ran {}", command)).await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    connection.ping().await.unwrap();
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        vec!["ran status".to_string()]
+    );
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn pings_with_the_configured_command() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, packet_type, command) = read_typed(&mut server).await;
+        assert_eq!((packet_type, command.as_str()), (EXECCOMMAND, "echo"));
+        write_typed(&mut server, id, RESPONSE_VALUE, "").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .ping_command("echo")
+        .build()
+        .unwrap();
+
+    assert!(connection.is_alive().await);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn ping_reports_a_vanished_server() {
+    let (client, server) = duplex(4096);
+    drop(server);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert!(matches!(connection.ping().await, Err(Error::Disconnected)));
+}
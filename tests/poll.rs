@@ -0,0 +1,64 @@
+use std::future::poll_fn;
+
+use specul::{ConnectionBuilder, Error};
+use tokio::io::{duplex, AsyncWriteExt};
+
+#[tokio::test]
+async fn polls_packets_by_hand() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let server = tokio::spawn(async move {
+        for payload in ["spawned", "joined"] {
+            server
+                .write_i32_le(10 + payload.len() as i32)
+                .await
+                .unwrap();
+            server.write_i32_le(0).await.unwrap();
+            server.write_i32_le(0).await.unwrap();
+            server.write_all(payload.as_bytes()).await.unwrap();
+            server.write_all(&[0, 0]).await.unwrap();
+        }
+        server
+    });
+
+    let first = poll_fn(|cx| connection.poll_next_packet(cx)).await.unwrap();
+    let second = poll_fn(|cx| connection.poll_next_packet(cx)).await.unwrap();
+
+    assert_eq!(first.payload, "spawned");
+    assert_eq!(second.payload, "joined");
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn buffered_packets_stop_at_a_malformed_one() {
+    let (client, mut server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    let mut bytes = Vec::new();
+    for payload in ["one", "two", "three"] {
+        bytes.extend((10 + payload.len() as i32).to_le_bytes());
+        bytes.extend([0; 8]);
+        bytes.extend(payload.as_bytes());
+        bytes.extend([0, 0]);
+    }
+    // A length too short to hold an id and a type.
+    bytes.extend(4i32.to_le_bytes());
+    bytes.extend([0; 10]);
+    server.write_all(&bytes).await.unwrap();
+
+    // Polling reads everything the server sent into the buffer.
+    let first = poll_fn(|cx| connection.poll_next_packet(cx)).await.unwrap();
+    assert_eq!(first.payload, "one");
+
+    let buffered = connection.try_read_buffered_packets().unwrap();
+    let payloads: Vec<_> = buffered.iter().map(|packet| &packet.payload[..]).collect();
+    assert_eq!(payloads, [&b"two"[..], b"three"]);
+
+    let error = connection.try_read_buffered_packets().unwrap_err();
+    assert!(matches!(error, Error::MalformedPacket(_)));
+    assert!(connection.last_error().is_some());
+}
@@ -0,0 +1,112 @@
+#![cfg(all(feature = "pool", feature = "testing"))]
+
+use specul::{
+    pool::{Pool, PoolOptionsBuilder, PoolStatus},
+    testing::{MockServer, Reply},
+};
+
+async fn mock() -> MockServer {
+    MockServer::builder("password")
+        .respond("status", Reply::text("hostname: test"))
+        .start()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn opens_min_size_connections_up_front() {
+    let mock = mock().await;
+    let options = PoolOptionsBuilder::default()
+        .min_size(2)
+        .max_size(4)
+        .build()
+        .unwrap();
+
+    let pool = Pool::connect(&mock.addr().to_string(), "password", options)
+        .await
+        .unwrap();
+
+    assert_eq!(pool.status(), PoolStatus { size: 2, idle: 2 });
+    assert_eq!(
+        pool.execute("status").await.unwrap(),
+        vec!["hostname: test".to_string()]
+    );
+    assert_eq!(pool.status(), PoolStatus { size: 2, idle: 2 });
+}
+
+#[tokio::test]
+async fn callers_beyond_max_size_wait_their_turn() {
+    let mock = mock().await;
+    let options = PoolOptionsBuilder::default()
+        .min_size(0)
+        .max_size(1)
+        .build()
+        .unwrap();
+
+    let pool = Pool::connect(&mock.addr().to_string(), "password", options)
+        .await
+        .unwrap();
+
+    let (first, second, third) = tokio::join!(
+        pool.execute("status"),
+        pool.execute("status"),
+        pool.execute("status")
+    );
+
+    for response in [first, second, third] {
+        assert_eq!(response.unwrap(), vec!["hostname: test".to_string()]);
+    }
+    assert_eq!(pool.status(), PoolStatus { size: 1, idle: 1 });
+}
+
+#[tokio::test]
+async fn replaces_closed_connections() {
+    let mock = mock().await;
+    let pool = Pool::connect(&mock.addr().to_string(), "password", Default::default())
+        .await
+        .unwrap();
+
+    let mut connection = pool.get().await.unwrap();
+    connection.close().await.unwrap();
+    drop(connection);
+
+    assert_eq!(pool.status(), PoolStatus { size: 0, idle: 0 });
+
+    assert_eq!(
+        pool.execute("status").await.unwrap(),
+        vec!["hostname: test".to_string()]
+    );
+    assert_eq!(pool.status(), PoolStatus { size: 1, idle: 1 });
+
+    pool.get().await.unwrap().discard();
+    pool.fill().await.unwrap();
+
+    assert_eq!(pool.status(), PoolStatus { size: 1, idle: 1 });
+}
+
+#[tokio::test]
+async fn runs_the_health_check_on_idle_connections() {
+    let mock = mock().await;
+    let options = PoolOptionsBuilder::default()
+        .health_check("status")
+        .health_check_after(std::time::Duration::ZERO)
+        .build()
+        .unwrap();
+
+    let pool = Pool::connect(&mock.addr().to_string(), "password", options)
+        .await
+        .unwrap();
+    let monitor = pool.get().await.unwrap().monitor();
+
+    assert_eq!(monitor.stats().commands, 1);
+}
+
+#[test]
+fn rejects_min_size_above_max_size() {
+    let result = PoolOptionsBuilder::default()
+        .min_size(3)
+        .max_size(2)
+        .build();
+
+    assert!(result.is_err());
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use common::{read_id, write_packet};
+use specul::{ConnectionBuilder, Error, Quirks};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn minecraft_reassembles_fragmented_responses() {
+    let (client, mut server) = duplex(16 * 1024);
+    let first = "a".repeat(4096);
+
+    let server = tokio::spawn({
+        let first = first.clone();
+        async move {
+            let id = read_id(&mut server).await;
+            let sentinel = read_id(&mut server).await;
+
+            write_packet(&mut server, id, &first).await;
+            write_packet(&mut server, id, "tail").await;
+            write_packet(&mut server, sentinel, "Unknown request 0").await;
+            server
+        }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .quirks(Quirks::Minecraft)
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("list").await.unwrap();
+
+    assert_eq!(response, vec![first + "tail"]);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn minecraft_rejects_commands_over_1446_bytes() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .quirks(Quirks::Minecraft)
+        .build()
+        .unwrap();
+
+    let result = connection.execute_command(&"x".repeat(1447)).await;
+
+    assert!(matches!(result, Err(Error::PayloadSize)));
+}
+
+/// Factorio's answer to authenticating with packet id 0.
+const FACTORIO_AUTH_RESPONSE: &[u8] = b"\x0a\x00\x00\x00\x00\x00\x00\x00\x02\x00\x00\x00\x00\x00";
+
+/// Factorio's answer to `/players online` sent with packet id 1.
+const FACTORIO_PLAYERS_RESPONSE: &[u8] = b">\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00Online players (2):\x0a  alice (online)\x0a  bob (online)\x0a\x00\x00";
+
+#[tokio::test]
+async fn factorio_reads_single_responses_without_a_sentinel() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        assert_eq!(read_id(&mut server).await, 0);
+        server.write_all(FACTORIO_AUTH_RESPONSE).await.unwrap();
+
+        assert_eq!(read_id(&mut server).await, 1);
+        server.write_all(FACTORIO_PLAYERS_RESPONSE).await.unwrap();
+
+        // Nothing but the command may follow, since Factorio answers a
+        // sentinel with an error.
+        let mut rest = Vec::new();
+        server.read_to_end(&mut rest).await.unwrap();
+        assert!(rest.is_empty());
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .quirks(Quirks::Factorio)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+
+    connection.authenticate("password").await.unwrap();
+    let response = connection.execute_command("/players online").await.unwrap();
+
+    assert_eq!(
+        response,
+        vec!["Online players (2):\n  alice (online)\n  bob (online)\n".to_string()]
+    );
+
+    connection.close().await.unwrap();
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn factorio_limits_commands_to_one_packet() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .quirks(Quirks::Factorio)
+        .max_payload_size(8192)
+        .build()
+        .unwrap();
+
+    let result = connection.execute_command(&"x".repeat(4087)).await;
+
+    assert!(matches!(result, Err(Error::PayloadSize)));
+}
@@ -0,0 +1,271 @@
+mod common;
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use common::{read_id, write_typed};
+use specul::{Backoff, ConnectionBuilder, Connector, Error, Event, State};
+use tokio::io::{duplex, AsyncWriteExt, DuplexStream};
+
+const RESPONSE_VALUE: i32 = 0;
+const AUTH_RESPONSE: i32 = 2;
+
+#[tokio::test]
+async fn reconnects_and_retries_after_the_server_restarts() {
+    let (first, mut crashing) = duplex(4096);
+    let (second, mut restarted) = duplex(4096);
+
+    let crashing = tokio::spawn(async move {
+        let id = read_id(&mut crashing).await;
+        write_typed(&mut crashing, id, AUTH_RESPONSE, "").await;
+    });
+
+    let restarted = tokio::spawn(async move {
+        let id = read_id(&mut restarted).await;
+        write_typed(&mut restarted, id, AUTH_RESPONSE, "").await;
+        let id = read_id(&mut restarted).await;
+        write_typed(&mut restarted, id, RESPONSE_VALUE, "back").await;
+        restarted
+    });
+
+    let streams = Arc::new(Mutex::new(vec![second]));
+    let connector = Connector::new(move || {
+        let stream = streams.lock().unwrap().pop();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .auto_reconnect(Backoff {
+            initial: Duration::from_millis(10),
+            ..Backoff::default()
+        })
+        .build()
+        .unwrap();
+    let mut events = connection.monitor().events();
+
+    connection.authenticate("password").await.unwrap();
+    crashing.await.unwrap();
+
+    let response = connection.execute_command("status").await.unwrap();
+
+    assert_eq!(response, vec!["back".to_string()]);
+    assert_eq!(events.try_recv().unwrap(), Event::Reconnecting);
+    assert_eq!(events.try_recv().unwrap(), Event::Reconnected);
+
+    let _restarted = restarted.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_retry_does_not_resend_a_rejected_password() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        read_id(&mut server).await;
+        write_typed(&mut server, -1, AUTH_RESPONSE, "").await;
+        server
+    });
+
+    let dials = Arc::new(Mutex::new(0));
+    let counted = dials.clone();
+    let connector = Connector::new(move || {
+        *counted.lock().unwrap() += 1;
+        async { Err::<DuplexStream, _>(io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .connector(connector)
+        .build()
+        .unwrap();
+
+    let result = connection.authenticate_retry("wrong", 3).await;
+
+    assert!(matches!(result, Err(Error::Authentication)));
+    assert_eq!(connection.state(), State::Failed);
+    assert_eq!(*dials.lock().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn authenticate_retry_reconnects_after_io_errors() {
+    let (first, closed) = duplex(4096);
+    let (second, mut server) = duplex(4096);
+    drop(closed);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+        server
+    });
+
+    let streams = Arc::new(Mutex::new(vec![second]));
+    let connector = Connector::new(move || {
+        let stream = streams.lock().unwrap().pop();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .build()
+        .unwrap();
+
+    connection.authenticate_retry("password", 1).await.unwrap();
+
+    assert!(connection.is_authenticated());
+    let _server = server.await.unwrap();
+}
+
+/// Answers authentication, then `status` with `reply` written as it is.
+fn desyncing(mut server: DuplexStream, reply: Vec<u8>) -> tokio::task::JoinHandle<DuplexStream> {
+    tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+        read_id(&mut server).await;
+        server.write_all(&reply).await.unwrap();
+        server
+    })
+}
+
+/// Answers authentication, then one command with `fresh`.
+fn fresh(mut server: DuplexStream) -> tokio::task::JoinHandle<DuplexStream> {
+    tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+        let id = read_id(&mut server).await;
+        write_typed(&mut server, id, RESPONSE_VALUE, "fresh").await;
+        server
+    })
+}
+
+async fn resyncs_after(reply: Vec<u8>, check: fn(&Error) -> bool) {
+    let (first, broken) = duplex(4096);
+    let (second, restarted) = duplex(4096);
+    let broken = desyncing(broken, reply);
+    let restarted = fresh(restarted);
+
+    let streams = Arc::new(Mutex::new(vec![second]));
+    let connector = Connector::new(move || {
+        let stream = streams.lock().unwrap().pop();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .reconnect_on_desync(true)
+        .strict_ids(true)
+        .build()
+        .unwrap();
+    let mut events = connection.monitor().events();
+
+    connection.authenticate("password").await.unwrap();
+
+    let error = connection.execute_command("status").await.unwrap_err();
+    assert!(check(&error), "{:?}", error);
+    assert_eq!(events.try_recv().unwrap(), Event::Reconnecting);
+    assert_eq!(events.try_recv().unwrap(), Event::Reconnected);
+
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        ["fresh"]
+    );
+
+    let _broken = broken.await.unwrap();
+    let _restarted = restarted.await.unwrap();
+}
+
+#[tokio::test]
+async fn resyncs_after_a_malformed_packet() {
+    let mut reply = 4i32.to_le_bytes().to_vec();
+    reply.extend([0; 10]);
+
+    resyncs_after(reply, |error| matches!(error, Error::MalformedPacket(_))).await;
+}
+
+#[tokio::test]
+async fn resyncs_after_a_mismatched_id() {
+    let mut reply = 14i32.to_le_bytes().to_vec();
+    reply.extend(1234i32.to_le_bytes());
+    reply.extend(RESPONSE_VALUE.to_le_bytes());
+    reply.extend(b"late\0\0");
+
+    resyncs_after(reply, |error| matches!(error, Error::IdMismatch { .. })).await;
+}
+
+#[tokio::test]
+async fn authenticate_retry_waits_and_keeps_dialing() {
+    let (first, closed) = duplex(4096);
+    let (second, mut server) = duplex(4096);
+    drop(closed);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+        server
+    });
+
+    // Two dials are refused before the server is back.
+    let dials = Arc::new(Mutex::new(vec![Some(second), None, None]));
+    let connector = Connector::new(move || {
+        let stream = dials.lock().unwrap().pop().flatten();
+        async move { stream.ok_or_else(|| io::ErrorKind::ConnectionRefused.into()) }
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(first)
+        .connector(connector)
+        .auto_reconnect(Backoff {
+            initial: Duration::from_millis(20),
+            multiplier: 1,
+            ..Backoff::default()
+        })
+        .build()
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    connection.authenticate_retry("password", 3).await.unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(60));
+    assert!(connection.is_authenticated());
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn authenticate_retry_returns_the_last_real_error() {
+    let (client, closed) = duplex(4096);
+    drop(closed);
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    // Without a connector, the failed attempt's own error is returned.
+    assert!(matches!(
+        connection.authenticate_retry("password", 3).await,
+        Err(Error::Io(_))
+    ));
+
+    let (client, closed) = duplex(4096);
+    drop(closed);
+    let connector = Connector::new(|| async {
+        Err::<DuplexStream, _>(io::ErrorKind::ConnectionRefused.into())
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .connector(connector)
+        .auto_reconnect(Backoff {
+            initial: Duration::from_millis(1),
+            ..Backoff::default()
+        })
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.authenticate_retry("password", 2).await,
+        Err(Error::Io(error)) if error.kind() == io::ErrorKind::ConnectionRefused
+    ));
+}
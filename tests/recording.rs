@@ -0,0 +1,127 @@
+#![cfg(feature = "recording")]
+
+mod common;
+
+use std::io;
+
+use common::{read_packet, write_typed};
+use specul::{
+    recording::{Chunk, Recording, RecordingTransport, ReplayTransport},
+    ConnectionBuilder, Error,
+};
+use tokio::io::{duplex, DuplexStream};
+
+/// Accepts the password, then answers two commands.
+fn server(mut server: DuplexStream) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        write_typed(&mut server, id, 0, "").await;
+        write_typed(&mut server, id, 2, "").await;
+
+        for response in ["hostname: test", "Unknown command \"foo\""] {
+            let (id, _) = read_packet(&mut server).await;
+            write_typed(&mut server, id, 0, response).await;
+        }
+    })
+}
+
+async fn record() -> Recording {
+    let (client, server_io) = duplex(16 * 1024);
+    let server = server(server_io);
+
+    let transport = RecordingTransport::new(client);
+    let recorder = transport.recorder();
+
+    let mut connection = ConnectionBuilder::default().io(transport).build().unwrap();
+    connection.authenticate("hunter2").await.unwrap();
+    connection.execute_command("status").await.unwrap();
+    connection.execute_command("foo").await.unwrap();
+    server.await.unwrap();
+
+    recorder.recording()
+}
+
+#[tokio::test]
+async fn recordings_replay_the_session() {
+    let recording = record().await;
+
+    assert_eq!(recording.chunks().len(), 6);
+    assert!(matches!(recording.chunks()[0], Chunk::Sent(_)));
+    assert!(matches!(recording.chunks()[1], Chunk::Received(_)));
+
+    let saved = recording.to_string();
+    assert!(saved.starts_with("# specul recording"));
+    let loaded: Recording = saved.parse().unwrap();
+    assert_eq!(loaded, recording);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(ReplayTransport::new(loaded))
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        ["hostname: test"]
+    );
+    assert_eq!(
+        connection.execute_command("foo").await.unwrap(),
+        ["Unknown command \"foo\""]
+    );
+
+    // Nothing more was recorded.
+    match connection.execute_command("status").await {
+        Err(Error::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::BrokenPipe),
+        result => panic!("{:?}", result),
+    }
+}
+
+#[tokio::test]
+async fn replays_fail_when_the_client_diverges() {
+    let recording = record().await;
+
+    let mut connection = ConnectionBuilder::default()
+        .io(ReplayTransport::new(recording))
+        .build()
+        .unwrap();
+
+    connection.authenticate("hunter2").await.unwrap();
+
+    match connection.execute_command("users").await {
+        Err(Error::Io(error)) => {
+            assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+            assert!(error.to_string().contains("replay diverged"), "{}", error);
+        }
+        result => panic!("{:?}", result),
+    }
+}
+
+#[test]
+fn recordings_parse_comments_and_wrapped_lines() {
+    let recording = Recording::parse(
+        "# a comment\n\
+         > 0a0b\n\
+         > 0c\n\
+         \n\
+         < ff 00\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        recording.chunks(),
+        [Chunk::Sent(vec![10, 11, 12]), Chunk::Received(vec![255, 0])]
+    );
+
+    for text in ["> abc", "> zz", "? 00", "é 00"] {
+        let error = Recording::parse(text).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", text);
+    }
+}
+
+#[test]
+fn recordings_reject_signs_and_non_ascii_digits() {
+    for text in ["> aé1", "> +f", "> é", "< 0-1", "> ００"] {
+        let error = Recording::parse(text).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", text);
+    }
+}
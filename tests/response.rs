@@ -0,0 +1,70 @@
+mod common;
+
+use common::{read_id, write_packet};
+use specul::{parse::DEFAULT_SEPARATORS, ConnectionBuilder, Error};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn describes_a_multi_packet_response() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        let sentinel = read_id(&mut server).await;
+
+        write_packet(&mut server, id, "hostname: test\n").await;
+        write_packet(&mut server, id, "players: 3").await;
+        write_packet(&mut server, sentinel, "").await;
+        write_packet(&mut server, sentinel, "\0\u{1}\0\0").await;
+        (server, id)
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .current_packet_id(7)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+
+    let response = connection.execute("status").await.unwrap();
+    let (_server, id) = server.await.unwrap();
+
+    assert_eq!(response, "hostname: test\nplayers: 3");
+    assert_eq!(response.payloads, ["hostname: test\n", "players: 3"]);
+    assert_eq!(response.request_id, 7);
+    assert_eq!(response.request_id, id);
+    assert_eq!(response.response_ids, [7, 7]);
+    assert_eq!(response.packets(), 2);
+    assert!(response.lines().any(|line| line == "players: 3"));
+    assert_eq!(response.to_string(), response.body);
+
+    let pairs = response.parse_pairs(DEFAULT_SEPARATORS);
+    assert_eq!(pairs["hostname"], "test");
+    assert_eq!(pairs["players"], "3");
+}
+
+#[tokio::test]
+async fn checked_commands_fail_on_an_error_packet() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let id = read_id(&mut server).await;
+        write_packet(&mut server, id, "cvar set").await;
+        read_id(&mut server).await;
+        write_packet(&mut server, -1, "no such cvar").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+
+    assert_eq!(
+        connection.execute_checked("sv_cheats 0").await.unwrap(),
+        "cvar set"
+    );
+    match connection.execute_checked("sv_nope 1").await {
+        Err(Error::ServerError(payload)) => assert_eq!(payload, "no such cvar"),
+        result => panic!("{:?}", result),
+    }
+
+    let _server = server.await.unwrap();
+}
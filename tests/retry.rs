@@ -0,0 +1,123 @@
+mod common;
+
+use std::{io, time::Duration};
+
+use common::{read_packet, write_packet};
+use specul::{ConnectionBuilder, Connector, Error, RetryPolicy};
+use tokio::io::duplex;
+
+fn policy(max_attempts: usize) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial: Duration::from_millis(5),
+        max: Duration::from_millis(20),
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn classifies_errors() {
+    assert!(Error::Timeout.is_retriable());
+    assert!(Error::Disconnected.is_retriable());
+    assert!(Error::Io(io::ErrorKind::ConnectionReset.into()).is_retriable());
+
+    assert!(!Error::Authentication.is_retriable());
+    assert!(!Error::PayloadSize.is_retriable());
+    assert!(!Error::ConnectionClosed.is_retriable());
+    assert!(!Error::ServerError("denied".to_string()).is_retriable());
+    assert!(!Error::Io(io::ErrorKind::InvalidInput.into()).is_retriable());
+}
+
+#[tokio::test]
+async fn retries_timeouts_until_answered() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        // The first attempt goes unanswered.
+        let (_, first) = read_packet(&mut server).await;
+        let (id, second) = read_packet(&mut server).await;
+        assert_eq!((first.as_str(), second.as_str()), ("status", "status"));
+        write_packet(&mut server, id, "hostname: retried").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_deadline(Duration::from_millis(50))
+        .retry_policy(policy(3))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        ["hostname: retried"]
+    );
+    assert_eq!(connection.stats().commands, 2);
+
+    let _server = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn gives_up_after_max_attempts() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .command_deadline(Duration::from_millis(20))
+        .retry_policy(policy(3))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.execute_command("status").await,
+        Err(Error::Timeout)
+    ));
+    assert_eq!(connection.stats().commands, 3);
+}
+
+#[tokio::test]
+async fn redials_when_the_server_went_away() {
+    let (client, server) = duplex(4096);
+    drop(server);
+
+    let connector = Connector::new(|| async {
+        let (client, mut server) = duplex(4096);
+
+        tokio::spawn(async move {
+            let (id, command) = read_packet(&mut server).await;
+            write_packet(&mut server, id, &format!("ran {}", command)).await;
+            server
+        });
+
+        Ok(client)
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .connector(connector)
+        .retry_policy(policy(2))
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.execute_command("status").await.unwrap(),
+        ["ran status"]
+    );
+}
+
+#[tokio::test]
+async fn does_not_retry_other_errors() {
+    let (client, _server) = duplex(4096);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .retry_policy(policy(3))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        connection.execute_command(&"x".repeat(10_000)).await,
+        Err(Error::PayloadSize)
+    ));
+    assert_eq!(connection.stats().commands, 0);
+}
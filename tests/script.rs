@@ -0,0 +1,67 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use common::echo;
+use specul::{ConnectionBuilder, Error, OnError, ScriptOptions};
+use tokio::io::duplex;
+
+const SCRIPT: &str = "# Set up the match\n\
+                      \n\
+                      sv_cheats 0\n\
+                      // too long for the server\n\
+                      say this line is far too long\n\
+                      \x20 mp_restartgame 1 \n";
+
+#[tokio::test]
+async fn scripts_skip_comments_and_stop_at_errors() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 1);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(20)
+        .build()
+        .unwrap();
+
+    let lines = connection.run_script(SCRIPT.as_bytes()).await.unwrap();
+
+    assert_eq!(server.await.unwrap(), ["sv_cheats 0"]);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(
+        (lines[0].line, lines[0].command.as_str()),
+        (3, "sv_cheats 0")
+    );
+    assert_eq!(lines[0].result.as_ref().unwrap().body, "sv_cheats 0");
+    assert_eq!(lines[1].line, 5);
+    assert!(matches!(lines[1].result, Err(Error::PayloadSize)));
+}
+
+#[tokio::test]
+async fn scripts_can_continue_past_errors_with_a_delay() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 2);
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .max_payload_size(20)
+        .build()
+        .unwrap();
+
+    let options = ScriptOptions {
+        delay: Duration::from_millis(50),
+        on_error: OnError::Continue,
+    };
+    let started = Instant::now();
+    let lines = connection
+        .run_script_with(SCRIPT.as_bytes(), options)
+        .await
+        .unwrap();
+
+    assert!(started.elapsed() >= Duration::from_millis(100));
+    assert_eq!(server.await.unwrap(), ["sv_cheats 0", "mp_restartgame 1"]);
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].result.is_err());
+    assert_eq!(lines[2].line, 6);
+    assert_eq!(lines[2].result.as_ref().unwrap().body, "mp_restartgame 1");
+}
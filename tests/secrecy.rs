@@ -0,0 +1,50 @@
+mod common;
+
+use common::{read_typed, write_typed};
+use specul::{ConnectionBuilder, Packet, PacketType};
+use tokio::io::duplex;
+
+const AUTH_RESPONSE: i32 = 2;
+
+#[test]
+fn redacts_authentication_payloads_in_debug() {
+    let auth = Packet::new(1, PacketType::Authentication, "hunter2".to_string());
+    let command = Packet::new(2, PacketType::Message, "status".to_string());
+
+    assert!(!format!("{:?}", auth).contains("hunter2"));
+    assert!(format!("{:?}", command).contains("status"));
+}
+
+#[tokio::test]
+async fn keeps_the_password_out_of_connection_debug() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, _, password) = read_typed(&mut server).await;
+        assert_eq!(password, "hunter2");
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    connection.authenticate("hunter2").await.unwrap();
+
+    assert!(!format!("{:?}", connection).contains("hunter2"));
+}
+
+#[cfg(feature = "secrecy")]
+#[tokio::test]
+async fn authenticates_with_a_secret_string() {
+    let (client, mut server) = duplex(4096);
+
+    tokio::spawn(async move {
+        let (id, packet_type, password) = read_typed(&mut server).await;
+        assert_eq!((packet_type, password.as_str()), (3, "hunter2"));
+        write_typed(&mut server, id, AUTH_RESPONSE, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let password = secrecy::SecretString::from("hunter2");
+    connection.authenticate_secret(password).await.unwrap();
+
+    assert_eq!(connection.state(), specul::State::Authenticated);
+}
@@ -0,0 +1,137 @@
+#![cfg(feature = "serde")]
+
+use std::time::Duration;
+
+use serde_json::json;
+use specul::{Backoff, ConnectionConfig, Packet, PacketType, Quirks, RetryPolicy};
+
+#[test]
+fn packets_serialize_with_text_payloads() {
+    let packet = Packet::new(7, PacketType::Response, "hostname: My Server");
+    let value = serde_json::to_value(&packet).unwrap();
+
+    assert_eq!(
+        value,
+        json!({
+            "id": 7,
+            "length": 29,
+            "packet_type": "Response",
+            "payload": "hostname: My Server",
+        })
+    );
+    assert_eq!(serde_json::from_value::<Packet>(value).unwrap(), packet);
+}
+
+#[test]
+fn binary_payloads_serialize_as_bytes() {
+    let packet = Packet::new(1, PacketType::Unknown(9), &b"caf\xe9"[..]);
+    let value = serde_json::to_value(&packet).unwrap();
+
+    assert_eq!(value["payload"], json!([99, 97, 102, 233]));
+    assert_eq!(value["packet_type"], json!({ "Unknown": 9 }));
+    assert_eq!(serde_json::from_value::<Packet>(value).unwrap(), packet);
+}
+
+#[test]
+fn passwords_are_masked() {
+    let packet = Packet::new(1, PacketType::Authentication, "hunter2");
+    let value = serde_json::to_value(&packet).unwrap();
+
+    assert_eq!(value["payload"], "*******");
+    assert_eq!(value["length"], packet.length);
+    assert!(!value.to_string().contains("hunter2"));
+}
+
+#[test]
+fn packet_lengths_may_be_left_out() {
+    let packet: Packet = serde_json::from_value(json!({
+        "id": 3,
+        "packet_type": "Message",
+        "payload": "status",
+    }))
+    .unwrap();
+
+    assert_eq!(packet, Packet::new(3, PacketType::Message, "status"));
+    assert_eq!(packet.length, 16);
+}
+
+#[test]
+fn connection_configs_load_from_json() {
+    let config: ConnectionConfig = serde_json::from_value(json!({
+        "host": "10.0.0.5",
+        "password": "hunter2",
+        "timeout": "5s",
+        "read_timeout": 2,
+        "command_deadline": "1.5m",
+        "quirks": "minecraft",
+    }))
+    .unwrap();
+
+    assert_eq!(config.addr(), "10.0.0.5:27015");
+    assert_eq!(config.password, "hunter2");
+    assert_eq!(config.timeout, Some(Duration::from_secs(5)));
+    assert_eq!(config.read_timeout, Some(Duration::from_secs(2)));
+    assert_eq!(config.command_deadline, Some(Duration::from_secs(90)));
+    assert_eq!(config.quirks, Some(Quirks::Minecraft));
+    assert_eq!(config.multiple_responses, None);
+
+    assert_eq!(
+        serde_json::to_value(&config).unwrap(),
+        json!({
+            "host": "10.0.0.5",
+            "port": 27015,
+            "password": "hunter2",
+            "timeout": "5s",
+            "command_deadline": "90s",
+            "read_timeout": "2s",
+            "quirks": "minecraft",
+        })
+    );
+}
+
+#[test]
+fn connection_configs_reject_unknown_fields_and_bad_durations() {
+    let typo = serde_json::from_value::<ConnectionConfig>(json!({
+        "host": "10.0.0.5",
+        "timout": "5s",
+    }));
+    assert!(typo.unwrap_err().to_string().contains("timout"));
+
+    let bad = serde_json::from_value::<ConnectionConfig>(json!({
+        "host": "10.0.0.5",
+        "timeout": "soon",
+    }));
+    assert!(bad.unwrap_err().to_string().contains("soon"));
+}
+
+#[test]
+fn policies_fill_in_defaults() {
+    let policy: RetryPolicy = serde_json::from_value(json!({
+        "max_attempts": 5,
+        "initial": "250ms",
+    }))
+    .unwrap();
+
+    assert_eq!(
+        policy,
+        RetryPolicy {
+            max_attempts: 5,
+            initial: Duration::from_millis(250),
+            ..RetryPolicy::default()
+        }
+    );
+    assert_eq!(
+        serde_json::to_value(policy).unwrap(),
+        json!({
+            "max_attempts": 5,
+            "initial": "250ms",
+            "max": "2s",
+            "multiplier": 2,
+            "jitter": 0.2,
+        })
+    );
+
+    let backoff: Backoff = serde_json::from_value(json!({ "max": "1m" })).unwrap();
+    assert_eq!(backoff.max, Duration::from_secs(60));
+    assert_eq!(backoff.initial, Backoff::default().initial);
+}
@@ -0,0 +1,85 @@
+#![cfg(feature = "server")]
+
+use specul::{
+    server::{CommandHandler, Server},
+    Connection, ConnectionBuilder, Error,
+};
+use tokio::net::TcpStream;
+
+struct Echo;
+
+impl CommandHandler for Echo {
+    async fn handle(&self, command: &str) -> String {
+        format!("you said {}", command)
+    }
+}
+
+#[tokio::test]
+async fn answers_authenticated_commands() {
+    let server = Server::bind("127.0.0.1:0", "password".to_string(), Echo)
+        .await
+        .unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    let mut connection = Connection::connect(addr, "password").await.unwrap();
+    let response = connection.execute_command("hello").await.unwrap();
+
+    assert_eq!(response, vec!["you said hello".to_string()]);
+}
+
+#[tokio::test]
+async fn rejects_wrong_password() {
+    let server = Server::bind("127.0.0.1:0", "password".to_string(), Echo)
+        .await
+        .unwrap();
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    let result = Connection::connect(addr, "wrong").await;
+
+    assert!(matches!(result, Err(Error::Authentication)));
+}
+
+#[tokio::test]
+async fn splits_long_responses() {
+    let server = Server::bind("127.0.0.1:0", "password".to_string(), Echo)
+        .await
+        .unwrap()
+        .max_fragment(4);
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    let mut connection = ConnectionBuilder::default()
+        .io(TcpStream::connect(addr).await.unwrap())
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+    connection.authenticate("password").await.unwrap();
+
+    let response = connection.execute_command("hello").await.unwrap();
+
+    assert_eq!(response.concat(), "you said hello");
+    assert_eq!(response.len(), 4);
+}
+
+#[tokio::test]
+async fn handshake_measures_the_fragment_size() {
+    let server = Server::bind("127.0.0.1:0", "password".to_string(), Echo)
+        .await
+        .unwrap()
+        .max_fragment(4);
+    let addr = server.local_addr().unwrap();
+    tokio::spawn(server.run());
+
+    let mut connection = ConnectionBuilder::default()
+        .io(TcpStream::connect(addr).await.unwrap())
+        .handshake_probe("a long command")
+        .build()
+        .unwrap();
+
+    let capabilities = connection.handshake("password").await.unwrap();
+
+    assert!(capabilities.multi_response);
+    assert_eq!(capabilities.max_fragment, 4);
+}
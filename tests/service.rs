@@ -0,0 +1,46 @@
+#![cfg(feature = "tower")]
+
+mod common;
+
+use common::{read_packet, write_packet};
+use futures::future::poll_fn;
+use specul::{service::RconService, ConnectionBuilder, Error, State};
+use tokio::io::duplex;
+use tower_service::Service;
+
+#[tokio::test]
+async fn serves_commands_until_the_connection_is_gone() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, command) = read_packet(&mut server).await;
+        assert_eq!(command, "status");
+        write_packet(&mut server, id, "hostname: tower").await;
+        // Dropping the server closes the connection.
+    });
+
+    let client = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .client()
+        .unwrap();
+    let monitor = client.monitor();
+    let mut service = RconService::new(client);
+
+    poll_fn(|cx| service.poll_ready(cx)).await.unwrap();
+    let response = service.call("status".to_string()).await.unwrap();
+    assert_eq!(response, "hostname: tower");
+    assert_eq!(response.packets(), 1);
+
+    server.await.unwrap();
+    let mut events = monitor.events();
+    while monitor.state() != State::Closed {
+        events.recv().await.unwrap();
+    }
+
+    assert!(matches!(
+        poll_fn(|cx| service.poll_ready(cx)).await,
+        Err(Error::ConnectionClosed)
+    ));
+}
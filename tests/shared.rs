@@ -0,0 +1,44 @@
+mod common;
+
+use std::sync::Arc;
+
+use common::echo;
+use specul::ConnectionBuilder;
+use tokio::io::duplex;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_callers_each_get_their_own_response() {
+    let (client, server) = duplex(16 * 1024);
+    let server = echo(server, 16);
+
+    let connection = Arc::new(
+        ConnectionBuilder::default()
+            .io(client)
+            .build()
+            .unwrap()
+            .shared(),
+    );
+
+    let callers: Vec<_> = (0..16)
+        .map(|caller| {
+            let connection = connection.clone();
+            tokio::spawn(async move {
+                let command = format!("say {}", caller);
+                let response = connection.execute_command(&command).await.unwrap();
+                (command, response)
+            })
+        })
+        .collect();
+
+    for caller in callers {
+        let (command, response) = caller.await.unwrap();
+        assert_eq!(response, [command]);
+    }
+
+    let mut sent = server.await.unwrap();
+    sent.sort();
+    let mut expected: Vec<_> = (0..16).map(|caller| format!("say {}", caller)).collect();
+    expected.sort();
+    assert_eq!(sent, expected);
+    assert_eq!(connection.monitor().stats().commands, 16);
+}
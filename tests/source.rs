@@ -0,0 +1,106 @@
+#![cfg(feature = "source")]
+
+use specul::{
+    source::{GetStatus, Status},
+    Command, Error,
+};
+
+const CSGO: &str = "hostname: Valve CS:GO Server
+version : 1.38.2.2/13822 1340/8012 secure  [G:1:1234567]
+udp/ip  : 0.0.0.0:27015  (public ip: 203.0.113.1)
+os      :  Linux
+type    :  community dedicated
+map     : de_dust2
+players : 2 humans, 1 bots (20/0 max) (not hibernating)
+
+# userid name uniqueid connected ping loss state rate adr
+# 2 1 \"Gordon\" STEAM_1:0:11101 00:35 50 0 active 196608 198.51.100.7:27005
+# 3 2 \"Alyx \"the\" Vance\" STEAM_1:1:22202 1:02:10 40 1 active 196608 198.51.100.8:27005
+#  4 \"BOT\" BOT active 64
+#end
+";
+
+const CS2: &str = "hostname  : CS2 Server
+version   : 1.39.8.9/13989 9839 secure  public
+steamid   : [G:1:2345678] (85568392925000000)
+udp/ip    : 0.0.0.0:27015 (public 203.0.113.1:27015)
+os/type   : Linux dedicated
+players   : 1 humans, 1 bots (0 max) (not hibernating) (unreserved)
+loaded spawngroup(  1)  : SV:  [1: de_mirage | main lump | mapload]
+
+---------players--------
+  id     time ping loss      state   rate adr name
+65535 [NoChan]    0    0 challenging      0unknown ''
+    1      BOT    0    0     active      0 'Bot Alfred'
+    2    01:23   50    0      active 786432 198.51.100.7:27005 'Gordon'
+#end
+";
+
+const GMOD: &str = "hostname: Garry's Mod
+version : 2023.06.28/24 9096 secure
+udp/ip  : 203.0.113.1:27015  (public ip: 203.0.113.1)
+map     : gm_construct at: 0 x, 0 y, 0 z
+players : 1 (32 max)
+
+# userid name                uniqueid            connected ping loss state  adr
+#      2 \"Gordon\"            STEAM_0:1:1234      00:35       50    0 active 198.51.100.7:27005
+#      3 \"Broken\"            not-an-id           00:10       40    0 active 198.51.100.8:27005
+";
+
+#[test]
+fn parses_csgo_status() {
+    let status = Status::parse(CSGO).unwrap();
+
+    assert_eq!(status.hostname, "Valve CS:GO Server");
+    assert!(status.version.starts_with("1.38.2.2/13822"));
+    assert_eq!(status.map, "de_dust2");
+    assert_eq!((status.players, status.bots), (2, 1));
+    assert_eq!(status.max_players, Some(20));
+
+    let names: Vec<&str> = status.player_list.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Gordon", "Alyx \"the\" Vance"]);
+
+    let alyx = &status.player_list[1];
+    assert_eq!(alyx.userid, 3);
+    assert_eq!(alyx.steam_id.unwrap().account_id(), 44405);
+    assert_eq!(alyx.connected, "1:02:10");
+    assert_eq!((alyx.ping, alyx.loss), (40, 1));
+    assert_eq!(alyx.address, Some("198.51.100.8:27005".parse().unwrap()));
+}
+
+#[test]
+fn parses_cs2_status() {
+    let status = Status::parse(CS2).unwrap();
+
+    assert_eq!(status.hostname, "CS2 Server");
+    assert_eq!(status.map, "de_mirage");
+    assert_eq!((status.players, status.bots), (1, 1));
+
+    assert_eq!(status.player_list.len(), 1);
+    let gordon = &status.player_list[0];
+    assert_eq!((gordon.userid, gordon.name.as_str()), (2, "Gordon"));
+    assert_eq!(gordon.steam_id, None);
+    assert_eq!(gordon.ping, 50);
+    assert_eq!(gordon.address, Some("198.51.100.7:27005".parse().unwrap()));
+    assert!(status.malformed.is_empty());
+}
+
+#[test]
+fn parses_gmod_status() {
+    let status = GetStatus::parse_response(GMOD).unwrap();
+
+    assert_eq!(GetStatus.to_command_string(), "status");
+    assert_eq!(status.map, "gm_construct");
+    assert_eq!((status.players, status.bots), (1, 0));
+    assert_eq!(status.max_players, Some(32));
+    assert_eq!(status.player_list.len(), 1);
+    assert_eq!(status.malformed.len(), 1);
+}
+
+#[test]
+fn rejects_responses_that_are_not_status() {
+    assert!(matches!(
+        Status::parse("Unknown command \"status\""),
+        Err(Error::UnexpectedResponse(_))
+    ));
+}
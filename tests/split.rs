@@ -0,0 +1,52 @@
+mod common;
+
+use common::{read_id, write_typed};
+use specul::ConnectionBuilder;
+use tokio::io::duplex;
+
+const RESPONSE_VALUE: i32 = 0;
+
+#[tokio::test]
+async fn sends_and_receives_from_separate_tasks() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..3 {
+            let id = read_id(&mut server).await;
+            write_typed(&mut server, id, RESPONSE_VALUE, &format!("answer {}", id)).await;
+        }
+        server
+    });
+
+    let connection = ConnectionBuilder::default()
+        .io(client)
+        .current_packet_id(5)
+        .keepalive_id(6)
+        .build()
+        .unwrap();
+    let (mut sender, mut receiver) = connection.split();
+
+    let reader = tokio::spawn(async move {
+        let mut responses = Vec::new();
+        for _ in 0..3 {
+            let packet = receiver.receive_packet().await.unwrap();
+            responses.push((packet.id, packet.to_str().unwrap().to_string()));
+        }
+        responses
+    });
+
+    let mut ids = Vec::new();
+    for command in ["a", "b", "c"] {
+        ids.push(sender.send_command(command).await.unwrap());
+    }
+
+    assert_eq!(ids, vec![5, 7, 8]);
+    assert_eq!(
+        reader.await.unwrap(),
+        ids.iter()
+            .map(|id| (*id, format!("answer {}", id)))
+            .collect::<Vec<_>>()
+    );
+
+    let _server = server.await.unwrap();
+}
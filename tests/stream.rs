@@ -0,0 +1,101 @@
+#![cfg(feature = "stream")]
+
+mod common;
+
+use common::{read_packet, write_packet};
+use futures::{SinkExt, StreamExt};
+use specul::{ConnectionBuilder, Packet, PacketType};
+use tokio::io::duplex;
+
+#[tokio::test]
+async fn sends_and_receives_packets() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let (id, command) = read_packet(&mut server).await;
+            write_packet(&mut server, id, &format!("ran {}", command)).await;
+        }
+    });
+
+    let mut stream = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .into_stream();
+
+    stream
+        .feed(Packet::new(1, PacketType::Message, "a"))
+        .await
+        .unwrap();
+    stream
+        .feed(Packet::new(2, PacketType::Message, "b"))
+        .await
+        .unwrap();
+    stream.flush().await.unwrap();
+
+    let received: Vec<(i32, String)> = stream
+        .map(|packet| {
+            let packet = packet.unwrap();
+            (packet.id, packet.to_str().unwrap().to_string())
+        })
+        .collect()
+        .await;
+
+    assert_eq!(
+        received,
+        [(1, "ran a".to_string()), (2, "ran b".to_string())]
+    );
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn flushes_large_batches() {
+    let (client, mut server) = duplex(1024);
+    let payload = "x".repeat(1000);
+
+    let reader = tokio::spawn({
+        let payload = payload.clone();
+        async move {
+            for id in 0..20 {
+                assert_eq!(read_packet(&mut server).await, (id, payload.clone()));
+            }
+        }
+    });
+
+    let mut stream = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .into_stream();
+    let monitor = stream.get_ref().monitor();
+
+    let packets = (0..20).map(|id| Ok(Packet::new(id, PacketType::Message, payload.clone())));
+    stream
+        .send_all(&mut futures::stream::iter(packets))
+        .await
+        .unwrap();
+
+    reader.await.unwrap();
+    assert_eq!(monitor.stats().packets_sent, 20);
+}
+
+#[tokio::test]
+async fn buffered_packets_survive_into_inner() {
+    let (client, mut server) = duplex(4096);
+
+    write_packet(&mut server, 1, "one").await;
+    write_packet(&mut server, 2, "two").await;
+
+    let mut stream = ConnectionBuilder::default()
+        .io(client)
+        .build()
+        .unwrap()
+        .into_stream();
+
+    let first = stream.next().await.unwrap().unwrap();
+    let mut connection = stream.into_inner();
+    let second = connection.receive_packet().await.unwrap();
+
+    assert_eq!((first.id, second.id), (1, 2));
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use specul::{CommandTemplate, Error, Quirks};
+
+#[test]
+fn templates_substitute_named_placeholders() {
+    let template = CommandTemplate::new("sm_slay {target}; say {{{target}}} was slain").unwrap();
+
+    assert_eq!(
+        template.placeholders().collect::<Vec<_>>(),
+        ["target", "target"]
+    );
+    assert_eq!(
+        template
+            .render([("target", "Gordon"), ("unused", "x")])
+            .unwrap(),
+        "sm_slay Gordon; say {Gordon} was slain"
+    );
+    assert_eq!(
+        template.to_string(),
+        "sm_slay {target}; say {{{target}}} was slain"
+    );
+
+    let values: HashMap<&str, String> = [("target", "Alyx".to_string())].into_iter().collect();
+    assert_eq!(
+        template.render(&values).unwrap(),
+        "sm_slay Alyx; say {Alyx} was slain"
+    );
+}
+
+#[test]
+fn templates_reject_malformed_placeholders() {
+    for text in ["say {name", "say name}", "say {}", "say {two words}"] {
+        assert!(
+            matches!(CommandTemplate::new(text), Err(Error::Template(_))),
+            "{}",
+            text
+        );
+    }
+}
+
+#[test]
+fn templates_catch_missing_parameters() {
+    let template = CommandTemplate::new("banid {minutes} {steamid}").unwrap();
+
+    match template.render([("steamid", "STEAM_1:0:11101")]) {
+        Err(Error::Template(message)) => assert_eq!(message, "missing parameter minutes"),
+        result => panic!("{:?}", result),
+    }
+}
+
+#[test]
+fn source_values_cannot_break_out_of_the_command() {
+    let template = CommandTemplate::new(r#"kickid {userid} "{reason}""#).unwrap();
+
+    assert_eq!(
+        template
+            .render([("userid", "2"), ("reason", "spam; quit")])
+            .unwrap(),
+        r#"kickid 2 "spam; quit""#
+    );
+    assert_eq!(
+        template
+            .render([("userid", "2; quit"), ("reason", "spam")])
+            .unwrap(),
+        r#"kickid "2; quit" "spam""#
+    );
+
+    for (userid, reason) in [
+        ("2\"; quit", "spam"),
+        ("2", r#"spam"; quit"#),
+        ("2", "spam\nquit"),
+        ("2\0", "spam"),
+    ] {
+        let result = template.render([("userid", userid), ("reason", reason)]);
+        assert!(matches!(result, Err(Error::Template(_))), "{:?}", result);
+    }
+}
+
+#[test]
+fn minecraft_values_are_escaped_in_quotes() {
+    let template = CommandTemplate::new(r#"tellraw {target} {{"text":"{message}"}}"#)
+        .unwrap()
+        .quirks(Quirks::Minecraft);
+
+    assert_eq!(
+        template
+            .render([("target", "Steve"), ("message", r#"a "quoted" \ word"#)])
+            .unwrap(),
+        r#"tellraw Steve {"text":"a \"quoted\" \\ word"}"#
+    );
+    assert!(template
+        .render([("target", "Steve"), ("message", "two\nlines")])
+        .is_err());
+}
+
+#[test]
+fn templates_escape_values_as_escape_argument_does() {
+    for quirks in [Quirks::Source, Quirks::Minecraft, Quirks::Factorio] {
+        let template = CommandTemplate::new("say {message}")
+            .unwrap()
+            .quirks(quirks);
+
+        for value in [
+            "Gordon",
+            "",
+            "Alyx Vance",
+            "a;quit",
+            "x//y",
+            "{}()':",
+            r#"a"b\c"#,
+            "one\ntwo",
+            "bell\x07",
+        ] {
+            let rendered = template.render([("message", value)]);
+
+            match quirks.escape_argument(value) {
+                Ok(escaped) => assert_eq!(rendered.unwrap(), format!("say {}", escaped)),
+                Err(_) => assert!(
+                    matches!(rendered, Err(Error::Template(_))),
+                    "{:?} {:?}: {:?}",
+                    quirks,
+                    value,
+                    rendered
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::{read_packet, write_packet};
+use specul::{
+    text::{strip_ansi, strip_formatting, strip_minecraft, strip_source_colors},
+    ConnectionBuilder,
+};
+use tokio::io::duplex;
+
+#[test]
+fn strips_minecraft_codes() {
+    assert_eq!(
+        strip_minecraft("§6There are §c2§6 of a max of §c20§6 players online"),
+        "There are 2 of a max of 20 players online"
+    );
+    assert_eq!(strip_minecraft("§x§f§f§0§0§0§0Red§r"), "Red");
+    assert_eq!(strip_minecraft("trailing §"), "trailing ");
+}
+
+#[test]
+fn strips_ansi_sequences() {
+    assert_eq!(strip_ansi("\x1b[32m[INFO]\x1b[0m ready"), "[INFO] ready");
+    assert_eq!(strip_ansi("\x1b]0;title\x07text"), "text");
+    assert_eq!(strip_ansi("\x1b]0;title\x1b\\text"), "text");
+    assert_eq!(strip_ansi("\x1b7saved\x1b8"), "saved");
+}
+
+#[test]
+fn strips_source_colors() {
+    assert_eq!(
+        strip_source_colors("\x01\x0bPlayer \x04Gordon\x01 joined\n"),
+        "Player Gordon joined\n"
+    );
+    assert_eq!(
+        strip_source_colors("\x07FF0000red\x08FF000080clear"),
+        "redclear"
+    );
+    assert_eq!(strip_source_colors("\x07not hex"), "not hex");
+    assert_eq!(strip_source_colors("a\tb\r\n"), "a\tb\r\n");
+}
+
+#[test]
+fn strips_everything() {
+    assert_eq!(
+        strip_formatting("§a\x1b[1m\x04Done\x1b[0m§r"),
+        "Done".to_string()
+    );
+}
+
+#[tokio::test]
+async fn strips_responses_when_enabled() {
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let (id, _) = read_packet(&mut server).await;
+            write_packet(&mut server, id, "§eSaved the game").await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .strip_formatting(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        connection.execute_command("save-all").await.unwrap(),
+        vec!["Saved the game"]
+    );
+    assert_eq!(
+        connection.execute("save-all").await.unwrap(),
+        "Saved the game"
+    );
+
+    let _server = server.await.unwrap();
+}
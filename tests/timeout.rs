@@ -0,0 +1,171 @@
+use std::{
+    future::Future,
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use specul::{ConnectionBuilder, Error};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    time::{sleep, Sleep},
+};
+
+/// An io that accepts every write and drips a canned response one byte at a
+/// time.
+struct SlowDrip {
+    data: Vec<u8>,
+    position: usize,
+    interval: Duration,
+    sleep: Pin<Box<Sleep>>,
+}
+
+impl SlowDrip {
+    fn new(payload: &str, interval: Duration) -> Self {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(10 + payload.len() as i32).to_le_bytes());
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.extend_from_slice(&0i32.to_le_bytes());
+        data.extend_from_slice(payload.as_bytes());
+        data.extend_from_slice(&[0, 0]);
+
+        SlowDrip {
+            data,
+            position: 0,
+            interval,
+            sleep: Box::pin(sleep(interval)),
+        }
+    }
+}
+
+impl AsyncRead for SlowDrip {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        if self.position == self.data.len() {
+            return Poll::Pending;
+        }
+
+        if self.sleep.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+
+        let deadline = tokio::time::Instant::now() + self.interval;
+        self.sleep.as_mut().reset(deadline);
+
+        let byte = self.data[self.position];
+        self.position += 1;
+        buf.put_slice(&[byte]);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for SlowDrip {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[tokio::test]
+async fn read_timeout_bounds_inactivity_not_total_time() {
+    let io = SlowDrip::new("hello world", Duration::from_millis(10));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(io)
+        .read_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    // 23 bytes at 10ms each takes well over the read timeout in total.
+    let response = connection.execute_command("status").await.unwrap();
+    assert_eq!(response, vec!["hello world".to_string()]);
+}
+
+#[tokio::test]
+async fn read_timeout_fires_when_server_stalls() {
+    let io = SlowDrip::new("hello world", Duration::from_secs(10));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(io)
+        .read_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let result = connection.execute_command("status").await;
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[tokio::test]
+async fn command_deadline_bounds_slow_drip() {
+    let io = SlowDrip::new("hello world", Duration::from_millis(10));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(io)
+        .read_timeout(Duration::from_millis(100))
+        .command_deadline(Duration::from_millis(100))
+        .build()
+        .unwrap();
+
+    let result = connection.execute_command("status").await;
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[tokio::test]
+async fn timeout_bounds_authentication() {
+    let io = SlowDrip::new("", Duration::from_secs(10));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(io)
+        .timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let result = connection.authenticate("password").await;
+    assert!(matches!(result, Err(Error::Timeout)));
+}
+
+#[tokio::test]
+async fn receive_packet_timeout_fires_and_keeps_the_partial_packet() {
+    let io = SlowDrip::new("hello world", Duration::from_millis(10));
+
+    let mut connection = ConnectionBuilder::default().io(io).build().unwrap();
+
+    let result = connection
+        .receive_packet_timeout(Duration::from_millis(50))
+        .await;
+    assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+
+    let packet = connection
+        .receive_packet_timeout(Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(packet.to_str().unwrap(), "hello world");
+}
+
+#[tokio::test]
+async fn execute_command_timeout_fires_when_server_stalls() {
+    let io = SlowDrip::new("hello world", Duration::from_secs(10));
+
+    let mut connection = ConnectionBuilder::default().io(io).build().unwrap();
+
+    let result = connection
+        .execute_command_timeout("status", Duration::from_millis(50))
+        .await;
+    assert!(matches!(result, Err(Error::Timeout)), "{:?}", result);
+}
@@ -0,0 +1,165 @@
+#![cfg(feature = "tls")]
+
+use std::sync::Arc;
+
+use rcgen::CertifiedKey;
+use specul::{
+    tls::{
+        pinned_config,
+        rustls::{
+            crypto::ring::default_provider,
+            pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+            ServerConfig,
+        },
+        CertificatePin, TlsOptionsBuilder,
+    },
+    Connection, Error, State,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+};
+use tokio_rustls::TlsAcceptor;
+
+const AUTH_RESPONSE: i32 = 2;
+
+fn sha256(bytes: &[u8]) -> [u8; 32] {
+    ring::digest::digest(&ring::digest::SHA256, bytes)
+        .as_ref()
+        .try_into()
+        .unwrap()
+}
+
+/// Answers an authentication packet as an RCON server would.
+async fn accept_rcon_auth(stream: &mut (impl AsyncRead + AsyncWrite + Unpin)) {
+    let length = stream.read_i32_le().await.unwrap();
+    let id = stream.read_i32_le().await.unwrap();
+    let mut rest = vec![0; length as usize - 4];
+    stream.read_exact(&mut rest).await.unwrap();
+
+    stream.write_i32_le(10).await.unwrap();
+    stream.write_i32_le(id).await.unwrap();
+    stream.write_i32_le(AUTH_RESPONSE).await.unwrap();
+    stream.write_all(&[0, 0]).await.unwrap();
+    stream.flush().await.unwrap();
+}
+
+/// Starts an RCON server behind TLS with a fresh self-signed certificate,
+/// returning its address and certificate.
+async fn tls_server() -> (String, CertifiedKey) {
+    let certified = rcgen::generate_simple_self_signed(["localhost".to_string()]).unwrap();
+    let key = PrivateKeyDer::from(PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der()));
+    let config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_no_client_auth()
+        .with_single_cert(vec![certified.cert.der().clone()], key)
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    tokio::spawn(async move {
+        while let Ok((tcp, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+
+            tokio::spawn(async move {
+                if let Ok(mut stream) = acceptor.accept(tcp).await {
+                    accept_rcon_auth(&mut stream).await;
+                    // Held open until the client hangs up.
+                    let _ = stream.read_u8().await;
+                }
+            });
+        }
+    });
+
+    (addr, certified)
+}
+
+#[tokio::test]
+async fn connects_to_a_server_matching_a_pin() {
+    let (addr, certified) = tls_server().await;
+    let der: &CertificateDer<'static> = certified.cert.der();
+
+    for pin in [
+        CertificatePin::Certificate(der.clone()),
+        CertificatePin::Sha256(sha256(der)),
+        CertificatePin::Spki(sha256(&certified.key_pair.public_key_der())),
+    ] {
+        let config = Arc::new(pinned_config([pin.clone()]));
+        let connection = Connection::connect_tls(&addr, "localhost", "password", config)
+            .await
+            .unwrap_or_else(|error| panic!("{:?}: {}", pin, error));
+
+        assert_eq!(connection.state(), State::Authenticated);
+        assert!(connection.peer_addr().is_some());
+    }
+}
+
+#[tokio::test]
+async fn refuses_a_server_matching_no_pin() {
+    let (addr, certified) = tls_server().await;
+    let other = rcgen::generate_simple_self_signed(["localhost".to_string()]).unwrap();
+
+    for pin in [
+        CertificatePin::Sha256(sha256(other.cert.der())),
+        CertificatePin::Spki(sha256(&other.key_pair.public_key_der())),
+        // The certificate's digest is not its key's.
+        CertificatePin::Spki(sha256(certified.cert.der())),
+    ] {
+        let config = Arc::new(pinned_config([pin.clone()]));
+        let result = Connection::connect_tls(&addr, "localhost", "password", config).await;
+
+        assert!(
+            matches!(result, Err(Error::Io(_))),
+            "{:?}: {:?}",
+            pin,
+            result
+        );
+    }
+}
+
+#[test]
+fn trusts_the_webpki_roots_by_default() {
+    let options = TlsOptionsBuilder::default().build().unwrap();
+    let config = options.client_config().unwrap();
+
+    assert!(config.enable_sni);
+}
+
+#[test]
+fn can_disable_sni() {
+    let options = TlsOptionsBuilder::default().sni(false).build().unwrap();
+    let config = options.client_config().unwrap();
+
+    assert!(!config.enable_sni);
+}
+
+#[test]
+fn accepts_pins_without_roots() {
+    let options = TlsOptionsBuilder::default()
+        .webpki_roots(false)
+        .pin(CertificatePin::Sha256([0; 32]))
+        .build()
+        .unwrap();
+
+    assert!(options.client_config().is_ok());
+}
+
+#[test]
+fn requires_something_to_trust() {
+    let result = TlsOptionsBuilder::default().webpki_roots(false).build();
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn rejects_unparseable_roots() {
+    let options = TlsOptionsBuilder::default()
+        .root(b"not a certificate".to_vec().into())
+        .build()
+        .unwrap();
+
+    assert!(options.client_config().is_err());
+}
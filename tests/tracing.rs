@@ -0,0 +1,160 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use common::{read_packet, write_typed};
+use specul::{ConnectionBuilder, ExecOptions};
+use tokio::io::duplex;
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+/// Records every span and event as a line of `name field=value ...`.
+#[derive(Clone, Default)]
+struct Recorder {
+    lines: Arc<Mutex<Vec<String>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+struct Line(String);
+
+impl Visit for Line {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0 += &format!(" {}={:?}", field.name(), value);
+    }
+}
+
+impl Subscriber for Recorder {
+    fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, attributes: &span::Attributes<'_>) -> span::Id {
+        let mut line = Line(format!("span {}", attributes.metadata().name()));
+        attributes.record(&mut line);
+        self.lines.lock().unwrap().push(line.0);
+
+        span::Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+
+    fn record(&self, _span: &span::Id, values: &span::Record<'_>) {
+        let mut line = Line("record".to_string());
+        values.record(&mut line);
+        self.lines.lock().unwrap().push(line.0);
+    }
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut line = Line("event".to_string());
+        event.record(&mut line);
+        self.lines.lock().unwrap().push(line.0);
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
+
+#[tokio::test]
+async fn traces_commands_and_packets_without_the_password() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        write_typed(&mut server, id, 2, "").await;
+
+        let (id, _) = read_packet(&mut server).await;
+        write_typed(&mut server, id, 0, "hostname: traced").await;
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    connection.authenticate("hunter2").await.unwrap();
+    connection.execute_command("status").await.unwrap();
+    let _server = server.await.unwrap();
+
+    let lines = recorder.lines.lock().unwrap().clone();
+
+    assert!(
+        lines.iter().all(|line| !line.contains("hunter2")),
+        "{:#?}",
+        lines
+    );
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("span authenticate")));
+    assert!(lines
+        .iter()
+        .any(|line| line.contains("payload=\"<redacted>\"")));
+    assert!(lines
+        .iter()
+        .any(|line| line.starts_with("span command") && line.contains("command=\"status\"")));
+    for field in [
+        "request_id=",
+        "packets=1",
+        "payload_length=16",
+        "latency_ms=",
+    ] {
+        assert!(lines
+            .iter()
+            .any(|line| line.starts_with("record") && line.contains(field)));
+    }
+    assert!(lines.iter().any(|line| {
+        line.starts_with("event")
+            && line.contains("received packet")
+            && line.contains("payload=\"hostname: traced\"")
+    }));
+}
+
+#[tokio::test]
+async fn records_the_correlation_token_on_the_command_span() {
+    let recorder = Recorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let (client, mut server) = duplex(4096);
+
+    let server = tokio::spawn(async move {
+        for _ in 0..2 {
+            let (id, _) = read_packet(&mut server).await;
+            write_typed(&mut server, id, 0, "ok").await;
+        }
+        server
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let options = ExecOptions {
+        correlation: Some("trace-42".to_string()),
+        ..connection.exec_options()
+    };
+    connection
+        .execute_command_with("status", options)
+        .await
+        .unwrap();
+    connection.execute_command("users").await.unwrap();
+    let _server = server.await.unwrap();
+
+    let lines = recorder.lines.lock().unwrap().clone();
+    let spans: Vec<_> = lines
+        .iter()
+        .filter(|line| line.starts_with("span command"))
+        .collect();
+
+    assert_eq!(spans.len(), 2, "{:#?}", lines);
+    assert!(
+        spans[0].contains("command=\"status\"") && spans[0].contains("correlation=\"trace-42\"")
+    );
+    assert!(!spans[1].contains("correlation="), "{}", spans[1]);
+}
@@ -0,0 +1,59 @@
+use specul::{ConnectionBuilder, ResponseTransform};
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt, DuplexStream};
+
+async fn respond(mut server: DuplexStream, payload: String) -> DuplexStream {
+    let length = server.read_i32_le().await.unwrap();
+    let id = server.read_i32_le().await.unwrap();
+    let mut rest = vec![0; length as usize - 4];
+    server.read_exact(&mut rest).await.unwrap();
+
+    server
+        .write_i32_le(10 + payload.len() as i32)
+        .await
+        .unwrap();
+    server.write_i32_le(id).await.unwrap();
+    server.write_i32_le(0).await.unwrap();
+    server.write_all(payload.as_bytes()).await.unwrap();
+    server.write_all(&[0, 0]).await.unwrap();
+    server
+}
+
+#[tokio::test]
+async fn applies_custom_transform() {
+    let (client, server) = duplex(4096);
+    let server = tokio::spawn(respond(server, "hello".to_string()));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .response_transform(ResponseTransform::new(|bytes| {
+            Ok(bytes.to_ascii_uppercase())
+        }))
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("status").await.unwrap();
+    assert_eq!(response, vec!["HELLO".to_string()]);
+
+    server.await.unwrap();
+}
+
+#[cfg(feature = "base64")]
+#[tokio::test]
+async fn round_trips_base64_response() {
+    use base64::Engine;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode("players: 3\nmap: de_dust2");
+    let (client, server) = duplex(4096);
+    let server = tokio::spawn(respond(server, encoded));
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .response_transform(ResponseTransform::base64())
+        .build()
+        .unwrap();
+
+    let response = connection.execute_command("status").await.unwrap();
+    assert_eq!(response, vec!["players: 3\nmap: de_dust2".to_string()]);
+
+    server.await.unwrap();
+}
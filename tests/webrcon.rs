@@ -0,0 +1,118 @@
+#![cfg(feature = "webrcon")]
+
+use futures::{SinkExt, StreamExt};
+use specul::{
+    webrcon::{MessageType, WebRconConnection},
+    Error,
+};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::{
+    handshake::server::{ErrorResponse, Request, Response},
+    http::StatusCode,
+    Message,
+};
+
+/// Serves one WebRcon client, answering every command after a line of chat.
+// The handshake callback's error type is set by tungstenite.
+#[allow(clippy::result_large_err)]
+async fn serve(listener: TcpListener) {
+    let (stream, _) = listener.accept().await.unwrap();
+
+    let check_password = |request: &Request, response: Response| {
+        if request.uri().path() == "/password" {
+            Ok(response)
+        } else {
+            let mut error = ErrorResponse::new(None);
+            *error.status_mut() = StatusCode::UNAUTHORIZED;
+            Err(error)
+        }
+    };
+
+    let Ok(mut socket) = tokio_tungstenite::accept_hdr_async(stream, check_password).await else {
+        return;
+    };
+
+    while let Some(Ok(Message::Text(request))) = socket.next().await {
+        let request: serde_json::Value = serde_json::from_str(&request).unwrap();
+        assert_eq!(request["Name"], "WebRcon");
+
+        socket
+            .send(Message::text(
+                r#"{"Message":"[CHAT] alice: hi","Identifier":0,"Type":"Chat","Stacktrace":""}"#,
+            ))
+            .await
+            .unwrap();
+
+        let response = serde_json::json!({
+            "Message": format!("ran {}", request["Message"].as_str().unwrap()),
+            "Identifier": request["Identifier"],
+            "Type": "Generic",
+            "Stacktrace": "",
+        });
+        socket
+            .send(Message::text(response.to_string()))
+            .await
+            .unwrap();
+    }
+}
+
+#[tokio::test]
+async fn executes_commands_and_streams_console_output() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(serve(listener));
+
+    let connection = WebRconConnection::connect(&addr, "password").await.unwrap();
+    let mut console = connection.console();
+
+    let (status, players) = tokio::join!(
+        connection.execute_command("status"),
+        connection.execute_command("players")
+    );
+
+    assert_eq!(status.unwrap(), vec!["ran status".to_string()]);
+    assert_eq!(players.unwrap(), vec!["ran players".to_string()]);
+
+    let output = console.recv().await.unwrap();
+    assert_eq!(output.message, "[CHAT] alice: hi");
+    assert_eq!(output.message_type, MessageType::Chat);
+}
+
+#[tokio::test]
+async fn rejects_a_wrong_password() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    tokio::spawn(serve(listener));
+
+    let result = WebRconConnection::connect(&addr, "wrong").await;
+
+    assert!(matches!(result, Err(Error::Authentication)));
+}
+
+// The handshake callback's error type is set by tungstenite.
+#[allow(clippy::result_large_err)]
+#[tokio::test]
+async fn percent_encodes_the_password() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut path = String::new();
+
+        let record = |request: &Request, response: Response| {
+            path = request.uri().path().to_string();
+            Ok(response)
+        };
+        let _socket = tokio_tungstenite::accept_hdr_async(stream, record)
+            .await
+            .unwrap();
+        path
+    });
+
+    WebRconConnection::connect(&addr, "a/b?c#d%e f")
+        .await
+        .unwrap();
+
+    assert_eq!(server.await.unwrap(), "/a%2Fb%3Fc%23d%25e%20f");
+}
@@ -0,0 +1,117 @@
+mod common;
+
+use std::{
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use common::{read_packet, write_packet};
+use specul::ConnectionBuilder;
+use tokio::io::{duplex, AsyncRead, AsyncWrite, DuplexStream, ReadBuf};
+
+/// A stream counting the writes made to it.
+struct Counting {
+    io: DuplexStream,
+    writes: Arc<AtomicUsize>,
+}
+
+impl AsyncRead for Counting {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for Counting {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.writes.fetch_add(1, Ordering::Relaxed);
+        Pin::new(&mut self.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.io).poll_shutdown(cx)
+    }
+}
+
+fn counting() -> (Counting, DuplexStream, Arc<AtomicUsize>) {
+    let (client, server) = duplex(64 * 1024);
+    let writes = Arc::new(AtomicUsize::new(0));
+    let client = Counting {
+        io: client,
+        writes: writes.clone(),
+    };
+
+    (client, server, writes)
+}
+
+#[tokio::test]
+async fn writes_a_packet_at_once() {
+    let (client, mut server, writes) = counting();
+
+    tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        write_packet(&mut server, id, "ok").await;
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    connection.execute_command("status").await.unwrap();
+
+    assert_eq!(writes.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn writes_a_command_and_its_sentinel_together() {
+    let (client, mut server, writes) = counting();
+
+    tokio::spawn(async move {
+        let (id, _) = read_packet(&mut server).await;
+        let (sentinel, _) = read_packet(&mut server).await;
+        write_packet(&mut server, id, "ok").await;
+        write_packet(&mut server, sentinel, "").await;
+    });
+
+    let mut connection = ConnectionBuilder::default()
+        .io(client)
+        .multiple_responses(true)
+        .build()
+        .unwrap();
+    connection.execute_command("status").await.unwrap();
+
+    assert_eq!(writes.load(Ordering::Relaxed), 1);
+}
+
+#[tokio::test]
+async fn pipelines_a_batch_in_one_write() {
+    let (client, mut server, writes) = counting();
+
+    tokio::spawn(async move {
+        for _ in 0..100 {
+            let (id, command) = read_packet(&mut server).await;
+            write_packet(&mut server, id, &command).await;
+        }
+    });
+
+    let mut connection = ConnectionBuilder::default().io(client).build().unwrap();
+    let commands: Vec<String> = (0..100).map(|i| format!("echo {}", i)).collect();
+
+    let responses = connection.execute_commands(&commands).await.unwrap();
+
+    assert_eq!(responses[99], vec!["echo 99"]);
+    assert_eq!(writes.load(Ordering::Relaxed), 1);
+}